@@ -69,33 +69,66 @@ impl Plugin for LensFlarePlugin {
     }
 }
 
+/// Light source drawing a lens flare, whose strength follows its illuminance at the camera.
 #[derive(Default, Component, Reflect)]
 #[reflect(Component)]
 #[require(Transform)]
-pub struct LensFlareTarget;
+pub struct LensFlareTarget {
+    /// Radiated power, in watts, or in any unit shared by all targets.
+    pub luminosity: f64,
+}
+
+impl LensFlareTarget {
+    /// Illuminance at the given distance, up to a constant factor shared by all targets.
+    fn illuminance_at(&self, distance: f32) -> f64 {
+        self.luminosity / (distance.max(1.0) as f64).powi(2)
+    }
+}
+
+/// Maximum number of targets drawing a lens flare at once. The brightest ones at the camera are
+/// kept.
+pub const MAX_LENS_FLARE_TARGETS: usize = 4;
 
+/// World-space positions of the targets of a camera, with their illuminance relative to the
+/// brightest one.
 #[derive(Default, Component)]
 #[require(GlobalTransform)]
 #[doc(hidden)]
-pub struct LensFlareTargetRef(Vec3);
+pub struct LensFlareTargetRef(Vec<Vec4>);
 
 fn update_lens_flare_target(
-    mut q: Query<&mut LensFlareTargetRef>,
-    q_target: Query<&GlobalTransform, With<LensFlareTarget>>,
+    mut q: Query<(&GlobalTransform, &mut LensFlareTargetRef)>,
+    q_target: Query<(&GlobalTransform, &LensFlareTarget)>,
 ) {
-    let target_transform = match q_target.get_single() {
-        Ok(t) => t,
-        Err(err) => {
-            warn!("Cannot get lens flare target: {err}");
-            return;
-        }
-    };
-    for mut target in &mut q {
-        target.0 = target_transform.translation();
-        debug!("Set lens flare target {}", target.0);
+    for (transform, mut targets) in &mut q {
+        let camera = transform.translation();
+        targets.0.clear();
+        targets.0.extend(brightest_targets(q_target.iter().map(
+            |(transform, target)| {
+                let position = transform.translation();
+                (position, target.illuminance_at(position.distance(camera)))
+            },
+        )));
+        debug!("Set lens flare targets {:?}", targets.0);
     }
 }
 
+/// Keeps the [`MAX_LENS_FLARE_TARGETS`] brightest targets, given as their position and illuminance,
+/// with their illuminance relative to the brightest one in `w`.
+fn brightest_targets(targets: impl IntoIterator<Item = (Vec3, f64)>) -> Vec<Vec4> {
+    let mut targets = targets
+        .into_iter()
+        .filter(|(_, illuminance)| *illuminance > 0.0)
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| b.1.total_cmp(&a.1));
+    targets.truncate(MAX_LENS_FLARE_TARGETS);
+    let brightest = targets.first().map_or(1.0, |(_, illuminance)| *illuminance);
+    targets
+        .into_iter()
+        .map(|(position, illuminance)| position.extend((illuminance / brightest) as f32))
+        .collect()
+}
+
 #[derive(Debug, Copy, Clone, Component, Reflect)]
 #[reflect(Component)]
 #[require(LensFlareTargetRef, DepthPrepass)]
@@ -128,14 +161,26 @@ impl ExtractComponent for LensFlare {
     type Out = LensFlareSettingsExtracted;
 
     fn extract_component(
-        (this, transform, camera, &LensFlareTargetRef(pos)): QueryItem<'_, Self::QueryData>,
+        (this, transform, camera, LensFlareTargetRef(targets)): QueryItem<'_, Self::QueryData>,
     ) -> Option<Self::Out> {
-        let position = camera.world_to_ndc(transform, pos)?;
+        let mut positions = [Vec4::ZERO; MAX_LENS_FLARE_TARGETS];
+        let mut target_count = 0;
+        for target in targets {
+            let Some(position) = camera.world_to_ndc(transform, target.xyz()) else {
+                continue;
+            };
+            positions[target_count as usize] = position.extend(target.w);
+            target_count += 1;
+        }
+        if target_count == 0 {
+            return None;
+        }
         let aspect =
             camera.physical_viewport_size()?.x as f32 / camera.physical_viewport_size()?.y as f32;
-        debug!("lens flare position: {position}");
+        debug!("lens flare positions: {positions:?}");
         Some(LensFlareSettingsExtracted {
-            position,
+            positions,
+            target_count,
             intensity: this.intensity,
             aspect,
             distortion_barrel: this.distortion_barrel,
@@ -147,7 +192,10 @@ impl ExtractComponent for LensFlare {
 
 #[derive(Debug, Copy, Clone, Component, ShaderType)]
 pub struct LensFlareSettingsExtracted {
-    position: Vec3,
+    /// Normalized device coordinates of the targets, and their illuminance relative to the
+    /// brightest one in `w`. Only the first `target_count` are set.
+    positions: [Vec4; MAX_LENS_FLARE_TARGETS],
+    target_count: u32,
     intensity: f32,
     aspect: f32,
     distortion_barrel: f32,
@@ -334,3 +382,19 @@ impl render_graph::ViewNode for LensFlareNode {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brightest_targets_are_kept_and_weighted() {
+        let dim = (0..MAX_LENS_FLARE_TARGETS).map(|i| (Vec3::X * i as f32, 1.0));
+        let targets = brightest_targets(dim.chain([(Vec3::Y, 4.0)]));
+        assert_eq!(targets.len(), MAX_LENS_FLARE_TARGETS);
+        assert_eq!(targets[0], Vec4::new(0.0, 1.0, 0.0, 1.0));
+        assert_eq!(targets[1].w, 0.25);
+
+        assert!(brightest_targets([(Vec3::ZERO, 0.0)]).is_empty());
+    }
+}
@@ -1,7 +1,10 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 
+const MAX_TARGETS: u32 = 4u;
+
 struct Settings {
-    position: vec3<f32>,
+    positions: array<vec4<f32>, MAX_TARGETS>,
+    target_count: u32,
     intensity: f32,
     aspect: f32,
     distortion_barrel: f32,
@@ -109,18 +112,24 @@ fn lensflare(lens_flare: LensFlare, size: f32, color: vec3<f32>) -> vec3<f32> {
 
 @fragment
 fn lens_flare(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
-    var pos = vec2<f32>(-1.0, 1.0) * settings.position.xy;
-    pos.x *= settings.aspect;
-
-    let pos_uv = vec2<f32>(0.5, -0.5) * settings.position.xy + 0.5;
-    let input_color = textureSample(screen_texture, screen_sampler, pos_uv).rgb;
-
     var uv = in.uv - 0.5;
     uv.x *= settings.aspect;
 
-    let lens_flare_instance = LensFlare(pos, uv);
+    // Each target flares with its own color and strength, the flares adding up
+    var final_color = vec3<f32>(0.0);
+    for (var i: u32 = 0u; i < min(settings.target_count, MAX_TARGETS); i = i + 1u) {
+        let position = settings.positions[i].xy;
+        var pos = vec2<f32>(-1.0, 1.0) * position;
+        pos.x *= settings.aspect;
+
+        let pos_uv = vec2<f32>(0.5, -0.5) * position + 0.5;
+        let input_color = textureSampleLevel(screen_texture, screen_sampler, pos_uv, 0.0).rgb;
+
+        let lens_flare_instance = LensFlare(pos, uv);
+        let color = lensflare(lens_flare_instance, 1.0, input_color * settings.intensity);
+        // Scaled by the illuminance relative to the brightest target
+        final_color += pow(color, vec3<f32>(settings.gamma)) * settings.positions[i].w;
+    }
 
-    let final_color = lensflare(lens_flare_instance, 1.0, input_color * settings.intensity);
-    return textureSample(screen_texture, screen_sampler, in.uv)
-        + vec4(pow(final_color, vec3<f32>(settings.gamma)), 1.0);
+    return textureSample(screen_texture, screen_sampler, in.uv) + vec4(final_color, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,291 @@
+use crate::sun::{self, SunIlluminance, SunPosition};
+use bevy::asset::embedded_asset;
+use bevy::image::ImageSampler;
+use bevy::math::DVec3;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, Face, RenderPipelineDescriptor, ShaderRef, ShaderType,
+    SpecializedMeshPipelineError, TextureDimension, TextureFormat,
+};
+
+pub struct AtmospherePlugin;
+
+impl Plugin for AtmospherePlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/atmosphere.wgsl");
+        app.add_plugins(MaterialPlugin::<AtmosphereMaterial>::default())
+            .register_type::<Atmosphere>()
+            .add_systems(Update, update_atmospheres.after(sun::sun_lighting));
+    }
+}
+
+/// Size of the transmittance lookup table, along the view zenith angle and the altitude.
+pub const TRANSMITTANCE_LUT_SIZE: UVec2 = UVec2::new(256, 64);
+const TRANSMITTANCE_SAMPLE_COUNT: usize = 64;
+
+/// Physical description of an atmosphere, with distances in meters and coefficients per meter at
+/// ground level. It is put on a sphere mesh scaled to the top of the atmosphere, and centered on
+/// the body.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct Atmosphere {
+    pub bottom_radius: f32,
+    pub top_radius: f32,
+    pub rayleigh_scattering: Vec3,
+    pub rayleigh_scale_height: f32,
+    pub mie_scattering: f32,
+    pub mie_absorption: f32,
+    pub mie_scale_height: f32,
+    pub mie_asymmetry: f32,
+}
+
+impl Atmosphere {
+    /// Extinction coefficients at the given altitude.
+    pub fn extinction(&self, altitude: f64) -> DVec3 {
+        let rayleigh = self.rayleigh_scattering.as_dvec3()
+            * (-altitude / self.rayleigh_scale_height as f64).exp();
+        let mie = (self.mie_scattering + self.mie_absorption) as f64
+            * (-altitude / self.mie_scale_height as f64).exp();
+        rayleigh + DVec3::splat(mie)
+    }
+
+    /// Transmittance from a point at distance `r` of the center to the top of the atmosphere,
+    /// along a ray with `mu` as the cosine of its zenith angle. Rays hitting the ground are not
+    /// handled.
+    pub fn transmittance(&self, r: f64, mu: f64) -> DVec3 {
+        let length = self.distance_to_top(r, mu);
+        let dt = length / TRANSMITTANCE_SAMPLE_COUNT as f64;
+        let optical_depth = (0..TRANSMITTANCE_SAMPLE_COUNT)
+            .map(|i| {
+                let t = (i as f64 + 0.5) * dt;
+                let altitude =
+                    (t * t + 2.0 * r * mu * t + r * r).sqrt() - self.bottom_radius as f64;
+                self.extinction(altitude) * dt
+            })
+            .sum::<DVec3>();
+        (-optical_depth).exp()
+    }
+
+    /// Precomputes the transmittance to the top of the atmosphere, for every altitude and view
+    /// zenith angle, following the parametrization of Bruneton.
+    pub fn transmittance_lut(&self) -> Image {
+        let data = (0..TRANSMITTANCE_LUT_SIZE.y)
+            .flat_map(|y| (0..TRANSMITTANCE_LUT_SIZE.x).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                let uv = (UVec2::new(x, y).as_dvec2() + 0.5) / TRANSMITTANCE_LUT_SIZE.as_dvec2();
+                let (r, mu) = self.transmittance_lut_r_mu(uv);
+                let transmittance = self.transmittance(r, mu).as_vec3();
+                transmittance.extend(1.0).to_array()
+            })
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let mut image = Image::new(
+            Extent3d {
+                width: TRANSMITTANCE_LUT_SIZE.x,
+                height: TRANSMITTANCE_LUT_SIZE.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba32Float,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        image.sampler = ImageSampler::linear();
+        image
+    }
+
+    fn distance_to_top(&self, r: f64, mu: f64) -> f64 {
+        let top = self.top_radius as f64;
+        let discriminant = r * r * (mu * mu - 1.0) + top * top;
+        (-r * mu + discriminant.max(0.0).sqrt()).max(0.0)
+    }
+
+    /// Inverse of the lookup table parametrization, mirrored in `atmosphere.wgsl`.
+    fn transmittance_lut_r_mu(&self, uv: DVec2) -> (f64, f64) {
+        let (bottom, top) = (self.bottom_radius as f64, self.top_radius as f64);
+        let horizon = (top * top - bottom * bottom).sqrt();
+        let rho = horizon * uv.y;
+        let r = (rho * rho + bottom * bottom).sqrt();
+        let d_min = top - r;
+        let d_max = rho + horizon;
+        let d = d_min + uv.x * (d_max - d_min);
+        let mu = if d == 0.0 {
+            1.0
+        } else {
+            (horizon * horizon - rho * rho - d * d) / (2.0 * r * d)
+        };
+        (r, mu.clamp(-1.0, 1.0))
+    }
+
+    fn uniform(&self) -> AtmosphereUniform {
+        AtmosphereUniform {
+            bottom_radius: self.bottom_radius,
+            top_radius: self.top_radius,
+            rayleigh_scattering: self.rayleigh_scattering,
+            rayleigh_scale_height: self.rayleigh_scale_height,
+            mie_scattering: self.mie_scattering,
+            mie_absorption: self.mie_absorption,
+            mie_scale_height: self.mie_scale_height,
+            mie_asymmetry: self.mie_asymmetry,
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ShaderType, Reflect)]
+pub struct AtmosphereUniform {
+    /// World-space center of the body, kept up to date by [`AtmospherePlugin`].
+    pub center: Vec3,
+    pub bottom_radius: f32,
+    pub sun_position: Vec3,
+    pub top_radius: f32,
+    pub rayleigh_scattering: Vec3,
+    pub rayleigh_scale_height: f32,
+    pub mie_scattering: f32,
+    pub mie_absorption: f32,
+    pub mie_scale_height: f32,
+    pub mie_asymmetry: f32,
+    pub illuminance: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AtmosphereMaterialKey {
+    camera_inside: bool,
+}
+
+impl From<&AtmosphereMaterial> for AtmosphereMaterialKey {
+    fn from(value: &AtmosphereMaterial) -> Self {
+        Self {
+            camera_inside: value.camera_inside,
+        }
+    }
+}
+
+/// Single scattering atmosphere, ray marched on the atmosphere shell with the transmittance
+/// towards the sun read from a precomputed lookup table.
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+#[bind_group_data(AtmosphereMaterialKey)]
+pub struct AtmosphereMaterial {
+    #[uniform(0)]
+    pub uniform: AtmosphereUniform,
+    #[texture(1)]
+    #[sampler(2)]
+    pub transmittance_lut: Handle<Image>,
+    /// Whether the camera is within the atmosphere, in which case the back faces of the shell
+    /// are drawn instead of the front faces.
+    pub camera_inside: bool,
+}
+
+impl AtmosphereMaterial {
+    pub fn new(atmosphere: &Atmosphere, transmittance_lut: Handle<Image>) -> Self {
+        Self {
+            uniform: atmosphere.uniform(),
+            transmittance_lut,
+            camera_inside: false,
+        }
+    }
+}
+
+impl Material for AtmosphereMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/atmosphere.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Premultiplied
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = Some(if key.bind_group_data.camera_inside {
+            Face::Front
+        } else {
+            Face::Back
+        });
+        Ok(())
+    }
+}
+
+fn update_atmospheres(
+    sun_position: Res<SunPosition>,
+    sun_illuminance: Res<SunIlluminance>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    q_atmospheres: Query<(
+        &Atmosphere,
+        &GlobalTransform,
+        &MeshMaterial3d<AtmosphereMaterial>,
+    )>,
+    mut materials: ResMut<Assets<AtmosphereMaterial>>,
+) {
+    let illuminance = sun_illuminance.0;
+    let camera_position = q_cameras
+        .iter()
+        .find(|(camera, _)| camera.is_active)
+        .map(|(_, transform)| transform.translation());
+    for (atmosphere, transform, handle) in &q_atmospheres {
+        let Some(material) = materials.get_mut(&handle.0) else {
+            continue;
+        };
+        let center = transform.translation();
+        material.uniform.center = center;
+        material.uniform.sun_position = sun_position.0;
+        material.uniform.illuminance = illuminance;
+        if let Some(camera_position) = camera_position {
+            material.camera_inside = camera_position.distance(center) < atmosphere.top_radius;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn earth() -> Atmosphere {
+        Atmosphere {
+            bottom_radius: 6360e3,
+            top_radius: 6460e3,
+            rayleigh_scattering: Vec3::new(5.802e-6, 13.558e-6, 33.1e-6),
+            rayleigh_scale_height: 8e3,
+            mie_scattering: 3.996e-6,
+            mie_absorption: 4.4e-6,
+            mie_scale_height: 1.2e3,
+            mie_asymmetry: 0.8,
+        }
+    }
+
+    #[test]
+    fn zenith_transmittance_matches_optical_depth() {
+        let atmosphere = earth();
+        let transmittance = atmosphere.transmittance(atmosphere.bottom_radius as f64, 1.0);
+        // Close to the closed form for an infinitely high atmosphere
+        let expected = (-(atmosphere.rayleigh_scattering.as_dvec3() * 8e3
+            + DVec3::splat((3.996e-6 + 4.4e-6) * 1.2e3)))
+        .exp();
+        assert!((transmittance - expected).abs().max_element() < 2e-3);
+
+        // Sunsets are red
+        let horizon = atmosphere.transmittance(atmosphere.bottom_radius as f64, 0.0);
+        assert!(horizon.x > horizon.y && horizon.y > horizon.z);
+        assert!(horizon.max_element() < transmittance.min_element());
+    }
+
+    #[test]
+    fn lut_parametrization_covers_the_atmosphere() {
+        let atmosphere = earth();
+        let (r, mu) = atmosphere.transmittance_lut_r_mu(DVec2::new(0.0, 0.0));
+        assert!((r - atmosphere.bottom_radius as f64).abs() < 1e-6);
+        assert!((mu - 1.0).abs() < 1e-9);
+        let (r, mu) = atmosphere.transmittance_lut_r_mu(DVec2::new(1.0, 0.0));
+        assert!((r - atmosphere.bottom_radius as f64).abs() < 1e-6);
+        assert!(mu.abs() < 1e-9);
+        let (r, _) = atmosphere.transmittance_lut_r_mu(DVec2::new(0.5, 1.0));
+        assert!((r - atmosphere.top_radius as f64).abs() < 1e-3);
+    }
+}
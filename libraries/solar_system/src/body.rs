@@ -1,3 +1,5 @@
+use crate::mjd::Mjd;
+use crate::time_scale::TimeScale;
 use bevy::prelude::*;
 use big_space::{GridCell, ReferenceFrameCommands};
 use std::borrow::Cow;
@@ -8,7 +10,15 @@ impl Plugin for BodyPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<PlanetaryBody>()
             .register_type::<RotationSpeed>()
-            .add_systems(Update, rotation_speed_system);
+            .register_type::<BodyShape>()
+            .register_type::<FitShapeToMesh>()
+            .add_systems(
+                Update,
+                (
+                    rotation_speed_system.run_if(resource_exists::<Time<Mjd>>),
+                    fit_shapes_to_meshes,
+                ),
+            );
     }
 }
 
@@ -18,23 +28,94 @@ pub struct PlanetaryBody;
 
 #[derive(Debug, Clone, Copy, Component, Reflect)]
 #[reflect(Component)]
-pub struct RotationSpeed(pub f32);
+pub struct RotationSpeed(pub f64);
 
 impl RotationSpeed {
-    pub fn from_duration(length: f32) -> Self {
-        Self(std::f32::consts::TAU / length)
+    pub fn from_duration(length: f64) -> Self {
+        Self(std::f64::consts::TAU / length)
     }
 }
 
+/// Shape of a body, as the semi-axes of an ellipsoid in meters, in the local frame of its mesh
+/// (the rotation axis being +Y). Bodies with a custom mesh are approximated by the ellipsoid
+/// fitting the bounds of their mesh.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct BodyShape {
+    pub radii: Vec3,
+}
+
+impl BodyShape {
+    pub fn sphere(radius: f32) -> Self {
+        Self {
+            radii: Vec3::splat(radius),
+        }
+    }
+
+    pub fn ellipsoid(radii: Vec3) -> Self {
+        Self { radii }
+    }
+
+    /// Distance from `point` to the surface, measured along the line to the center, given the
+    /// global transform of the body mesh. Negative below the surface.
+    pub fn distance_to_surface(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        let (_, rotation, center) = transform.to_scale_rotation_translation();
+        let local = rotation.inverse() * (point - center);
+        let scaled = (local / self.radii).length();
+        if scaled == 0.0 {
+            return -self.radii.min_element();
+        }
+        local.length() * (1.0 - scaled.recip())
+    }
+
+    /// Lower bound on the distance from `point` to the surface, given the global transform of the
+    /// body mesh. Unlike [`Self::distance_to_surface`], it never exceeds the actual distance, which
+    /// can be shorter than along the line to the center on elongated bodies. Negative below the
+    /// surface.
+    pub fn min_distance_to_surface(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        let (_, rotation, center) = transform.to_scale_rotation_translation();
+        let local = rotation.inverse() * (point - center);
+        // Scaling the ellipsoid to the unit sphere shrinks distances by at most the smallest radius
+        self.radii.min_element() * ((local / self.radii).length() - 1.0)
+    }
+
+    /// Distance along the ray to where it enters the surface, or leaves it when starting inside,
+    /// given the global transform of the body mesh.
+    pub fn ray_intersection(&self, transform: &GlobalTransform, ray: Ray3d) -> Option<f32> {
+        let (_, rotation, center) = transform.to_scale_rotation_translation();
+        let inverse = rotation.inverse();
+        // In the space where the ellipsoid is the unit sphere, distances along the ray are kept
+        let origin = inverse * (ray.origin - center) / self.radii;
+        let direction = inverse * *ray.direction / self.radii;
+        let a = direction.length_squared();
+        let b = origin.dot(direction);
+        let c = origin.length_squared() - 1.0;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / a, (-b + root) / a]
+            .into_iter()
+            .find(|t| *t >= 0.0)
+    }
+}
+
+/// Marks body shapes to fit to the bounds of their mesh, once it is loaded.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct FitShapeToMesh;
+
 #[allow(clippy::too_many_arguments)]
 pub fn spawn<Prec: big_space::precision::GridPrecision>(
     commands: &mut ReferenceFrameCommands<Prec>,
     name: impl Into<Cow<'static, str>>,
     mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
+    material: impl Bundle,
     rotation_speed: RotationSpeed,
-    radius: f32,
+    scale: Vec3,
     inclination_deg: f32,
+    shape: impl Bundle,
 ) -> Entity {
     let name = name.into();
     commands.insert((
@@ -48,18 +129,81 @@ pub fn spawn<Prec: big_space::precision::GridPrecision>(
             Name::new(format!("{} (Spatial)", name)),
             GridCell::<Prec>::default(),
             Mesh3d(mesh),
-            MeshMaterial3d(material),
-            Transform::from_scale(Vec3::splat(radius))
+            material,
+            Transform::from_scale(scale)
                 .with_rotation(Quat::from_rotation_x(inclination_deg.to_radians())),
+            shape,
         ))
         .id()
 }
 
-pub fn rotation_speed_system(
-    time: Res<Time<Virtual>>,
-    mut q: Query<(&mut Transform, &RotationSpeed)>,
+fn fit_shapes_to_meshes(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    mut q: Query<(Entity, &Mesh3d, &Transform, &mut BodyShape), With<FitShapeToMesh>>,
 ) {
+    for (entity, mesh, transform, mut shape) in &mut q {
+        let Some(aabb) = meshes.get(&mesh.0).and_then(Mesh::compute_aabb) else {
+            continue;
+        };
+        let min = Vec3::from(aabb.min()).abs();
+        let max = Vec3::from(aabb.max()).abs();
+        shape.radii = min.max(max) * transform.scale;
+        commands.entity(entity).remove::<FitShapeToMesh>();
+    }
+}
+
+/// Sets the rotation of bodies from the simulation time, rather than integrating it frame by
+/// frame, so that it stays exact at any clock rate.
+pub fn rotation_speed_system(time: Res<Time<Mjd>>, mut q: Query<(&mut Transform, &RotationSpeed)>) {
+    let seconds = time.context().seconds_since(&Mjd::j2000(), TimeScale::Tt);
     for (mut transform, day) in &mut q {
-        transform.rotate_local_y(time.delta_secs() * day.0)
+        let angle = (seconds * day.0).rem_euclid(std::f64::consts::TAU);
+        transform.rotation = Quat::from_rotation_y(angle as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn ellipsoid_distances_follow_the_axes() {
+        let shape = BodyShape::ellipsoid(Vec3::new(3.0, 1.0, 2.0));
+        let transform = GlobalTransform::from_translation(Vec3::new(10.0, 0.0, 0.0));
+        assert_relative_eq!(
+            shape.distance_to_surface(&transform, Vec3::new(15.0, 0.0, 0.0)),
+            2.0
+        );
+        assert_relative_eq!(
+            shape.distance_to_surface(&transform, Vec3::new(10.0, 5.0, 0.0)),
+            4.0
+        );
+
+        let sphere = BodyShape::sphere(2.0);
+        assert_relative_eq!(
+            sphere.min_distance_to_surface(&transform, Vec3::new(15.0, 0.0, 0.0)),
+            3.0
+        );
+        // Off the axes, the surface is closer than along the line to the center
+        let point = Vec3::new(12.0, 2.0, 0.0);
+        let closest = (0..3600)
+            .map(|i| {
+                let angle = (i as f32 / 10.0).to_radians();
+                let surface = Vec3::new(3.0 * angle.cos(), angle.sin(), 0.0);
+                (point - transform.translation()).distance(surface)
+            })
+            .fold(f32::INFINITY, f32::min);
+        let min = shape.min_distance_to_surface(&transform, point);
+        assert!(closest < shape.distance_to_surface(&transform, point));
+        assert!(min <= closest, "{min} {closest}");
+
+        let ray = Ray3d::new(Vec3::new(10.0, 0.0, 10.0), Dir3::NEG_Z);
+        assert_relative_eq!(shape.ray_intersection(&transform, ray).unwrap(), 8.0);
+        let ray = Ray3d::new(Vec3::new(10.0, 0.0, 0.0), Dir3::Y);
+        assert_relative_eq!(shape.ray_intersection(&transform, ray).unwrap(), 1.0);
+        let ray = Ray3d::new(Vec3::new(10.0, 5.0, 0.0), Dir3::X);
+        assert!(shape.ray_intersection(&transform, ray).is_none());
     }
 }
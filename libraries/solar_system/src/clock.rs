@@ -0,0 +1,220 @@
+use crate::mjd::Mjd;
+use bevy::prelude::*;
+use bevy::time::TimeSystem;
+use chrono::Utc;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SimulationClock>()
+            .init_resource::<SimulationClock>()
+            .add_systems(
+                Update,
+                clock_tick
+                    .run_if(resource_exists::<Time<Mjd>>)
+                    .after(TimeSystem),
+            );
+    }
+}
+
+/// Named durations used both as playback rates ("1 day/s") and as step sizes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClockPreset {
+    pub label: &'static str,
+    pub seconds: f64,
+}
+
+impl ClockPreset {
+    pub const ALL: [Self; 8] = [
+        Self::new("1 s", 1.0),
+        Self::new("1 min", 60.0),
+        Self::new("1 hour", 3600.0),
+        Self::new("1 day", 86400.0),
+        Self::new("1 week", 7.0 * 86400.0),
+        Self::new("1 month", 30.4375 * 86400.0),
+        Self::new("1 year", 365.25 * 86400.0),
+        Self::new("10 years", 3652.5 * 86400.0),
+    ];
+
+    const fn new(label: &'static str, seconds: f64) -> Self {
+        Self { label, seconds }
+    }
+}
+
+/// Drives the simulation time in [`Time<Mjd>`].
+///
+/// The rate is the number of simulated seconds per real second, and can be negative to play time
+/// backwards. In live mode, the clock instead follows the system time, shifted by a fixed offset;
+/// any other change to the clock leaves live mode.
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationClock {
+    rate: f64,
+    paused: bool,
+    pending_step: f64,
+    live_offset: Option<f64>,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            paused: false,
+            pending_step: 0.0,
+            live_offset: None,
+        }
+    }
+}
+
+impl SimulationClock {
+    pub const MAX_RATE: f64 = 1e9;
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Sets the playback rate, clamped to ±[`Self::MAX_RATE`].
+    pub fn set_rate(&mut self, rate: f64) {
+        self.leave_live();
+        if rate.is_finite() {
+            self.rate = rate.clamp(-Self::MAX_RATE, Self::MAX_RATE);
+        }
+    }
+
+    pub fn set_preset(&mut self, preset: ClockPreset) {
+        self.set_rate(preset.seconds.copysign(self.rate));
+    }
+
+    pub fn reverse(&mut self) {
+        self.leave_live();
+        self.rate = -self.rate;
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.rate.is_sign_negative()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.leave_live();
+        self.paused = paused;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.leave_live();
+        self.paused = !self.paused;
+    }
+
+    /// Moves time by the given amount of seconds on the next update, regardless of the rate and
+    /// of whether the clock is paused.
+    pub fn step(&mut self, seconds: f64) {
+        self.leave_live();
+        self.pending_step += seconds;
+    }
+
+    /// Locks the clock to the system time, shifted by `offset_seconds`. Playback resumes at real
+    /// time.
+    pub fn go_live(&mut self, offset_seconds: f64) {
+        self.live_offset = Some(offset_seconds);
+        self.rate = 1.0;
+        self.paused = false;
+        self.pending_step = 0.0;
+    }
+
+    pub fn leave_live(&mut self) {
+        self.live_offset = None;
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live_offset.is_some()
+    }
+
+    /// Offset from the system time in seconds, when in live mode.
+    pub fn live_offset(&self) -> Option<f64> {
+        self.live_offset
+    }
+
+    /// Consumes `real_seconds` of wall-clock time and returns the amount of simulated seconds to
+    /// advance by.
+    pub fn advance(&mut self, real_seconds: f64) -> f64 {
+        let step = std::mem::take(&mut self.pending_step);
+        if self.paused {
+            step
+        } else {
+            step + real_seconds * self.rate
+        }
+    }
+}
+
+fn clock_tick(
+    mut clock: ResMut<SimulationClock>,
+    mut time: ResMut<Time<Mjd>>,
+    virtual_time: Res<Time<Virtual>>,
+) {
+    if let Some(offset) = clock.live_offset() {
+        let mut now = Mjd::from_utc(Utc::now());
+        now.add_seconds(offset);
+        *time.context_mut() = now;
+        return;
+    }
+    let delta = clock.advance(virtual_time.delta_secs_f64());
+    if delta != 0.0 {
+        time.context_mut().add_seconds(delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_is_clamped() {
+        let mut clock = SimulationClock::default();
+        clock.set_rate(-1e12);
+        assert_eq!(clock.rate(), -SimulationClock::MAX_RATE);
+        clock.set_rate(f64::NAN);
+        assert_eq!(clock.rate(), -SimulationClock::MAX_RATE);
+    }
+
+    #[test]
+    fn preset_keeps_direction() {
+        let mut clock = SimulationClock::default();
+        clock.reverse();
+        clock.set_preset(ClockPreset::ALL[3]);
+        assert_eq!(clock.rate(), -86400.0);
+    }
+
+    #[test]
+    fn step_applies_while_paused() {
+        let mut clock = SimulationClock::default();
+        clock.set_rate(10.0);
+        clock.set_paused(true);
+        assert_eq!(clock.advance(0.5), 0.0);
+        clock.step(-3600.0);
+        assert_eq!(clock.advance(0.5), -3600.0);
+        assert_eq!(clock.advance(0.5), 0.0);
+        clock.toggle_pause();
+        assert_eq!(clock.advance(0.5), 5.0);
+    }
+
+    #[test]
+    fn changes_leave_live_mode() {
+        let mut clock = SimulationClock::default();
+        clock.set_rate(-50.0);
+        clock.set_paused(true);
+        clock.go_live(3600.0);
+        assert_eq!(clock.live_offset(), Some(3600.0));
+        assert_eq!(clock.rate(), 1.0);
+        assert!(!clock.is_paused());
+
+        clock.step(60.0);
+        assert!(!clock.is_live());
+        clock.go_live(0.0);
+        clock.reverse();
+        assert!(!clock.is_live());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::atmosphere::Atmosphere;
+use crate::body::BodyShape;
+use crate::rings::PlanetRings;
+use crate::terrain::Terrain;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+pub struct DepthPlugin;
+
+impl Plugin for DepthPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AdaptiveNearPlane>().add_systems(
+            PostUpdate,
+            adapt_near_planes.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Moves the near plane of a perspective camera to a fraction of the distance to the closest
+/// surface, so that nothing gets clipped when landing on a moon while keeping the near plane out
+/// of the way from afar.
+///
+/// Bevy renders with an infinite far plane and reversed floating point depth, whose precision is
+/// relative to the distance, which is what lets bodies at millions of kilometers and stars at
+/// light-years share the depth buffer without fighting.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct AdaptiveNearPlane {
+    /// Fraction of the distance to the closest surface at which the near plane is put, leaving
+    /// room for the camera to move before the next update.
+    pub fraction: f32,
+    /// Bounds on the near plane distance, in meters.
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for AdaptiveNearPlane {
+    fn default() -> Self {
+        Self {
+            fraction: 0.5,
+            min: 0.01,
+            max: 1e6,
+        }
+    }
+}
+
+impl AdaptiveNearPlane {
+    /// Near plane distance for the given distance to the closest surface.
+    pub fn near(&self, closest: Option<f32>) -> f32 {
+        closest.map_or(self.max, |distance| {
+            (self.fraction * distance).clamp(self.min, self.max)
+        })
+    }
+}
+
+/// Distance from a point to a flat ring in the XZ plane, all in the same units.
+pub fn ring_distance(point: Vec3, inner_radius: f32, outer_radius: f32) -> f32 {
+    let radial = point.xz();
+    let length = radial.length();
+    let closest = if length > 0.0 {
+        radial / length * length.clamp(inner_radius, outer_radius)
+    } else {
+        Vec2::new(inner_radius, 0.0)
+    };
+    point.distance(Vec3::new(closest.x, 0.0, closest.y))
+}
+
+/// Distance from the point to the closest surface of a body, including its highest mountains, its
+/// atmosphere and its rings.
+fn closest_surface(
+    point: Vec3,
+    q_bodies: &Query<(Entity, &GlobalTransform, &BodyShape)>,
+    q_atmospheres: &Query<(&GlobalTransform, &Atmosphere)>,
+    q_rings: &Query<(&GlobalTransform, &PlanetRings)>,
+    elevations: &HashMap<Entity, f32>,
+) -> Option<f32> {
+    let bodies = q_bodies.iter().map(|(entity, transform, shape)| {
+        let elevation = elevations.get(&entity).copied().unwrap_or_default();
+        shape.min_distance_to_surface(transform, point) - elevation
+    });
+    // The shell is drawn from inside as well, so it can be close from either side
+    let atmospheres = q_atmospheres.iter().map(|(transform, atmosphere)| {
+        (transform.translation().distance(point) - atmosphere.top_radius).abs()
+    });
+    let rings = q_rings.iter().map(|(transform, rings)| {
+        let local = transform.affine().inverse().transform_point3(point);
+        let closest = ring_distance(local, rings.inner_radius, rings.outer_radius);
+        // Rings are scaled by the radius of their body
+        closest * transform.scale().max_element()
+    });
+    bodies.chain(atmospheres).chain(rings).reduce(f32::min)
+}
+
+/// Updates the near plane from the current positions. The projection matrix picks it up on the
+/// next frame, which the fraction of [`AdaptiveNearPlane`] leaves room for.
+fn adapt_near_planes(
+    mut q_cameras: Query<(&GlobalTransform, &AdaptiveNearPlane, &mut Projection)>,
+    q_bodies: Query<(Entity, &GlobalTransform, &BodyShape)>,
+    q_atmospheres: Query<(&GlobalTransform, &Atmosphere)>,
+    q_rings: Query<(&GlobalTransform, &PlanetRings)>,
+    q_terrains: Query<&Terrain>,
+) {
+    let elevations = q_terrains
+        .iter()
+        .map(|terrain| (terrain.body, terrain.max_elevation() as f32))
+        .collect::<HashMap<_, _>>();
+    for (transform, settings, mut projection) in &mut q_cameras {
+        let Projection::Perspective(perspective) = projection.as_ref() else {
+            continue;
+        };
+        let closest = closest_surface(
+            transform.translation(),
+            &q_bodies,
+            &q_atmospheres,
+            &q_rings,
+            &elevations,
+        );
+        let near = settings.near(closest);
+        if perspective.near != near {
+            if let Projection::Perspective(perspective) = projection.as_mut() {
+                perspective.near = near;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_distance_from_above_and_within() {
+        // Above the middle of the ring
+        assert_eq!(ring_distance(Vec3::new(1.5, 2.0, 0.0), 1.0, 2.0), 2.0);
+        // In the gap inside the ring
+        assert_eq!(ring_distance(Vec3::new(0.0, 0.0, 0.25), 1.0, 2.0), 0.75);
+        // Outside the ring, off its plane
+        let distance = ring_distance(Vec3::new(0.0, 4.0, -5.0), 1.0, 2.0);
+        assert!((distance - 5.0).abs() < 1e-6, "{distance}");
+    }
+
+    #[test]
+    fn near_plane_follows_the_closest_surface() {
+        let settings = AdaptiveNearPlane::default();
+        assert_eq!(settings.near(Some(1000.0)), 500.0);
+        assert_eq!(settings.near(Some(-10.0)), settings.min);
+        assert_eq!(settings.near(Some(1e12)), settings.max);
+        assert_eq!(settings.near(None), settings.max);
+    }
+}
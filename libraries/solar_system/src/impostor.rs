@@ -0,0 +1,235 @@
+use crate::body::BodyShape;
+use crate::scene::components::SceneCamera;
+use crate::sun::{self, Sun, SOLAR_LUMINOSITY};
+use bevy::asset::embedded_asset;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError,
+};
+use std::f64::consts::PI;
+
+pub struct ImpostorPlugin;
+
+impl Plugin for ImpostorPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/impostor.wgsl");
+        app.add_plugins(MaterialPlugin::<ImpostorMaterial>::default())
+            .register_type::<Impostor>()
+            .add_systems(
+                Update,
+                update_impostors
+                    .after(sun::sun_lighting)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Astronomical unit, in meters.
+const AU: f64 = 149_597_870_700.0;
+/// Apparent magnitude of a source giving an illuminance of 1 lux.
+const LUX_MAGNITUDE: f64 = -14.18;
+
+/// Point of light standing in for a body once it is smaller than a few pixels, spawned as a
+/// sibling of its mesh. Keeps stars of the scene visible from the other stars.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Impostor {
+    /// Entity holding the mesh of the body.
+    pub body: Entity,
+    /// Geometric albedo of the body.
+    pub albedo: f32,
+    /// Power radiated by the body itself, in watts, for stars which are seen by their own light.
+    pub luminosity: f64,
+}
+
+impl MapEntities for Impostor {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.body = entity_mapper.map_entity(self.body);
+    }
+}
+
+/// Apparent magnitude of a body lit by a star, seen from `observer_distance` with the given phase
+/// angle, the angle between the star and the observer as seen from the body. Distances are in
+/// meters, and the body is a Lambertian sphere.
+pub fn apparent_magnitude(
+    radius: f64,
+    albedo: f64,
+    luminosity: f64,
+    star_distance: f64,
+    observer_distance: f64,
+    phase_angle: f64,
+) -> f64 {
+    // Absolute magnitude of a body of this diameter and albedo, lit by the Sun
+    let absolute_magnitude = 5.0 * (1329e3 / (2.0 * radius * albedo.sqrt())).log10();
+    // Lambertian phase function, 1 when fully lit
+    let phase = (1.0 - phase_angle / PI) * phase_angle.cos() + phase_angle.sin() / PI;
+    absolute_magnitude + 5.0 * (star_distance * observer_distance / (AU * AU)).log10()
+        - 2.5 * phase.max(1e-9).log10()
+        - 2.5 * (luminosity / SOLAR_LUMINOSITY).log10()
+}
+
+/// Illuminance, in lux, given by a source of the given apparent magnitude.
+pub fn magnitude_illuminance(magnitude: f64) -> f64 {
+    10f64.powf(-0.4 * (magnitude - LUX_MAGNITUDE))
+}
+
+#[derive(Debug, Clone, Copy, Default, ShaderType, Reflect)]
+pub struct ImpostorMaterialUniform {
+    /// Illuminance given by the body at the camera, in lux, kept up to date by
+    /// [`ImpostorPlugin`].
+    pub illuminance: f32,
+    /// Radius of the body, to fade the impostor out once its disc covers a few pixels.
+    pub radius: f32,
+}
+
+/// Gaussian spot of a fixed size in pixels, on a camera facing quad placed in the vertex shader,
+/// whose integral over the screen is the illuminance given by the body. The quad is expected to
+/// be a 2×2 rectangle centered on the body.
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+pub struct ImpostorMaterial {
+    #[uniform(0)]
+    pub uniform: ImpostorMaterialUniform,
+}
+
+impl ImpostorMaterial {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            uniform: ImpostorMaterialUniform {
+                illuminance: 0.0,
+                radius,
+            },
+        }
+    }
+}
+
+impl Material for ImpostorMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/impostor.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/impostor.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Add
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout
+            .0
+            .get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// Sums the light reflected by each body towards the camera, from every sun, or takes the light
+/// stars give by themselves.
+fn update_impostors(
+    q_camera: Query<&GlobalTransform, With<SceneCamera>>,
+    q_suns: Query<(&Sun, &GlobalTransform)>,
+    q_bodies: Query<(&GlobalTransform, &BodyShape)>,
+    q_impostors: Query<(&Impostor, &MeshMaterial3d<ImpostorMaterial>)>,
+    mut materials: ResMut<Assets<ImpostorMaterial>>,
+) {
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let camera = camera.translation().as_dvec3();
+    for (impostor, material) in &q_impostors {
+        let Ok((transform, shape)) = q_bodies.get(impostor.body) else {
+            continue;
+        };
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        let radius = shape.radii.max_element() as f64;
+        let center = transform.translation().as_dvec3();
+        let to_observer = camera - center;
+        if impostor.luminosity > 0.0 {
+            let star = Sun {
+                radius: radius as _,
+                luminosity: impostor.luminosity,
+            };
+            material.uniform.illuminance =
+                star.illuminance_at(to_observer.length().max(radius)) as _;
+            material.uniform.radius = radius as _;
+            continue;
+        }
+        let illuminance = q_suns
+            .iter()
+            .map(|(sun, sun_transform)| {
+                let to_star = sun_transform.translation().as_dvec3() - center;
+                let magnitude = apparent_magnitude(
+                    radius,
+                    impostor.albedo as _,
+                    sun.luminosity,
+                    to_star.length().max(radius),
+                    to_observer.length().max(radius),
+                    to_star.angle_between(to_observer),
+                );
+                magnitude_illuminance(magnitude)
+            })
+            .sum::<f64>();
+        material.uniform.illuminance = illuminance as _;
+        material.uniform.radius = radius as _;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planets_have_their_observed_magnitudes() {
+        // Jupiter at opposition
+        let jupiter =
+            apparent_magnitude(69_911e3, 0.538, SOLAR_LUMINOSITY, 5.2 * AU, 4.2 * AU, 0.0);
+        assert!((jupiter + 2.7).abs() < 0.1, "{jupiter}");
+        // Venus at superior conjunction, fully lit
+        let venus = apparent_magnitude(
+            6_051.8e3,
+            0.689,
+            SOLAR_LUMINOSITY,
+            0.723 * AU,
+            1.723 * AU,
+            0.0,
+        );
+        assert!((venus + 3.9).abs() < 0.1, "{venus}");
+        // Thinner phases are dimmer
+        let crescent = apparent_magnitude(
+            6_051.8e3,
+            0.689,
+            SOLAR_LUMINOSITY,
+            0.723 * AU,
+            0.3 * AU,
+            150f64.to_radians(),
+        );
+        let gibbous = apparent_magnitude(
+            6_051.8e3,
+            0.689,
+            SOLAR_LUMINOSITY,
+            0.723 * AU,
+            0.3 * AU,
+            30f64.to_radians(),
+        );
+        assert!(crescent > gibbous);
+    }
+
+    #[test]
+    fn sun_illuminance_from_magnitude() {
+        let illuminance = magnitude_illuminance(-26.74);
+        assert!((illuminance - 107e3).abs() < 3e3, "{illuminance}");
+    }
+}
@@ -3,11 +3,25 @@ use bevy::prelude::*;
 use big_space::precision::GridPrecision;
 use std::marker::PhantomData;
 
+pub mod atmosphere;
 pub mod body;
+pub mod clock;
+pub mod depth;
+pub mod impostor;
+pub mod mesh;
 pub mod mjd;
+pub mod noise;
 pub mod orbit;
+pub mod orbit_line;
+pub mod planet_material;
+pub mod procedural;
+pub mod rings;
 pub mod scene;
+pub mod star;
 pub mod sun;
+pub mod terrain;
+pub mod time_scale;
+pub mod trail;
 
 pub struct SolarSystemPlugin<Prec: GridPrecision>(PhantomData<Prec>);
 
@@ -22,8 +36,18 @@ impl<Prec: GridPrecision> PluginGroup for SolarSystemPlugin<Prec> {
         PluginGroupBuilder::start::<Self>()
             .add(body::BodyPlugin)
             .add(mjd::MjdPlugin)
+            .add(clock::ClockPlugin)
             .add(orbit::OrbitPlugin::<Prec>::default())
+            .add(orbit_line::OrbitLinePlugin)
+            .add(trail::TrailPlugin::<Prec>::default())
             .add(sun::SunPlugin)
+            .add(star::StarPlugin)
+            .add(planet_material::PlanetMaterialPlugin)
+            .add(rings::RingsPlugin)
+            .add(atmosphere::AtmospherePlugin)
+            .add(impostor::ImpostorPlugin)
+            .add(depth::DepthPlugin)
+            .add(terrain::TerrainPlugin::<Prec>::default())
             .add(scene::PlanetScenePlugin::<Prec>::default())
     }
 }
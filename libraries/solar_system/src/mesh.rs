@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Creates a unit UV sphere whose texture coordinates follow the equirectangular projection of
+/// planetary maps: `u` goes eastwards from longitude −180° to 180°, with the prime meridian on
+/// +X, and `v` goes from the north pole (+Y) to the south pole.
+///
+/// The seam gets its own column of vertices so that textures do not wrap backwards across it,
+/// and each pole vertex is duplicated per sector to sample the middle of its triangle.
+pub fn equirectangular_sphere(sectors: u32, stacks: u32) -> Mesh {
+    let sectors = sectors.max(3);
+    let stacks = stacks.max(2);
+    let vertex_count = ((sectors + 1) * (stacks + 1)) as usize;
+    let mut positions = Vec::with_capacity(vertex_count);
+    let mut normals = Vec::with_capacity(vertex_count);
+    let mut uvs = Vec::with_capacity(vertex_count);
+
+    for stack in 0..=stacks {
+        let v = stack as f32 / stacks as f32;
+        let latitude = FRAC_PI_2 - v * PI;
+        for sector in 0..=sectors {
+            let mut u = sector as f32 / sectors as f32;
+            if stack == 0 || stack == stacks {
+                u += 0.5 / sectors as f32;
+            }
+            let longitude = u * TAU - PI;
+            let normal = Vec3::new(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                -latitude.cos() * longitude.sin(),
+            );
+            positions.push(normal.to_array());
+            normals.push(normal.to_array());
+            uvs.push([u, v]);
+        }
+    }
+
+    let row = sectors + 1;
+    let mut indices = Vec::with_capacity((6 * sectors * (stacks - 1)) as usize);
+    for stack in 0..stacks {
+        for sector in 0..sectors {
+            let top_left = stack * row + sector;
+            let bottom_left = top_left + row;
+            if stack != 0 {
+                indices.extend([top_left, bottom_left, top_left + 1]);
+            }
+            if stack != stacks - 1 {
+                indices.extend([top_left + 1, bottom_left, bottom_left + 1]);
+            }
+        }
+    }
+
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices));
+    // Tangents are needed for normal maps; they only fail to generate on malformed meshes
+    mesh.clone().with_generated_tangents().unwrap_or(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    #[test]
+    fn uvs_follow_longitude_and_latitude() {
+        let mesh = equirectangular_sphere(8, 4);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("Missing positions");
+        };
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("Missing UVs");
+        };
+        for (position, [u, v]) in positions.iter().zip(uvs) {
+            let position = Vec3::from_array(*position);
+            let latitude = position.y.asin();
+            assert!((v - (FRAC_PI_2 - latitude) / PI).abs() < 1e-5);
+            if position.y.abs() < 0.999 {
+                // Eastwards is counter-clockwise seen from the north pole
+                let longitude = (-position.z).atan2(position.x);
+                let expected = (longitude + PI) / TAU;
+                let delta = (u - expected).abs();
+                assert!(delta < 1e-5 || (delta - 1.0).abs() < 1e-5, "{u} {expected}");
+            }
+        }
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_TANGENT).is_some());
+    }
+}
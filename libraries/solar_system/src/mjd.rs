@@ -1,37 +1,37 @@
+use crate::time_scale::{normalize, tai_minus_utc, TimeScale, SECONDS_PER_DAY};
 use bevy::prelude::*;
-use bevy::time::TimeSystem;
-use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
-use serde::de::Error;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Utc};
+use serde::de::{Error, SeqAccess};
+use serde::ser::SerializeTuple;
 use serde::{Deserializer, Serializer};
 use std::fmt;
 use std::fmt::Formatter;
-use std::time::Duration;
 
 pub struct MjdPlugin;
 
 impl Plugin for MjdPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Time<Mjd>>().add_systems(
-            Update,
-            clock_tick
-                .run_if(resource_exists::<Time<Mjd>>)
-                .after(TimeSystem),
-        );
+        app.init_resource::<Time<Mjd>>();
     }
 }
 
-#[derive(Debug, Copy, Clone, Deref, Resource, Reflect)]
+/// Instant on the simulation timeline.
+///
+/// The instant is stored as a two-part Modified Julian Date in Terrestrial Time, which is what
+/// orbits are propagated in: a whole day count and the fraction of that day. This keeps
+/// sub-microsecond precision over ±1 million years, including dates before the MJD epoch. Use
+/// [`Mjd::to_utc`] for display purposes.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Resource, Reflect)]
 #[reflect(opaque)]
-pub struct Mjd(DateTime<Utc>);
+pub struct Mjd {
+    day: i64,
+    fraction: f64,
+}
 
 impl fmt::Display for Mjd {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "MJD ")?;
-        if let Some(mjd) = self.mjd() {
-            write!(f, "{:.1}", mjd)
-        } else {
-            write!(f, "N/A")
-        }
+        let precision = f.precision().unwrap_or(1);
+        write!(f, "MJD {:.*} TT", precision, self.mjd())
     }
 }
 
@@ -40,13 +40,22 @@ impl serde::Serialize for Mjd {
     where
         S: Serializer,
     {
-        serializer.serialize_f64(self.mjd().expect("Invalid MJD"))
+        let mjd = self.mjd();
+        if Mjd::from(mjd) == *self {
+            serializer.serialize_f64(mjd)
+        } else {
+            // Not representable as a single float, write out both parts instead
+            let mut tuple = serializer.serialize_tuple(2)?;
+            tuple.serialize_element(&self.day)?;
+            tuple.serialize_element(&self.fraction)?;
+            tuple.end()
+        }
     }
 }
 
 impl From<f64> for Mjd {
     fn from(value: f64) -> Self {
-        Self(Self::epoch() + Duration::from_secs_f64(value * 86400.0))
+        Self::from_mjd(TimeScale::Tt, value)
     }
 }
 
@@ -56,56 +65,318 @@ impl<'de> serde::Deserialize<'de> for Mjd {
         D: Deserializer<'de>,
     {
         struct MjdVisitor;
-        impl serde::de::Visitor<'_> for MjdVisitor {
+        impl<'de> serde::de::Visitor<'de> for MjdVisitor {
             type Value = Mjd;
 
             fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-                formatter.write_str("A floating value representing the MJD date (days since midnight of Nov. 17, 1858")
+                formatter.write_str("A floating value representing the MJD date in TT (days since midnight of Nov. 17, 1858), or a [day, fraction] pair")
             }
 
             fn visit_f64<E>(self, days: f64) -> Result<Self::Value, E>
             where
                 E: Error,
             {
+                if !days.is_finite() {
+                    return Err(E::custom("MJD must be finite"));
+                }
                 Ok(Mjd::from(days))
             }
+
+            fn visit_i64<E>(self, days: i64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Mjd::from_two_part(TimeScale::Tt, days, 0.0))
+            }
+
+            fn visit_u64<E>(self, days: u64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                let days = i64::try_from(days).map_err(E::custom)?;
+                self.visit_i64(days)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let day = seq
+                    .next_element::<i64>()?
+                    .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                let fraction = seq
+                    .next_element::<f64>()?
+                    .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+                if !fraction.is_finite() {
+                    return Err(A::Error::custom("MJD fraction must be finite"));
+                }
+                Ok(Mjd::from_two_part(TimeScale::Tt, day, fraction))
+            }
         }
-        deserializer.deserialize_f64(MjdVisitor)
+        deserializer.deserialize_any(MjdVisitor)
     }
 }
 
 impl Default for Mjd {
     fn default() -> Self {
-        Mjd(Utc::now())
+        Self::from_utc(Utc::now())
     }
 }
 
 impl Mjd {
+    /// MJD 0 TT, that is midnight of Nov. 17, 1858 in Terrestrial Time.
     pub fn zero() -> Self {
-        Self(Self::epoch())
+        Self {
+            day: 0,
+            fraction: 0.0,
+        }
+    }
+
+    /// The J2000.0 epoch, Jan. 1st 2000 at noon TT.
+    pub fn j2000() -> Self {
+        Self {
+            day: 51544,
+            fraction: 0.5,
+        }
+    }
+
+    /// Creates an instant from a two-part MJD expressed in the given time scale. The fraction
+    /// does not need to be normalized.
+    pub fn from_two_part(scale: TimeScale, day: i64, fraction: f64) -> Self {
+        Self::from_day_seconds(scale, day, fraction * SECONDS_PER_DAY)
+    }
+
+    /// Two-part MJD in the given time scale. The fraction is in `[0, 1)`, except during a UTC
+    /// leap second where it goes past 1.
+    pub fn to_two_part(&self, scale: TimeScale) -> (i64, f64) {
+        let (day, seconds) = self.to_day_seconds(scale);
+        (day, seconds / SECONDS_PER_DAY)
+    }
+
+    /// Creates an instant from a Julian Date expressed in the given time scale.
+    pub fn from_jd(scale: TimeScale, jd: f64) -> Self {
+        // Split before shifting the epoch to keep the fractional part precise
+        let day = jd.floor();
+        Self::from_two_part(scale, day as i64 - 2_400_001, jd - day + 0.5)
+    }
+
+    /// Creates an instant from a Modified Julian Date expressed in the given time scale.
+    pub fn from_mjd(scale: TimeScale, mjd: f64) -> Self {
+        let day = mjd.floor();
+        Self::from_two_part(scale, day as _, mjd - day)
+    }
+
+    /// Creates an instant from the number of seconds since 2000-01-01T12:00:00 in the given time
+    /// scale. Leap seconds are not counted in UTC.
+    pub fn from_j2000_seconds(scale: TimeScale, seconds: f64) -> Self {
+        let days = (seconds / SECONDS_PER_DAY).floor();
+        let seconds = seconds - days * SECONDS_PER_DAY;
+        Self::from_day_seconds(
+            scale,
+            J2000_DAY + days as i64,
+            seconds + SECONDS_PER_DAY / 2.0,
+        )
+    }
+
+    pub fn jd(&self, scale: TimeScale) -> f64 {
+        let (day, fraction) = self.to_two_part(scale);
+        (day + 2_400_000) as f64 + (fraction + 0.5)
     }
 
-    pub fn mjd(&self) -> Option<f64> {
-        let delta = self.0 - Self::epoch();
-        Some(delta.to_std().ok()?.as_secs_f64() / 86400.0)
+    pub fn mjd_in(&self, scale: TimeScale) -> f64 {
+        let (day, fraction) = self.to_two_part(scale);
+        day as f64 + fraction
+    }
+
+    pub fn j2000_seconds(&self, scale: TimeScale) -> f64 {
+        let (day, seconds) = self.to_day_seconds(scale);
+        (day - J2000_DAY) as f64 * SECONDS_PER_DAY + (seconds - SECONDS_PER_DAY / 2.0)
+    }
+
+    /// Number of seconds elapsed since `other`, measured in the given time scale.
+    pub fn seconds_since(&self, other: &Self, scale: TimeScale) -> f64 {
+        let (day, seconds) = self.to_day_seconds(scale);
+        let (other_day, other_seconds) = other.to_day_seconds(scale);
+        (day - other_day) as f64 * SECONDS_PER_DAY + (seconds - other_seconds)
+    }
+
+    /// MJD in Terrestrial Time.
+    pub fn mjd(&self) -> f64 {
+        self.day as f64 + self.fraction
+    }
+
+    /// Advances the instant by the given number of TT seconds, which can be negative.
+    pub fn add_seconds(&mut self, seconds: f64) {
+        *self = Self::from_day_seconds(
+            TimeScale::Tt,
+            self.day,
+            self.fraction * SECONDS_PER_DAY + seconds,
+        );
+    }
+
+    pub fn from_datetime(scale: TimeScale, datetime: NaiveDateTime) -> Self {
+        let day = (datetime.date() - mjd_epoch_date()).num_days();
+        // chrono represents a leap second as a nanosecond field overflowing into the next second
+        let seconds =
+            datetime.num_seconds_from_midnight() as f64 + datetime.nanosecond() as f64 * 1e-9;
+        Self::from_day_seconds(scale, day, seconds)
+    }
+
+    /// Calendar date and time in the given time scale, if within the range supported by chrono.
+    pub fn to_datetime(&self, scale: TimeScale) -> Option<NaiveDateTime> {
+        let (mut day, seconds) = self.to_day_seconds(scale);
+        let mut whole = seconds.floor() as i64;
+        let mut nanos = ((seconds - seconds.floor()) * 1e9).round() as i64;
+        if nanos >= 1_000_000_000 {
+            whole += 1;
+            nanos -= 1_000_000_000;
+        }
+        let is_leap_day = scale == TimeScale::Utc && tai_minus_utc(day + 1) > tai_minus_utc(day);
+        if whole >= 86400 && !is_leap_day {
+            day += 1;
+            whole -= 86400;
+        }
+        let date = mjd_epoch_date().checked_add_signed(TimeDelta::try_days(day)?)?;
+        let time = if whole >= 86400 {
+            NaiveTime::from_hms_nano_opt(23, 59, 59, 1_000_000_000 + nanos as u32)?
+        } else {
+            NaiveTime::from_num_seconds_from_midnight_opt(whole as u32, nanos as u32)?
+        };
+        Some(date.and_time(time))
+    }
+
+    pub fn from_utc(datetime: DateTime<Utc>) -> Self {
+        Self::from_datetime(TimeScale::Utc, datetime.naive_utc())
+    }
+
+    /// UTC date and time, if within the range supported by chrono.
+    pub fn to_utc(&self) -> Option<DateTime<Utc>> {
+        self.to_datetime(TimeScale::Utc)
+            .map(|datetime| datetime.and_utc())
     }
 
     pub fn set_from_datetime(&mut self, datetime: DateTime<Utc>) {
-        self.0 = datetime;
+        *self = Self::from_utc(datetime);
     }
 
     pub fn set_from_mjd(&mut self, mjd: f64) {
         *self = Self::from(mjd)
     }
 
-    fn epoch() -> DateTime<Utc> {
-        NaiveDate::from_ymd_opt(1858, 11, 17)
-            .unwrap()
-            .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-            .and_utc()
+    fn from_day_seconds(scale: TimeScale, day: i64, seconds: f64) -> Self {
+        let (day, seconds) = scale.to_tt(day, seconds);
+        Self {
+            day,
+            fraction: seconds / SECONDS_PER_DAY,
+        }
+    }
+
+    fn to_day_seconds(self, scale: TimeScale) -> (i64, f64) {
+        let (day, seconds) = normalize(self.day, self.fraction * SECONDS_PER_DAY);
+        scale.from_tt(day, seconds)
     }
 }
 
-fn clock_tick(mut time: ResMut<Time<Mjd>>, virtual_time: Res<Time<Virtual>>) {
-    time.context_mut().0 += TimeDelta::from_std(virtual_time.delta()).unwrap();
+/// MJD day number of the J2000.0 epoch, which is at noon of that day.
+pub(crate) const J2000_DAY: i64 = 51544;
+
+fn mjd_epoch_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1858, 11, 17).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn j2000_conversions() {
+        let j2000 = Mjd::j2000();
+        assert_eq!(j2000.jd(TimeScale::Tt), 2_451_545.0);
+        assert_eq!(j2000.mjd_in(TimeScale::Tt), 51_544.5);
+        assert_eq!(j2000.j2000_seconds(TimeScale::Tt), 0.0);
+        assert_abs_diff_eq!(j2000.j2000_seconds(TimeScale::Tai), -32.184, epsilon = 1e-9);
+        assert_abs_diff_eq!(j2000.j2000_seconds(TimeScale::Utc), -64.184, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn float_conversions_round_trip() {
+        for scale in TimeScale::ALL {
+            let mjd = Mjd::from_mjd(scale, 60_123.25);
+            assert_abs_diff_eq!(mjd.mjd_in(scale), 60_123.25, epsilon = 1e-12);
+            let jd = Mjd::from_jd(scale, 2_460_000.75);
+            assert_abs_diff_eq!(jd.jd(scale), 2_460_000.75, epsilon = 1e-12);
+            let seconds = Mjd::from_j2000_seconds(scale, -123_456.5);
+            assert_abs_diff_eq!(seconds.j2000_seconds(scale), -123_456.5, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn negative_mjd() {
+        let mjd = Mjd::from(-1234.75);
+        assert_eq!(mjd.mjd(), -1234.75);
+        assert_eq!(mjd.to_string(), "MJD -1234.8 TT");
+        assert_eq!(
+            mjd.to_datetime(TimeScale::Tt),
+            NaiveDate::from_ymd_opt(1855, 7, 1)
+                .unwrap()
+                .and_hms_opt(6, 0, 0)
+        );
+    }
+
+    #[test]
+    fn deep_time() {
+        // One million years after J2000
+        let seconds = 1e6 * 365.25 * SECONDS_PER_DAY;
+        let mut mjd = Mjd::j2000();
+        mjd.add_seconds(seconds);
+        assert_abs_diff_eq!(mjd.seconds_since(&Mjd::j2000(), TimeScale::Tt), seconds);
+        mjd.add_seconds(1e-3);
+        assert_abs_diff_eq!(
+            mjd.seconds_since(&Mjd::j2000(), TimeScale::Tt),
+            seconds + 1e-3,
+            epsilon = 1e-6
+        );
+        assert!(mjd.to_utc().is_none());
+
+        mjd.add_seconds(-2.0 * seconds);
+        assert_abs_diff_eq!(
+            mjd.seconds_since(&Mjd::j2000(), TimeScale::Tt),
+            -seconds + 1e-3,
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn utc_round_trip() {
+        let utc = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_nano_opt(4, 5, 6, 789)
+            .unwrap()
+            .and_utc();
+        assert_eq!(Mjd::from_utc(utc).to_utc(), Some(utc));
+
+        let leap = NaiveDate::from_ymd_opt(2016, 12, 31)
+            .unwrap()
+            .and_hms_milli_opt(23, 59, 59, 1_500)
+            .unwrap()
+            .and_utc();
+        assert_eq!(Mjd::from_utc(leap).to_utc(), Some(leap));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mjd = Mjd::from(51544.5);
+        let yaml = serde_yaml::to_string(&mjd).unwrap();
+        assert_eq!(yaml.trim(), "51544.5");
+        assert_eq!(serde_yaml::from_str::<Mjd>(&yaml).unwrap(), mjd);
+        assert_eq!(
+            serde_yaml::from_str::<Mjd>("51544").unwrap(),
+            Mjd::from(51544.0)
+        );
+
+        let far = Mjd::from_two_part(TimeScale::Tt, -365_000_000, 0.123_456_789_012);
+        let yaml = serde_yaml::to_string(&far).unwrap();
+        assert_eq!(serde_yaml::from_str::<Mjd>(&yaml).unwrap(), far);
+    }
 }
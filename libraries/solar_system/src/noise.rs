@@ -0,0 +1,186 @@
+use bevy::math::DVec3;
+
+/// Seeded 3D gradient noise (Perlin's improved noise), with a permutation table shuffled from the
+/// seed so that different seeds give unrelated fields.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u32) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut rng = SplitMix64::new(seed as u64);
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Self {
+            permutation: std::array::from_fn(|i| table[i % 256]),
+        }
+    }
+
+    /// Samples the noise, in `[-1, 1]`. It is zero on integer lattice points.
+    pub fn sample(&self, point: DVec3) -> f64 {
+        let cell = point.floor();
+        let local = point - cell;
+        let [x, y, z] = cell.to_array().map(|c| (c as i64 & 255) as usize);
+        let p = &self.permutation;
+        let a = p[x] as usize + y;
+        let aa = p[a] as usize + z;
+        let ab = p[a + 1] as usize + z;
+        let b = p[x + 1] as usize + y;
+        let ba = p[b] as usize + z;
+        let bb = p[b + 1] as usize + z;
+
+        let DVec3 { x: u, y: v, z: w } = local.to_array().map(fade).into();
+        let corner = |hash: u8, offset: DVec3| gradient(hash, local - offset);
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    corner(p[aa], DVec3::ZERO),
+                    corner(p[ba], DVec3::new(1.0, 0.0, 0.0)),
+                ),
+                lerp(
+                    u,
+                    corner(p[ab], DVec3::new(0.0, 1.0, 0.0)),
+                    corner(p[bb], DVec3::new(1.0, 1.0, 0.0)),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    corner(p[aa + 1], DVec3::new(0.0, 0.0, 1.0)),
+                    corner(p[ba + 1], DVec3::new(1.0, 0.0, 1.0)),
+                ),
+                lerp(
+                    u,
+                    corner(p[ab + 1], DVec3::new(0.0, 1.0, 1.0)),
+                    corner(p[bb + 1], DVec3::ONE),
+                ),
+            ),
+        )
+        .clamp(-1.0, 1.0)
+    }
+}
+
+/// Fractal Brownian motion: octaves of [`Perlin`] noise at increasing frequencies and decreasing
+/// amplitudes.
+#[derive(Debug, Clone)]
+pub struct Fbm {
+    noise: Perlin,
+    pub octaves: u32,
+    /// Frequency of the first octave.
+    pub frequency: f64,
+    /// Amplitude of the first octave.
+    pub amplitude: f64,
+    /// Frequency ratio between successive octaves.
+    pub lacunarity: f64,
+    /// Amplitude ratio between successive octaves.
+    pub persistence: f64,
+}
+
+impl Fbm {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            octaves: 6,
+            frequency: 1.0,
+            amplitude: 1.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+
+    pub fn sample(&self, point: DVec3) -> f64 {
+        let mut frequency = self.frequency;
+        let mut amplitude = self.amplitude;
+        let mut sum = 0.0;
+        for octave in 0..self.octaves {
+            // Offsets each octave so that the lattice points of octaves do not line up
+            let offset = DVec3::splat(octave as f64 * 17.31);
+            sum += amplitude * self.noise.sample(point * frequency + offset);
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        sum
+    }
+}
+
+/// Small deterministic random number generator, so that seeds give the same results everywhere.
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform on the unit sphere.
+    pub fn next_direction(&mut self) -> DVec3 {
+        let y = 2.0 * self.next_f64() - 1.0;
+        let angle = std::f64::consts::TAU * self.next_f64();
+        let r = (1.0 - y * y).sqrt();
+        DVec3::new(r * angle.cos(), y, r * angle.sin())
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn gradient(hash: u8, offset: DVec3) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { offset.x } else { offset.y };
+    let v = match h {
+        0..4 => offset.y,
+        12 | 14 => offset.x,
+        _ => offset.z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_seeded() {
+        let points = (0..64)
+            .map(|i| {
+                DVec3::new(
+                    i as f64 * 0.37,
+                    i as f64 * 0.11 - 3.0,
+                    5.5 - i as f64 * 0.23,
+                )
+            })
+            .collect::<Vec<_>>();
+        let sample = |seed| {
+            let noise = Perlin::new(seed);
+            points.iter().map(|p| noise.sample(*p)).collect::<Vec<_>>()
+        };
+        assert_eq!(sample(1), sample(1));
+        assert_ne!(sample(1), sample(2));
+        assert!(sample(3).iter().all(|v| (-1.0..=1.0).contains(v)));
+        assert!(sample(3).iter().any(|v| v.abs() > 0.05));
+        assert_eq!(Perlin::new(4).sample(DVec3::new(3.0, -2.0, 7.0)), 0.0);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::mjd::Mjd;
+use crate::time_scale::TimeScale;
 use bevy::math::{dvec2, dvec3, DMat3, DVec2, DVec3};
 use bevy::prelude::*;
 use big_space::precision::GridPrecision;
@@ -29,21 +30,12 @@ impl<Prec: GridPrecision> Plugin for OrbitPlugin<Prec> {
             .insert_resource(DrawOrbits(self.draw_orbits))
             .add_systems(
                 Update,
-                update_positions::<Prec>.run_if(resource_exists::<Time<Mjd>>.and(mjd_valid)),
-            )
-            .add_systems(
-                PostUpdate,
-                draw_orbits.after(TransformSystem::TransformPropagate),
-            );
-        if self.draw_orbits {
-            app.add_systems(
-                PostUpdate,
-                draw_orbits.after(TransformSystem::TransformPropagate),
+                update_positions::<Prec>.run_if(resource_exists::<Time<Mjd>>),
             );
-        }
     }
 }
 
+/// Whether orbit lines are shown.
 #[derive(Resource, Deref, DerefMut, Debug)]
 pub struct DrawOrbits(bool);
 
@@ -102,9 +94,9 @@ impl From<KeplerElements> for Orbit {
 
 impl Orbit {
     #[inline]
-    pub fn point_on_orbit(&self, t: Mjd) -> Option<DVec3> {
-        let pt = self.point_on_orbit_local(t)?;
-        Some(self.get_rotation_matrix() * dvec3(pt.x, 0.0, pt.y))
+    pub fn point_on_orbit(&self, t: Mjd) -> DVec3 {
+        let pt = self.point_on_orbit_local(t);
+        self.get_rotation_matrix() * dvec3(pt.x, 0.0, pt.y)
     }
 
     #[inline]
@@ -114,12 +106,17 @@ impl Orbit {
     }
 
     #[inline]
-    pub fn point_on_orbit_local(&self, t: Mjd) -> Option<DVec2> {
-        let seconds_since_epoch = (t.mjd()? - self.elements.epoch.mjd()?) * 86400.0;
+    pub fn point_on_orbit_local(&self, t: Mjd) -> DVec2 {
+        self.position_from_angle_local(self.true_anomaly_at(t))
+    }
+
+    /// Angle between the periapsis and the body at the given date.
+    #[inline]
+    pub fn true_anomaly_at(&self, t: Mjd) -> Real {
+        let seconds_since_epoch = t.seconds_since(&self.elements.epoch, TimeScale::Tdb);
         let mean_anomaly = self.mean_anomaly(seconds_since_epoch);
         let eccentric_anomaly = self.eccentric_anomaly(mean_anomaly);
-        let true_anomaly = self.true_anomaly(eccentric_anomaly);
-        Some(self.position_from_angle_local(true_anomaly))
+        self.true_anomaly(eccentric_anomaly)
     }
 
     #[inline]
@@ -132,7 +129,8 @@ impl Orbit {
 
     #[inline]
     pub fn mean_anomaly(&self, seconds_since_epoch: Real) -> Real {
-        self.mean_angular_motion * seconds_since_epoch
+        // Wrap around so that the solver starts close to the solution at high clock rates
+        (self.mean_angular_motion * seconds_since_epoch).rem_euclid(consts::TAU)
     }
 
     #[inline]
@@ -156,15 +154,20 @@ impl Orbit {
         2.0 * (a * b * c).atan()
     }
 
+    /// Distance to the focus at the given true anomaly.
     #[inline]
-    fn heliocentric_distance(&self, eccentric_anomaly: Real) -> Real {
-        self.elements.semi_major_axis * (1.0 - self.elements.eccentricity * eccentric_anomaly.cos())
+    pub fn heliocentric_distance(&self, true_anomaly: Real) -> Real {
+        let e = self.elements.eccentricity;
+        self.elements.semi_major_axis * (1.0 - e * e) / (1.0 + e * true_anomaly.cos())
     }
 
+    /// Rotation from the orbital plane, with the periapsis along X and the body moving towards Z,
+    /// to the parent frame.
     #[inline]
     fn get_rotation_matrix(&self) -> DMat3 {
         DMat3::from_rotation_y(self.elements.longitude_of_ascending_node)
             * DMat3::from_rotation_x(self.elements.inclination)
+            * DMat3::from_rotation_y(-self.elements.argument_of_periapsis)
     }
 }
 
@@ -186,10 +189,6 @@ impl RootEquation for KeplerEquation {
     }
 }
 
-fn mjd_valid(mjd: Res<Time<Mjd>>) -> bool {
-    mjd.context().mjd().is_some()
-}
-
 fn update_positions<Prec: GridPrecision>(
     time: Res<Time<Mjd>>,
     mut q: Query<(
@@ -203,46 +202,12 @@ fn update_positions<Prec: GridPrecision>(
 
     q.par_iter_mut()
         .for_each(|(mut transform, mut grid, frame, orbit)| {
-            let Some(pos) = orbit.point_on_orbit(t) else {
-                error!("Could not compute position on orbit at time {}", t);
-                return;
-            };
-            let (new_grid, pos) = frame.translation_to_grid(pos);
+            let (new_grid, pos) = frame.translation_to_grid(orbit.point_on_orbit(t));
             *grid = new_grid;
             transform.translation = pos;
         });
 }
 
-fn draw_orbits(
-    mut g: Gizmos,
-    q: Query<(&Parent, &Orbit)>,
-    q_transform: Query<&GlobalTransform>,
-    draw_orbits: Res<DrawOrbits>,
-) {
-    if !**draw_orbits {
-        return;
-    }
-    for (parent, Orbit { elements, .. }) in &mut q.iter() {
-        let Ok(transform) = q_transform.get(**parent).map(|t| t.compute_transform()) else {
-            continue;
-        };
-        let position = transform.translation;
-        let rotation = transform.rotation
-            * Quat::from_rotation_y(elements.longitude_of_ascending_node as _)
-            * Quat::from_rotation_x((consts::FRAC_PI_2 + elements.inclination) as f32);
-        let half_size = Vec2::new(
-            elements.semi_major_axis as f32,
-            (elements.semi_major_axis * (1.0 - elements.eccentricity.powi(2)).sqrt()) as f32,
-        );
-        g.ellipse(
-            Isometry3d::new(position, rotation),
-            half_size,
-            Color::srgb(1.0, 1.0, 0.0),
-        )
-        .resolution(64);
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,12 +245,26 @@ mod tests {
     fn point_on_orbit_calculates_correctly() {
         let mjd = Mjd::zero();
         let orbit = orbit();
-        let point = orbit.point_on_orbit(mjd).unwrap();
+        let point = orbit.point_on_orbit(mjd);
         assert_abs_diff_eq!(point.x, -1.0e11, epsilon = 1e6);
         assert_abs_diff_eq!(point.y, 0.0, epsilon = 1e6);
         assert_abs_diff_eq!(point.z, 0.0, epsilon = 1e6);
     }
 
+    #[test]
+    fn periapsis_follows_argument_of_periapsis() {
+        let mut elements = orbit().elements;
+        elements.eccentricity = 0.5;
+        elements.argument_of_periapsis = consts::FRAC_PI_2;
+        let orbit = Orbit::from(elements);
+        let periapsis = orbit.point_from_angle(0.0);
+        assert_abs_diff_eq!(periapsis.length(), 0.5e11, epsilon = 1e3);
+        // A quarter turn along the direction of motion from the ascending node
+        assert_abs_diff_eq!(periapsis.normalize().z, 1.0, epsilon = 1e-9);
+        let apoapsis = orbit.point_from_angle(consts::PI);
+        assert_abs_diff_eq!(apoapsis.length(), 1.5e11, epsilon = 1e3);
+    }
+
     #[test]
     fn point_from_true_anomaly_calculates_correctly() {
         let orbit = orbit();
@@ -0,0 +1,317 @@
+use crate::mjd::Mjd;
+use crate::orbit::{DrawOrbits, Orbit};
+use crate::scene::components::SceneCamera;
+use bevy::asset::embedded_asset;
+use bevy::math::DVec3;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::{
+    Indices, MeshVertexAttribute, MeshVertexBufferLayoutRef, PrimitiveTopology,
+};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError,
+    VertexFormat,
+};
+use bevy::render::view::VisibilitySystems;
+use std::f64::consts::TAU;
+
+pub struct OrbitLinePlugin;
+
+impl Plugin for OrbitLinePlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/orbit_line.wgsl");
+        app.add_plugins(MaterialPlugin::<OrbitLineMaterial>::default())
+            .register_type::<OrbitLine>()
+            .add_systems(
+                PostUpdate,
+                update_orbit_lines
+                    .run_if(resource_exists::<Time<Mjd>>)
+                    .after(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::CheckVisibility),
+            );
+    }
+}
+
+/// Other end of the segment a line vertex belongs to.
+pub const ATTRIBUTE_SEGMENT_END: MeshVertexAttribute =
+    MeshVertexAttribute::new("OrbitLine_SegmentEnd", 988_540_917, VertexFormat::Float32x3);
+/// Side of the line, -1 or 1, whether the vertex is at the end of its segment, 0 or 1, and how
+/// bright the line is at the vertex.
+pub const ATTRIBUTE_LINE: MeshVertexAttribute =
+    MeshVertexAttribute::new("OrbitLine_Line", 988_540_918, VertexFormat::Float32x3);
+
+/// Angular error allowed between the orbit and its segments, seen from the camera, in radians.
+const ANGULAR_TOLERANCE: f64 = 1e-4;
+/// Bounds on the angle covered by a segment, around the focus of the orbit.
+const MIN_STEP: f64 = 1e-7;
+const MAX_STEP: f64 = TAU / 256.0;
+const MAX_POINTS: usize = 8192;
+
+/// Line along the orbit of its parent, whose mesh is regenerated around the camera whenever the
+/// camera or the body move noticeably.
+#[derive(Debug, Clone, Copy, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct OrbitLine {
+    /// Viewpoint the mesh was last generated from.
+    #[reflect(ignore)]
+    drawn: Option<LineViewpoint>,
+}
+
+/// Positions of the camera and of the body a line mesh was generated for, in the frame of the
+/// line, to skip regenerating the mesh while neither moved noticeably.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LineViewpoint {
+    pub camera: DVec3,
+    pub body: DVec3,
+}
+
+impl LineViewpoint {
+    /// Whether a mesh generated from this viewpoint is still accurate from `other`, within
+    /// [`ANGULAR_TOLERANCE`] as seen from the camera.
+    pub fn is_close_to(&self, other: &Self) -> bool {
+        let tolerance = ANGULAR_TOLERANCE * other.camera.distance(other.body);
+        self.camera.distance(other.camera) <= tolerance
+            && self.body.distance(other.body) <= tolerance
+    }
+}
+
+#[derive(Debug, Clone, Copy, ShaderType, Reflect)]
+pub struct OrbitLineMaterialUniform {
+    pub color: LinearRgba,
+    /// Width of the line, in pixels.
+    pub width: f32,
+    /// Opacity of the line just ahead of the body, where it has faded the most.
+    pub min_alpha: f32,
+}
+
+/// Line of constant width on screen, drawn on the quads of [`orbit_line_mesh`].
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+pub struct OrbitLineMaterial {
+    #[uniform(0)]
+    pub uniform: OrbitLineMaterialUniform,
+}
+
+impl OrbitLineMaterial {
+    pub fn new(color: LinearRgba) -> Self {
+        Self {
+            uniform: OrbitLineMaterialUniform {
+                color,
+                width: 1.5,
+                min_alpha: 0.1,
+            },
+        }
+    }
+}
+
+impl Material for OrbitLineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/orbit_line.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/orbit_line.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_SEGMENT_END.at_shader_location(1),
+            ATTRIBUTE_LINE.at_shader_location(2),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// Points along the orbit, relative to its focus, with how far behind the body they are as a
+/// fraction of the orbit. They start at the body and go backwards around the whole orbit, closer
+/// together where the camera is near so that the line stays smooth from up close.
+pub fn orbit_line_points(orbit: &Orbit, true_anomaly: f64, camera: DVec3) -> Vec<(DVec3, f64)> {
+    let mut points = Vec::new();
+    let mut behind = 0.0;
+    while points.len() < MAX_POINTS {
+        let anomaly = true_anomaly - behind;
+        let point = orbit.point_from_angle(anomaly);
+        points.push((point, behind / TAU));
+        if behind >= TAU {
+            break;
+        }
+        // The sagitta of a segment is about r * step² / 8, and has to stay under the tolerance
+        // once seen from the camera
+        let radius = orbit.heliocentric_distance(anomaly);
+        let distance = point.distance(camera);
+        let step = (8.0 * ANGULAR_TOLERANCE * distance / radius)
+            .sqrt()
+            .clamp(MIN_STEP, MAX_STEP);
+        behind = (behind + step).min(TAU);
+    }
+    points
+}
+
+/// Mesh of a line through the points, given with their brightness, as a quad per segment whose
+/// vertices are moved apart to the width of the line in the vertex shader.
+pub fn orbit_line_mesh(points: &[(Vec3, f32)]) -> Mesh {
+    let segments = points.len().saturating_sub(1).max(1);
+    let mut positions = Vec::with_capacity(segments * 4);
+    let mut ends = Vec::with_capacity(segments * 4);
+    let mut line = Vec::with_capacity(segments * 4);
+    let mut indices = Vec::with_capacity(segments * 6);
+    for (i, pair) in points.windows(2).enumerate() {
+        let [(start, start_fade), (end, end_fade)] = [pair[0], pair[1]];
+        for (is_end, fade) in [(0.0, start_fade), (1.0, end_fade)] {
+            for side in [-1.0, 1.0] {
+                positions.push(start);
+                ends.push(end);
+                line.push([side, is_end, fade]);
+            }
+        }
+        let base = 4 * i as u32;
+        indices.extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+    }
+    if positions.is_empty() {
+        // Keeps the vertex layout of the material valid while there is nothing to draw
+        positions = vec![Vec3::ZERO; 4];
+        ends = vec![Vec3::ZERO; 4];
+        line = vec![[0.0; 3]; 4];
+        indices = vec![0, 1, 2, 2, 1, 3];
+    }
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(ATTRIBUTE_SEGMENT_END, ends)
+    .with_inserted_attribute(ATTRIBUTE_LINE, line)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Regenerates orbit lines around the camera. Positions are computed in double precision relative
+/// to the camera, whose position relative to the focus of each orbit is taken from the body, and
+/// the line is moved to the camera. Meshes are kept while the camera and the body stay put.
+#[allow(clippy::type_complexity)]
+fn update_orbit_lines(
+    time: Res<Time<Mjd>>,
+    draw_orbits: Res<DrawOrbits>,
+    q_camera: Query<&GlobalTransform, (With<SceneCamera>, Without<OrbitLine>)>,
+    q_orbits: Query<(Ref<Orbit>, &GlobalTransform), Without<OrbitLine>>,
+    mut q_lines: Query<(
+        &Parent,
+        &mut OrbitLine,
+        &Mesh3d,
+        &mut GlobalTransform,
+        &mut Visibility,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let camera_position = camera.translation();
+    let t = *time.context();
+    for (parent, mut line, mesh, mut transform, mut visibility) in &mut q_lines {
+        let new_visibility = if **draw_orbits {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(new_visibility);
+        if !**draw_orbits {
+            continue;
+        }
+        let Ok((orbit, body_transform)) = q_orbits.get(parent.get()) else {
+            continue;
+        };
+        *transform = GlobalTransform::from_translation(camera_position);
+        let true_anomaly = orbit.true_anomaly_at(t);
+        let body = orbit.point_from_angle(true_anomaly);
+        let camera = body + (camera_position - body_transform.translation()).as_dvec3();
+        let viewpoint = LineViewpoint { camera, body };
+        if !orbit.is_changed()
+            && line
+                .drawn
+                .is_some_and(|drawn| drawn.is_close_to(&viewpoint))
+        {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+        line.drawn = Some(viewpoint);
+        let points = orbit_line_points(&orbit, true_anomaly, camera)
+            .into_iter()
+            .map(|(point, behind)| ((point - camera).as_vec3(), 1.0 - behind as f32))
+            .collect::<Vec<_>>();
+        *mesh = orbit_line_mesh(&points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::KeplerElements;
+
+    fn orbit() -> Orbit {
+        KeplerElements {
+            epoch: Mjd::j2000(),
+            period: 3.15576e7,
+            semi_major_axis: 1.5e11,
+            eccentricity: 0.0167,
+            inclination: 0.0,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 1.8,
+        }
+        .into()
+    }
+
+    #[test]
+    fn points_go_around_the_orbit_from_the_body() {
+        let orbit = orbit();
+        let camera = orbit.point_from_angle(1.0) + DVec3::new(0.0, 1e7, 0.0);
+        let points = orbit_line_points(&orbit, 1.0, camera);
+        assert_eq!(points.first().unwrap().0, orbit.point_from_angle(1.0));
+        assert_eq!(points.first().unwrap().1, 0.0);
+        assert_eq!(points.last().unwrap().1, 1.0);
+        assert!(points.len() < MAX_POINTS);
+    }
+
+    #[test]
+    fn points_are_denser_near_the_camera() {
+        let orbit = orbit();
+        let camera = orbit.point_from_angle(1.0) + DVec3::new(0.0, 1e7, 0.0);
+        let points = orbit_line_points(&orbit, 1.0, camera);
+        let near = points[0].0.distance(points[1].0);
+        let far = points
+            .windows(2)
+            .map(|pair| pair[0].0.distance(pair[1].0))
+            .fold(0.0, f64::max);
+        assert!(near * 50.0 < far, "{near} {far}");
+        // From afar, the orbit is drawn with the coarsest segments
+        let far_points = orbit_line_points(&orbit, 1.0, DVec3::new(0.0, 1e14, 0.0));
+        assert!(far_points.len() <= 258, "{}", far_points.len());
+    }
+
+    #[test]
+    fn viewpoints_allow_small_moves() {
+        let drawn = LineViewpoint {
+            camera: DVec3::new(0.0, 1e7, 0.0),
+            body: DVec3::ZERO,
+        };
+        let moved = |camera: f64| LineViewpoint {
+            camera: DVec3::new(camera, 1e7, 0.0),
+            ..drawn
+        };
+        assert!(drawn.is_close_to(&moved(100.0)));
+        assert!(!drawn.is_close_to(&moved(1e4)));
+    }
+}
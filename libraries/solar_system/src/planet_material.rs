@@ -0,0 +1,246 @@
+use crate::body::BodyShape;
+use crate::scene::components::SceneCamera;
+use crate::sun::{self, Sun, SunPosition, SunRadius};
+use bevy::asset::embedded_asset;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef, ShaderType};
+
+/// Material of planetary bodies: the standard PBR material, with night lights on the dark side,
+/// specular reflections restricted to oceans, and the shadows of rings and of other bodies.
+pub type PlanetStandardMaterial = ExtendedMaterial<StandardMaterial, PlanetMaterialExtension>;
+
+pub struct PlanetMaterialPlugin;
+
+impl Plugin for PlanetMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/planet.wgsl");
+        app.add_plugins(MaterialPlugin::<PlanetStandardMaterial>::default())
+            .add_systems(
+                Update,
+                (
+                    update_sun_position
+                        .after(sun::sun_lighting)
+                        .run_if(resource_changed::<SunPosition>),
+                    update_occluders.after(sun::sun_lighting),
+                ),
+            );
+    }
+}
+
+/// Maximum number of bodies casting eclipse shadows at once. The closest ones to the camera are
+/// kept.
+pub const MAX_OCCLUDERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, ShaderType, Reflect)]
+pub struct PlanetMaterialUniform {
+    /// World-space position of the sun, kept up to date by [`PlanetMaterialPlugin`].
+    pub sun_position: Vec3,
+    /// Half-width of the twilight band around the terminator, as the sine of the sun elevation.
+    pub twilight_width: f32,
+    pub night_lights_power: f32,
+    pub ocean_roughness: f32,
+    pub ocean_reflectance: f32,
+    pub flags: u32,
+    /// World-space center and normal of the ring plane, and its extent, when the body has rings.
+    pub ring_center: Vec3,
+    pub ring_inner_radius: f32,
+    pub ring_normal: Vec3,
+    pub ring_outer_radius: f32,
+    /// Radius of the sun, for the penumbra of eclipses.
+    pub sun_radius: f32,
+    pub occluder_count: u32,
+    /// World-space center and radius of the bodies casting eclipse shadows, kept up to date by
+    /// [`PlanetMaterialPlugin`]. Only the first `occluder_count` are set.
+    pub occluders: [Vec4; MAX_OCCLUDERS],
+}
+
+impl Default for PlanetMaterialUniform {
+    fn default() -> Self {
+        Self {
+            sun_position: Vec3::ZERO,
+            twilight_width: PlanetMaterialExtension::DEFAULT_TWILIGHT_ANGLE_DEG
+                .to_radians()
+                .sin(),
+            night_lights_power: 1.0,
+            ocean_roughness: 0.1,
+            ocean_reflectance: 0.5,
+            flags: 0,
+            ring_center: Vec3::ZERO,
+            ring_inner_radius: 0.0,
+            ring_normal: Vec3::Y,
+            ring_outer_radius: 0.0,
+            sun_radius: 0.0,
+            occluder_count: 0,
+            occluders: [Vec4::ZERO; MAX_OCCLUDERS],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Asset, AsBindGroup, Reflect)]
+pub struct PlanetMaterialExtension {
+    #[uniform(100)]
+    pub uniform: PlanetMaterialUniform,
+    #[texture(101)]
+    #[sampler(102)]
+    pub night_lights: Option<Handle<Image>>,
+    /// Mask of the oceans in the red channel.
+    #[texture(103)]
+    #[sampler(104)]
+    pub ocean_mask: Option<Handle<Image>>,
+    /// Radial opacity of the rings in the alpha channel, to shade the body from them.
+    #[texture(105)]
+    #[sampler(106)]
+    pub ring_texture: Option<Handle<Image>>,
+}
+
+impl PlanetMaterialExtension {
+    pub const FLAG_NIGHT_LIGHTS: u32 = 1;
+    pub const FLAG_OCEAN_MASK: u32 = 2;
+    pub const FLAG_RINGS: u32 = 4;
+    /// Sun elevation below which night lights are fully on, and above which they are fully off.
+    pub const DEFAULT_TWILIGHT_ANGLE_DEG: f32 = 6.0;
+
+    pub fn with_night_lights(mut self, texture: Handle<Image>, power: f32) -> Self {
+        self.night_lights = Some(texture);
+        self.uniform.night_lights_power = power;
+        self.uniform.flags |= Self::FLAG_NIGHT_LIGHTS;
+        self
+    }
+
+    pub fn with_ocean_mask(
+        mut self,
+        texture: Handle<Image>,
+        roughness: f32,
+        reflectance: f32,
+    ) -> Self {
+        self.ocean_mask = Some(texture);
+        self.uniform.ocean_roughness = roughness;
+        self.uniform.ocean_reflectance = reflectance;
+        self.uniform.flags |= Self::FLAG_OCEAN_MASK;
+        self
+    }
+
+    /// Sets the half-width of the twilight band, as the sun elevation in degrees.
+    pub fn with_twilight_angle(mut self, degrees: f32) -> Self {
+        self.uniform.twilight_width = degrees.to_radians().sin().max(1e-4);
+        self
+    }
+}
+
+impl MaterialExtension for PlanetMaterialExtension {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/planet.wgsl".into()
+    }
+}
+
+fn update_sun_position(
+    sun_position: Res<SunPosition>,
+    mut materials: ResMut<Assets<PlanetStandardMaterial>>,
+) {
+    update_uniforms(&mut materials, |uniform| {
+        uniform.sun_position = sun_position.0;
+    });
+}
+
+/// Applies `update` to the uniform of a planet material, only taking mutable access to it, and
+/// thus re-uploading it, when its uniform actually changes.
+pub(crate) fn update_uniform(
+    materials: &mut Assets<PlanetStandardMaterial>,
+    id: impl Into<AssetId<PlanetStandardMaterial>>,
+    update: impl FnOnce(&mut PlanetMaterialUniform),
+) {
+    let id = id.into();
+    let Some(material) = materials.get(id) else {
+        return;
+    };
+    let mut uniform = material.extension.uniform;
+    update(&mut uniform);
+    if uniform != material.extension.uniform {
+        if let Some(material) = materials.get_mut(id) {
+            material.extension.uniform = uniform;
+        }
+    }
+}
+
+/// Applies `update` to the uniform of every planet material, only taking mutable access to, and
+/// thus re-uploading, the materials whose uniform actually changes.
+fn update_uniforms(
+    materials: &mut Assets<PlanetStandardMaterial>,
+    update: impl Fn(&mut PlanetMaterialUniform),
+) {
+    let changed = materials
+        .iter()
+        .filter_map(|(id, material)| {
+            let mut uniform = material.extension.uniform;
+            update(&mut uniform);
+            (uniform != material.extension.uniform).then_some((id, uniform))
+        })
+        .collect::<Vec<_>>();
+    for (id, uniform) in changed {
+        if let Some(material) = materials.get_mut(id) {
+            material.extension.uniform = uniform;
+        }
+    }
+}
+
+/// Bounding spheres of the bodies closest to `camera`, as the center and radius of each, up to
+/// [`MAX_OCCLUDERS`].
+fn nearest_occluders(
+    camera: Vec3,
+    spheres: impl IntoIterator<Item = Vec4>,
+) -> ([Vec4; MAX_OCCLUDERS], u32) {
+    let mut spheres = spheres.into_iter().collect::<Vec<_>>();
+    // Distance to the surface, so that large bodies are not dropped for close small ones
+    spheres.sort_by(|a, b| {
+        let a = a.xyz().distance(camera) - a.w;
+        let b = b.xyz().distance(camera) - b.w;
+        a.total_cmp(&b)
+    });
+    let mut occluders = [Vec4::ZERO; MAX_OCCLUDERS];
+    let count = spheres.len().min(MAX_OCCLUDERS);
+    occluders[..count].copy_from_slice(&spheres[..count]);
+    (occluders, count as u32)
+}
+
+/// Shares the bodies closest to the camera with all planet materials, which darken where these
+/// hide the sun. Stars are not occluders, as they are the light sources.
+fn update_occluders(
+    sun_radius: Res<SunRadius>,
+    q_camera: Query<&GlobalTransform, With<SceneCamera>>,
+    q_shapes: Query<(&GlobalTransform, &BodyShape, &Parent)>,
+    q_suns: Query<(), With<Sun>>,
+    mut materials: ResMut<Assets<PlanetStandardMaterial>>,
+) {
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let spheres = q_shapes
+        .iter()
+        .filter(|(_, _, parent)| !q_suns.contains(parent.get()))
+        .map(|(transform, shape, _)| transform.translation().extend(shape.radii.max_element()));
+    let (occluders, occluder_count) = nearest_occluders(camera.translation(), spheres);
+    update_uniforms(&mut materials, |uniform| {
+        uniform.sun_radius = sun_radius.0;
+        uniform.occluders = occluders;
+        uniform.occluder_count = occluder_count;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_occluders_are_kept() {
+        let far = (0..MAX_OCCLUDERS).map(|i| Vec4::new(1e9 + i as f32, 0.0, 0.0, 1.0));
+        let moon = Vec4::new(3.8e8, 0.0, 0.0, 1.7e6);
+        let (occluders, count) = nearest_occluders(Vec3::ZERO, far.chain([moon]));
+        assert_eq!(count as usize, MAX_OCCLUDERS);
+        assert_eq!(occluders[0], moon);
+
+        let (occluders, count) = nearest_occluders(Vec3::ZERO, [moon]);
+        assert_eq!(count, 1);
+        assert_eq!(occluders[1], Vec4::ZERO);
+    }
+}
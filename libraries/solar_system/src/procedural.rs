@@ -0,0 +1,371 @@
+use crate::noise::{Fbm, Perlin, SplitMix64};
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+/// Seeded description of a generated surface, for bodies without texture data. The same seed
+/// always generates the same textures.
+#[derive(Debug, Clone)]
+pub struct ProceduralSurface {
+    pub seed: u32,
+    /// Width of the generated equirectangular textures, their height being half of it.
+    pub resolution: u32,
+    pub preset: SurfacePreset,
+}
+
+#[derive(Debug, Clone)]
+pub enum SurfacePreset {
+    /// Cratered rock, with crater sizes following a power law.
+    Rocky {
+        /// Crater density, 1 being saturated.
+        craters: f32,
+        low_color: Srgba,
+        high_color: Srgba,
+    },
+    /// Latitudinal bands warped by turbulence, with oval storms.
+    GasGiant {
+        bands: u32,
+        turbulence: f32,
+        storms: u32,
+        /// Colors cycled through by the bands.
+        colors: Vec<Srgba>,
+        storm_color: Srgba,
+    },
+    /// Smooth ice crossed by cracks.
+    Icy {
+        /// Visibility of the cracks, in `[0, 1]`.
+        cracks: f32,
+        color: Srgba,
+        crack_color: Srgba,
+    },
+    /// Dark crust with lakes of lava, which also make up the emissive texture.
+    Lava {
+        /// Approximate fraction of the surface covered with lava.
+        coverage: f32,
+        rock_color: Srgba,
+        lava_color: Srgba,
+    },
+}
+
+/// Textures generated for a [`ProceduralSurface`].
+#[derive(Debug, Clone)]
+pub struct GeneratedSurface {
+    pub color: Image,
+    pub emissive: Option<Image>,
+}
+
+impl ProceduralSurface {
+    pub fn generate(&self) -> GeneratedSurface {
+        let width = self.resolution.max(4);
+        let height = width / 2;
+        let mut rng = SplitMix64::new(self.seed as u64);
+        match &self.preset {
+            SurfacePreset::Rocky {
+                craters,
+                low_color,
+                high_color,
+            } => {
+                let fbm = fbm(self.seed, 4.0);
+                let shading = crater_shading(&mut rng, *craters, width, height);
+                let color = texels(width, height, |index, direction| {
+                    let t = 0.5 + 0.5 * fbm.sample(direction) as f32;
+                    let color = low_color.mix(high_color, t.clamp(0.0, 1.0));
+                    scale(color, 1.0 + shading[index])
+                });
+                GeneratedSurface {
+                    color: image(width, height, color),
+                    emissive: None,
+                }
+            }
+            SurfacePreset::GasGiant {
+                bands,
+                turbulence,
+                storms,
+                colors,
+                storm_color,
+            } => {
+                let fbm = fbm(self.seed, 1.0);
+                let perlin = Perlin::new(self.seed.wrapping_add(1));
+                let storms = (0..*storms)
+                    .map(|_| Storm::random(&mut rng))
+                    .collect::<Vec<_>>();
+                let bands = *bands as f64;
+                let color = texels(width, height, |_, direction| {
+                    // Stretching the noise along the latitude makes features flow along the bands
+                    let warp = fbm.sample(direction * DVec3::new(2.0, 12.0, 2.0));
+                    let latitude = direction.y.asin() + *turbulence as f64 * 0.1 * warp;
+                    let position = bands * (0.5 + 0.5 * latitude.sin())
+                        + 0.3 * perlin.sample(DVec3::new(latitude * bands, 0.5, 0.5));
+                    let mut color = band_color(colors, position);
+                    for storm in &storms {
+                        color = storm.apply(color, *storm_color, direction);
+                    }
+                    color
+                });
+                GeneratedSurface {
+                    color: image(width, height, color),
+                    emissive: None,
+                }
+            }
+            SurfacePreset::Icy {
+                cracks,
+                color,
+                crack_color,
+            } => {
+                let fbm = fbm(self.seed, 3.0);
+                let perlin = Perlin::new(self.seed.wrapping_add(1));
+                let color = texels(width, height, |_, direction| {
+                    let t = 0.5 + 0.5 * fbm.sample(direction) as f32;
+                    let crack = ridge(&perlin, direction * 2.0)
+                        + 0.5 * ridge(&perlin, direction * 5.0 + DVec3::splat(7.3));
+                    let ice = scale(*color, 0.9 + 0.1 * t);
+                    ice.mix(crack_color, (crack * *cracks * 2.0).clamp(0.0, 1.0))
+                });
+                GeneratedSurface {
+                    color: image(width, height, color),
+                    emissive: None,
+                }
+            }
+            SurfacePreset::Lava {
+                coverage,
+                rock_color,
+                lava_color,
+            } => {
+                let fbm = fbm(self.seed, 3.0);
+                // The fractal noise is roughly normally distributed, this maps the coverage to
+                // a threshold on it
+                let threshold = 0.5 + (coverage.clamp(0.0, 1.0) - 0.5) * 0.5;
+                let mut emissive = Vec::with_capacity((width * height) as usize);
+                let color = texels(width, height, |_, direction| {
+                    let t = 0.5 + 0.5 * fbm.sample(direction) as f32;
+                    let lava = 1.0 - smoothstep(threshold - 0.03, threshold + 0.03, t);
+                    emissive.push(scale(*lava_color, lava));
+                    scale(*rock_color, 0.6 + 0.4 * t).mix(lava_color, lava)
+                });
+                GeneratedSurface {
+                    color: image(width, height, color),
+                    emissive: Some(image(width, height, emissive)),
+                }
+            }
+        }
+    }
+}
+
+/// Oval storm, in the manner of Jupiter's Great Red Spot.
+struct Storm {
+    latitude: f64,
+    longitude: f64,
+    /// Angular semi-axes along the longitude and the latitude.
+    size: (f64, f64),
+}
+
+impl Storm {
+    fn random(rng: &mut SplitMix64) -> Self {
+        let width = 0.05 + 0.1 * rng.next_f64();
+        Self {
+            latitude: (rng.next_f64() - 0.5) * FRAC_PI_2 * 1.3,
+            longitude: rng.next_f64() * TAU - PI,
+            size: (width, width * 0.6),
+        }
+    }
+
+    fn apply(&self, color: Srgba, storm_color: Srgba, direction: DVec3) -> Srgba {
+        let latitude = direction.y.asin();
+        let longitude = (-direction.z).atan2(direction.x);
+        let d_longitude = (longitude - self.longitude + PI).rem_euclid(TAU) - PI;
+        let x = d_longitude * self.latitude.cos() / self.size.0;
+        let y = (latitude - self.latitude) / self.size.1;
+        let distance = (x * x + y * y) as f32;
+        if distance >= 1.0 {
+            return color;
+        }
+        let swirl = scale(storm_color, 0.9 + 0.1 * (distance * 12.0).sin());
+        color.mix(&swirl, smoothstep(1.0, 0.6, distance))
+    }
+}
+
+fn fbm(seed: u32, frequency: f64) -> Fbm {
+    let mut fbm = Fbm::new(seed);
+    fbm.frequency = frequency;
+    fbm
+}
+
+/// Evaluates `texel` for every texel of an equirectangular texture, given its index and the
+/// direction it points to.
+fn texels(width: u32, height: u32, mut texel: impl FnMut(usize, DVec3) -> Srgba) -> Vec<Srgba> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .enumerate()
+        .map(|(index, (x, y))| texel(index, texel_direction(x, y, width, height)))
+        .collect()
+}
+
+/// Inverse of the equirectangular mapping of
+/// [`equirectangular_sphere`](crate::mesh::equirectangular_sphere).
+fn texel_direction(x: u32, y: u32, width: u32, height: u32) -> DVec3 {
+    let longitude = (x as f64 + 0.5) / width as f64 * TAU - PI;
+    let latitude = FRAC_PI_2 - (y as f64 + 0.5) / height as f64 * PI;
+    let (sin_latitude, cos_latitude) = latitude.sin_cos();
+    let (sin_longitude, cos_longitude) = longitude.sin_cos();
+    DVec3::new(
+        cos_latitude * cos_longitude,
+        sin_latitude,
+        -cos_latitude * sin_longitude,
+    )
+}
+
+/// Brightness offsets of randomly placed craters, stamped on the texels within their bounding
+/// box.
+fn crater_shading(rng: &mut SplitMix64, density: f32, width: u32, height: u32) -> Vec<f32> {
+    let mut shading = vec![0.0; (width * height) as usize];
+    let count = (density.max(0.0) * 400.0) as u32;
+    for _ in 0..count {
+        let center = rng.next_direction();
+        // Power law: many small craters for a few large ones
+        let radius = 0.005 + 0.2 * rng.next_f64().powi(4);
+        let extent = radius * 1.3;
+        let latitude = center.y.asin();
+        let row = |latitude: f64| ((FRAC_PI_2 - latitude) / PI * height as f64) as i64;
+        let rows = row((latitude + extent).min(FRAC_PI_2)).max(0)
+            ..=row((latitude - extent).max(-FRAC_PI_2)).min(height as i64 - 1);
+        let half_columns = if latitude.abs() + extent >= FRAC_PI_2 {
+            width as i64 / 2
+        } else {
+            ((extent / (latitude.abs() + extent).cos()) / TAU * width as f64).ceil() as i64 + 1
+        };
+        let longitude = (-center.z).atan2(center.x);
+        let column = ((longitude + PI) / TAU * width as f64) as i64;
+        for y in rows {
+            for x in column - half_columns..=column + half_columns {
+                let x = x.rem_euclid(width as i64) as u32;
+                let direction = texel_direction(x, y as u32, width, height);
+                let distance = direction.dot(center).clamp(-1.0, 1.0).acos() / radius;
+                shading[(y as u32 * width + x) as usize] += crater_profile(distance as f32);
+            }
+        }
+    }
+    shading
+}
+
+/// Brightness offset at a distance from the center of a crater, in crater radii: a shadowed
+/// floor and a bright rim.
+fn crater_profile(distance: f32) -> f32 {
+    let floor = -0.15 * (1.0 - smoothstep(0.0, 0.85, distance));
+    let rim = 0.2 * (-((distance - 1.0) / 0.12).powi(2)).exp();
+    floor + rim
+}
+
+/// Ridged noise, peaking along the zeros of the noise.
+fn ridge(noise: &Perlin, point: DVec3) -> f32 {
+    (1.0 - noise.sample(point).abs() as f32).powi(32)
+}
+
+fn band_color(colors: &[Srgba], position: f64) -> Srgba {
+    if colors.is_empty() {
+        return Srgba::WHITE;
+    }
+    let index = position.floor() as i64;
+    let from = colors[index.rem_euclid(colors.len() as i64) as usize];
+    let to = colors[(index + 1).rem_euclid(colors.len() as i64) as usize];
+    from.mix(
+        &to,
+        smoothstep(0.3, 0.7, (position - position.floor()) as f32),
+    )
+}
+
+fn scale(color: Srgba, factor: f32) -> Srgba {
+    Srgba::new(
+        color.red * factor,
+        color.green * factor,
+        color.blue * factor,
+        color.alpha,
+    )
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn image(width: u32, height: u32, texels: Vec<Srgba>) -> Image {
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texels
+            .into_iter()
+            .flat_map(|texel| texel.to_u8_array())
+            .collect(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presets() -> Vec<SurfacePreset> {
+        vec![
+            SurfacePreset::Rocky {
+                craters: 1.0,
+                low_color: Srgba::rgb(0.3, 0.3, 0.3),
+                high_color: Srgba::rgb(0.6, 0.6, 0.6),
+            },
+            SurfacePreset::GasGiant {
+                bands: 12,
+                turbulence: 0.5,
+                storms: 4,
+                colors: vec![Srgba::rgb(0.9, 0.8, 0.6), Srgba::rgb(0.7, 0.5, 0.3)],
+                storm_color: Srgba::rgb(0.8, 0.3, 0.2),
+            },
+            SurfacePreset::Icy {
+                cracks: 0.5,
+                color: Srgba::rgb(0.9, 0.9, 0.9),
+                crack_color: Srgba::rgb(0.5, 0.3, 0.2),
+            },
+            SurfacePreset::Lava {
+                coverage: 0.3,
+                rock_color: Srgba::rgb(0.1, 0.1, 0.1),
+                lava_color: Srgba::rgb(1.0, 0.4, 0.0),
+            },
+        ]
+    }
+
+    #[test]
+    fn seeds_are_deterministic() {
+        for preset in presets() {
+            let generate = |seed| {
+                ProceduralSurface {
+                    seed,
+                    resolution: 64,
+                    preset: preset.clone(),
+                }
+                .generate()
+            };
+            let (first, again, other) = (generate(7), generate(7), generate(8));
+            assert_eq!(first.color.size(), UVec2::new(64, 32));
+            assert_eq!(first.color.data, again.color.data);
+            assert_ne!(first.color.data, other.color.data);
+            assert_eq!(
+                first.emissive.map(|image| image.data),
+                again.emissive.map(|image| image.data)
+            );
+        }
+    }
+
+    #[test]
+    fn texel_directions_match_equirectangular_mapping() {
+        // North at the top, and the middle of the texture looking along +X
+        assert!(texel_direction(0, 0, 64, 32).y > 0.99);
+        assert!(texel_direction(0, 31, 64, 32).y < -0.99);
+        let middle = texel_direction(32, 16, 64, 32);
+        assert!((middle.length() - 1.0).abs() < 1e-12);
+        assert!(middle.x > 0.99);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::planet_material::{self, PlanetMaterialExtension, PlanetStandardMaterial};
+use crate::sun::{self, SunIlluminance, SunPosition};
+use bevy::asset::embedded_asset;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexBufferLayoutRef, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef, ShaderType,
+    SpecializedMeshPipelineError, TextureDimension, TextureFormat,
+};
+use std::f32::consts::TAU;
+
+pub struct RingsPlugin;
+
+impl Plugin for RingsPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/rings.wgsl");
+        app.add_plugins(MaterialPlugin::<RingMaterial>::default())
+            .register_type::<PlanetRings>()
+            .add_systems(
+                Update,
+                (unique_ring_shadow_materials, update_rings)
+                    .chain()
+                    .after(sun::sun_lighting),
+            );
+    }
+}
+
+/// Rings around a body, spawned as a sibling of its mesh with the same transform so that they
+/// lie in the body's equatorial plane.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct PlanetRings {
+    /// Entity holding the mesh of the ringed body.
+    pub body: Entity,
+    /// Inner radius, in body radii.
+    pub inner_radius: f32,
+    /// Outer radius, in body radii.
+    pub outer_radius: f32,
+}
+
+impl MapEntities for PlanetRings {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.body = entity_mapper.map_entity(self.body);
+    }
+}
+
+/// Marks ringed bodies whose material has been made their own, since it receives the shadow of
+/// their rings.
+#[derive(Component)]
+struct UniqueRingShadowMaterial;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ShaderType, Reflect)]
+pub struct RingMaterialUniform {
+    pub sun_position: Vec3,
+    pub illuminance: f32,
+    pub planet_center: Vec3,
+    pub planet_radius: f32,
+    pub ring_normal: Vec3,
+}
+
+/// Lit, translucent ring material. The shadow of the planet is computed analytically, as
+/// shadow cascades cannot cover planetary scales.
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+pub struct RingMaterial {
+    #[uniform(0)]
+    pub uniform: RingMaterialUniform,
+    /// Radial strip, from the inner edge on the left to the outer edge on the right, with the
+    /// opacity in the alpha channel.
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Handle<Image>,
+}
+
+impl Material for RingMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/rings.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// Creates a flat annulus in the XZ plane, between `inner_radius` and `outer_radius`. The `u`
+/// texture coordinate goes radially from the inner to the outer edge.
+pub fn ring_mesh(inner_radius: f32, outer_radius: f32, sectors: u32) -> Mesh {
+    let sectors = sectors.max(3);
+    let mut positions = Vec::with_capacity(2 * (sectors as usize + 1));
+    let mut uvs = Vec::with_capacity(2 * (sectors as usize + 1));
+    for sector in 0..=sectors {
+        let v = sector as f32 / sectors as f32;
+        let (sin, cos) = (v * TAU).sin_cos();
+        positions.push([inner_radius * cos, 0.0, -inner_radius * sin]);
+        positions.push([outer_radius * cos, 0.0, -outer_radius * sin]);
+        uvs.push([0.0, v]);
+        uvs.push([1.0, v]);
+    }
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let indices = (0..sectors)
+        .flat_map(|sector| {
+            let inner = 2 * sector;
+            [inner, inner + 1, inner + 2, inner + 2, inner + 1, inner + 3]
+        })
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Samples a gradient given as (position, color) stops, sorted by position in `[0, 1]`.
+pub fn sample_gradient(stops: &[(f32, Srgba)], position: f32) -> Srgba {
+    let Some(&(first_position, first)) = stops.first() else {
+        return Srgba::WHITE;
+    };
+    if position <= first_position {
+        return first;
+    }
+    for window in stops.windows(2) {
+        let [(start, from), (end, to)] = [window[0], window[1]];
+        if position <= end {
+            let t = if end > start {
+                (position - start) / (end - start)
+            } else {
+                1.0
+            };
+            return from.mix(&to, t);
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// Bakes a gradient into a one pixel high radial strip texture.
+pub fn gradient_image(stops: &[(f32, Srgba)], width: u32) -> Image {
+    let data = (0..width)
+        .flat_map(|x| {
+            let position = (x as f32 + 0.5) / width as f32;
+            sample_gradient(stops, position).to_u8_array()
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Gives ringed bodies their own copy of their material, once it is loaded, so that the ring
+/// shadow does not leak onto other bodies sharing it.
+fn unique_ring_shadow_materials(
+    mut commands: Commands,
+    q_rings: Query<(&PlanetRings, &MeshMaterial3d<RingMaterial>)>,
+    mut q_bodies: Query<
+        &mut MeshMaterial3d<PlanetStandardMaterial>,
+        Without<UniqueRingShadowMaterial>,
+    >,
+    ring_materials: Res<Assets<RingMaterial>>,
+    mut materials: ResMut<Assets<PlanetStandardMaterial>>,
+) {
+    for (rings, ring_material) in &q_rings {
+        let Ok(mut body_material) = q_bodies.get_mut(rings.body) else {
+            continue;
+        };
+        let (Some(material), Some(ring_material)) = (
+            materials.get(&body_material.0),
+            ring_materials.get(&ring_material.0),
+        ) else {
+            continue;
+        };
+        let mut material = material.clone();
+        material.extension.ring_texture = Some(ring_material.texture.clone());
+        material.extension.uniform.flags |= PlanetMaterialExtension::FLAG_RINGS;
+        body_material.0 = materials.add(material);
+        commands.entity(rings.body).insert(UniqueRingShadowMaterial);
+    }
+}
+
+/// Shares the sun and the placement of the rings with their material and the material of their
+/// body, only modifying the materials whose values change.
+fn update_rings(
+    sun_position: Res<SunPosition>,
+    sun_illuminance: Res<SunIlluminance>,
+    q_rings: Query<(
+        &PlanetRings,
+        &GlobalTransform,
+        &MeshMaterial3d<RingMaterial>,
+    )>,
+    q_bodies: Query<&MeshMaterial3d<PlanetStandardMaterial>, With<UniqueRingShadowMaterial>>,
+    mut ring_materials: ResMut<Assets<RingMaterial>>,
+    mut materials: ResMut<Assets<PlanetStandardMaterial>>,
+) {
+    let illuminance = sun_illuminance.0;
+    for (rings, transform, ring_material) in &q_rings {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let planet_radius = scale.x;
+        let ring_normal = rotation * Vec3::Y;
+        let uniform = RingMaterialUniform {
+            sun_position: sun_position.0,
+            illuminance,
+            planet_center: translation,
+            planet_radius,
+            ring_normal,
+        };
+        let changed = ring_materials
+            .get(&ring_material.0)
+            .is_some_and(|material| material.uniform != uniform);
+        if changed {
+            if let Some(material) = ring_materials.get_mut(&ring_material.0) {
+                material.uniform = uniform;
+            }
+        }
+
+        let Ok(body_material) = q_bodies.get(rings.body) else {
+            continue;
+        };
+        planet_material::update_uniform(&mut materials, &body_material.0, |uniform| {
+            uniform.ring_center = translation;
+            uniform.ring_normal = ring_normal;
+            uniform.ring_inner_radius = rings.inner_radius * planet_radius;
+            uniform.ring_outer_radius = rings.outer_radius * planet_radius;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_interpolates_between_stops() {
+        let stops = [
+            (0.2, Srgba::new(0.0, 0.0, 0.0, 0.0)),
+            (0.6, Srgba::new(1.0, 0.5, 0.0, 1.0)),
+        ];
+        assert_eq!(sample_gradient(&stops, 0.0), stops[0].1);
+        assert_eq!(sample_gradient(&stops, 1.0), stops[1].1);
+        let middle = sample_gradient(&stops, 0.4);
+        assert!((middle.red - 0.5).abs() < 1e-6);
+        assert!((middle.alpha - 0.5).abs() < 1e-6);
+        assert_eq!(sample_gradient(&[], 0.5), Srgba::WHITE);
+    }
+}
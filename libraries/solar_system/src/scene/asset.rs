@@ -1,20 +1,32 @@
-use crate::body::RotationSpeed;
+use crate::atmosphere::{Atmosphere, AtmosphereMaterial};
+use crate::body::{BodyShape, FitShapeToMesh, RotationSpeed};
+use crate::impostor::{Impostor, ImpostorMaterial};
 use crate::mjd::Mjd;
 use crate::orbit::Orbit;
+use crate::orbit_line::{self, OrbitLine, OrbitLineMaterial};
+use crate::planet_material::PlanetStandardMaterial;
+use crate::rings::{PlanetRings, RingMaterial};
 use crate::scene::components::SceneCamera;
-use crate::scene::manifest::{CameraConfig, PlanetMaterial};
+use crate::scene::manifest::CameraConfig;
+use crate::scene::material::{create_planet_material, load_linear};
 use crate::scene::{components, error, manifest};
-use crate::{body, orbit, sun};
+use crate::star::{CoronaMaterial, StarMaterial, StarMaterialUniform};
+use crate::terrain::{Terrain, TerrainHeightMap, TerrainNoise};
+use crate::trail::Trail;
+use crate::{body, mesh, orbit, rings, star, sun};
 use bevy::asset::io::Reader;
 use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
-use bevy::math::DVec3;
+use bevy::math::{DQuat, DVec3};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
 use bevy::prelude::*;
+use bevy::render::view::NoFrustumCulling;
 use bevy::utils::ConditionalSendFuture;
 use big_space::precision::GridPrecision;
 use big_space::{
     BigReferenceFrameBundle, BigSpaceCommands, GridCell, ReferenceFrame, ReferenceFrameCommands,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 #[derive(Debug, Serialize, Deserialize, Component, Reflect)]
@@ -61,10 +73,17 @@ impl<Prec: GridPrecision> AssetLoader for SolarSystemLoader<Prec> {
             let manifest = serde_yaml::from_str::<manifest::SolarSystem>(&input)?;
             let mut world = World::default();
 
-            let root =
-                Planet::from_manifest(load_context, manifest.root.name, manifest.root.planet);
+            let root = match manifest.root.body {
+                manifest::RootBody::Planet(planet) => {
+                    Planet::from_manifest(load_context, manifest.root.name, planet)?
+                }
+                manifest::RootBody::Barycenter(barycenter) => {
+                    Planet::barycenter(load_context, manifest.root.name, barycenter)?
+                }
+            };
             load_planet_config::<Prec>(&mut world, load_context, &root, settings);
             setup_camera::<Prec>(&mut world, &manifest.camera)?;
+            link_trail_references(&mut world, &root)?;
 
             load_context.add_labeled_asset("Scene".to_string(), Scene::new(world));
             Ok(SolarSystem {
@@ -82,12 +101,274 @@ impl<Prec: GridPrecision> AssetLoader for SolarSystemLoader<Prec> {
 #[derive(Debug, Clone)]
 pub struct Planet {
     name: String,
+    orbit: Option<Orbit>,
+    /// Mesh and material of the orbit line, regenerated every frame around the camera.
+    orbit_line: Option<(Handle<Mesh>, Handle<OrbitLineMaterial>)>,
+    trail: Option<PlanetTrailConfig>,
+    /// Body at the center of the frame, absent for barycenters.
+    body: Option<PlanetBody>,
+    satellites: Vec<Planet>,
+}
+
+#[derive(Debug, Clone)]
+struct PlanetBody {
     radius: f32,
+    shape: PlanetShape,
     inclination: f32,
-    material: Handle<StandardMaterial>,
+    material: BodyMaterial,
+    albedo: f32,
+    /// Material of the point of light drawn in place of the body when it is too small.
+    impostor: Handle<ImpostorMaterial>,
     rotation_speed: RotationSpeed,
-    orbit: Option<Orbit>,
-    satellites: Vec<Planet>,
+    rings: Option<PlanetRingsConfig>,
+    atmosphere: Option<PlanetAtmosphereConfig>,
+    terrain: Option<PlanetTerrainConfig>,
+}
+
+#[derive(Debug, Clone)]
+enum BodyMaterial {
+    Planet(Handle<PlanetStandardMaterial>),
+    Star {
+        /// Radiated power, in watts.
+        luminosity: f64,
+        material: Handle<StarMaterial>,
+        corona: Handle<CoronaMaterial>,
+        corona_mesh: Handle<Mesh>,
+    },
+}
+
+impl BodyMaterial {
+    fn star(
+        load_context: &mut LoadContext,
+        name: &str,
+        radius: f64,
+        manifest: manifest::Star,
+    ) -> Self {
+        let color = star::temperature_color(manifest.temperature)
+            * manifest.emissive_power.as_base_value() as f32;
+        Self::Star {
+            luminosity: manifest.luminosity.as_base_value(),
+            material: load_context.add_labeled_asset(
+                format!("Stars/{name}/Material"),
+                StarMaterial {
+                    uniform: StarMaterialUniform {
+                        color: color.to_vec3(),
+                        limb_darkening: manifest.limb_darkening,
+                        granulation: manifest.granulation,
+                        granulation_scale: manifest.granulation_scale,
+                    },
+                },
+            ),
+            corona: load_context.add_labeled_asset(
+                format!("Stars/{name}/Corona"),
+                CoronaMaterial::new(
+                    color * manifest.corona_intensity,
+                    radius as _,
+                    manifest.corona_extent,
+                ),
+            ),
+            corona_mesh: load_context.add_labeled_asset(
+                format!("Stars/{name}/CoronaMesh"),
+                Rectangle::new(2.0, 2.0).into(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlanetTerrainConfig {
+    height_map: Option<TerrainHeightMap>,
+    noise: Option<TerrainNoise>,
+    resolution: u32,
+    max_depth: u8,
+    max_screen_error: f32,
+}
+
+impl PlanetTerrainConfig {
+    fn from_manifest(load_context: &mut LoadContext, manifest: manifest::Terrain) -> Self {
+        Self {
+            height_map: manifest.height_map.map(|height_map| TerrainHeightMap {
+                image: load_linear(load_context, &height_map.path),
+                min_elevation: height_map.min.as_base_value() as _,
+                max_elevation: height_map.max.as_base_value() as _,
+            }),
+            noise: manifest.noise.map(|noise| TerrainNoise {
+                seed: noise.seed,
+                octaves: noise.octaves,
+                amplitude: noise.amplitude.as_base_value(),
+                wavelength: noise.wavelength.as_base_value(),
+                lacunarity: noise.lacunarity,
+                persistence: noise.persistence,
+            }),
+            resolution: manifest.resolution.max(2),
+            max_depth: manifest.max_depth.min(Terrain::MAX_DEPTH),
+            max_screen_error: manifest.max_screen_error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PlanetShape {
+    /// The shared unit sphere, scaled to these semi-axes
+    Ellipsoid(Vec3),
+    /// A custom mesh, with the length of its units
+    Mesh(Handle<Mesh>, f32),
+}
+
+impl PlanetShape {
+    fn from_manifest(
+        load_context: &mut LoadContext,
+        radius: f64,
+        shape: Option<manifest::Shape>,
+    ) -> Result<Self, error::SceneLoadError> {
+        Ok(match shape {
+            None => Self::Ellipsoid(Vec3::splat(radius as _)),
+            Some(manifest::Shape::Ellipsoid {
+                ellipsoid: [a, b, c],
+            }) => Self::Ellipsoid(Vec3::new(
+                a.as_base_value() as _,
+                c.as_base_value() as _,
+                b.as_base_value() as _,
+            )),
+            Some(manifest::Shape::Mesh { mesh, unit }) => {
+                // Only glTF is loaded, and labels point to a mesh within a glTF file
+                let (file, label) = mesh.split_once('#').unwrap_or((&mesh, ""));
+                if !(file.ends_with(".glb") || file.ends_with(".gltf")) {
+                    return Err(error::SceneLoadError::UnsupportedMesh(mesh));
+                }
+                let path = if label.is_empty() {
+                    format!("{file}#Mesh0/Primitive0")
+                } else {
+                    mesh
+                };
+                Self::Mesh(load_context.load(path), unit.as_base_value() as _)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlanetTrailConfig {
+    /// Duration of the trail, in seconds.
+    duration: f64,
+    /// Name of the body around which positions are recorded, linked once the scene is spawned.
+    reference: Option<String>,
+    mesh: Handle<Mesh>,
+    material: Handle<OrbitLineMaterial>,
+}
+
+impl PlanetTrailConfig {
+    fn from_manifest(load_context: &mut LoadContext, name: &str, trail: manifest::Trail) -> Self {
+        let color = trail.color.unwrap_or(Vec3::ONE);
+        let color = Srgba::from_f32_array_no_alpha(color.to_array()).into();
+        Self {
+            duration: trail.duration.as_seconds(),
+            reference: trail.reference,
+            mesh: load_context.add_labeled_asset(
+                format!("Trails/{name}/Mesh"),
+                orbit_line::orbit_line_mesh(&[]),
+            ),
+            material: load_context.add_labeled_asset(
+                format!("Trails/{name}/Material"),
+                OrbitLineMaterial::new(color),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlanetRingsConfig {
+    /// Inner radius, in body radii.
+    inner_radius: f32,
+    /// Outer radius, in body radii.
+    outer_radius: f32,
+    mesh: Handle<Mesh>,
+    material: Handle<RingMaterial>,
+}
+
+impl PlanetRingsConfig {
+    fn from_manifest(
+        load_context: &mut LoadContext,
+        name: &str,
+        body_radius: f64,
+        manifest: manifest::Rings,
+    ) -> Self {
+        let inner_radius = (manifest.inner_radius.as_base_value() / body_radius) as f32;
+        let outer_radius = (manifest.outer_radius.as_base_value() / body_radius) as f32;
+        let texture = match manifest.texture {
+            Some(path) => load_context.load(path),
+            None => {
+                let stops = manifest
+                    .gradient
+                    .iter()
+                    .map(|stop| {
+                        let [r, g, b] = stop.color.to_array();
+                        (stop.position, Srgba::new(r, g, b, stop.opacity))
+                    })
+                    .collect::<Vec<_>>();
+                load_context.add_labeled_asset(
+                    format!("Rings/{name}/Gradient"),
+                    rings::gradient_image(&stops, 512),
+                )
+            }
+        };
+        Self {
+            inner_radius,
+            outer_radius,
+            mesh: load_context.add_labeled_asset(
+                format!("Rings/{name}/Mesh"),
+                rings::ring_mesh(inner_radius, outer_radius, 256),
+            ),
+            material: load_context.add_labeled_asset(
+                format!("Rings/{name}/Material"),
+                RingMaterial {
+                    uniform: default(),
+                    texture,
+                },
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlanetAtmosphereConfig {
+    atmosphere: Atmosphere,
+    material: Handle<AtmosphereMaterial>,
+}
+
+impl PlanetAtmosphereConfig {
+    fn from_manifest(
+        load_context: &mut LoadContext,
+        name: &str,
+        body_radius: f64,
+        manifest: manifest::Atmosphere,
+    ) -> Self {
+        let bottom_radius = manifest
+            .radius
+            .map(|radius| radius.as_base_value())
+            .unwrap_or(body_radius);
+        let atmosphere = Atmosphere {
+            bottom_radius: bottom_radius as _,
+            top_radius: (bottom_radius + manifest.height.as_base_value()) as _,
+            rayleigh_scattering: manifest.rayleigh_scattering,
+            rayleigh_scale_height: manifest.rayleigh_scale_height.as_base_value() as _,
+            mie_scattering: manifest.mie_scattering,
+            mie_absorption: manifest.mie_absorption,
+            mie_scale_height: manifest.mie_scale_height.as_base_value() as _,
+            mie_asymmetry: manifest.mie_asymmetry,
+        };
+        let transmittance_lut = load_context.add_labeled_asset(
+            format!("Atmospheres/{name}/Transmittance"),
+            atmosphere.transmittance_lut(),
+        );
+        Self {
+            atmosphere,
+            material: load_context.add_labeled_asset(
+                format!("Atmospheres/{name}/Material"),
+                AtmosphereMaterial::new(&atmosphere, transmittance_lut),
+            ),
+        }
+    }
 }
 
 impl Planet {
@@ -95,28 +376,95 @@ impl Planet {
         load_context: &mut LoadContext,
         name: String,
         manifest: manifest::Planet,
-    ) -> Self {
-        Self {
-            name: name.clone(),
-            radius: manifest.radius.as_base_value() as _,
+    ) -> Result<Self, error::SceneLoadError> {
+        let radius = manifest.radius.as_base_value();
+        let body = PlanetBody {
+            radius: radius as _,
+            shape: PlanetShape::from_manifest(load_context, radius, manifest.shape)?,
             inclination: manifest.inclination,
-            material: match manifest.material {
-                manifest::MaterialSource::Inline(material) => load_context
-                    .labeled_asset_scope(format!("Materials/{name}"), |_| {
-                        create_planet_material(&material)
+            material: match (manifest.star, manifest.material) {
+                (Some(star), _) => BodyMaterial::star(load_context, &name, radius, star),
+                (None, manifest::MaterialSource::Path(path)) => {
+                    BodyMaterial::Planet(load_context.load(path))
+                }
+                (None, manifest::MaterialSource::Inline(material)) => BodyMaterial::Planet(
+                    load_context.labeled_asset_scope(format!("Materials/{name}"), |load_context| {
+                        create_planet_material(
+                            load_context,
+                            &format!("Materials/{name}/Procedural"),
+                            &material,
+                        )
                     }),
+                ),
             },
-            rotation_speed: RotationSpeed::from_duration(manifest.siderial_day.as_seconds() as f32),
+            albedo: manifest.albedo,
+            impostor: load_context.add_labeled_asset(
+                format!("Impostors/{name}/Material"),
+                ImpostorMaterial::new(radius as _),
+            ),
+            rotation_speed: RotationSpeed::from_duration(manifest.siderial_day.as_seconds()),
+            rings: manifest
+                .rings
+                .map(|rings| PlanetRingsConfig::from_manifest(load_context, &name, radius, rings)),
+            terrain: manifest
+                .terrain
+                .map(|terrain| PlanetTerrainConfig::from_manifest(load_context, terrain)),
+            atmosphere: manifest.atmosphere.map(|atmosphere| {
+                PlanetAtmosphereConfig::from_manifest(load_context, &name, radius, atmosphere)
+            }),
+        };
+        let orbit_line = manifest.orbit.as_ref().map(|orbit| {
+            let color = orbit.color.unwrap_or(Vec3::new(1.0, 1.0, 0.0));
+            let color = Srgba::from_f32_array_no_alpha(color.to_array()).into();
+            (
+                load_context.add_labeled_asset(
+                    format!("Orbits/{name}/Mesh"),
+                    orbit_line::orbit_line_mesh(&[]),
+                ),
+                load_context.add_labeled_asset(
+                    format!("Orbits/{name}/Material"),
+                    OrbitLineMaterial::new(color),
+                ),
+            )
+        });
+        Ok(Self {
+            name,
             orbit: manifest
                 .orbit
                 .map(Into::into)
                 .map(From::<orbit::KeplerElements>::from),
-            satellites: manifest
-                .satellites
-                .into_iter()
-                .map(|(name, satellite)| Self::from_manifest(load_context, name, satellite))
-                .collect(),
-        }
+            orbit_line,
+            trail: manifest
+                .trail
+                .map(|trail| PlanetTrailConfig::from_manifest(load_context, &name, trail)),
+            body: Some(body),
+            satellites: Self::satellites(load_context, manifest.satellites)?,
+        })
+    }
+
+    fn barycenter(
+        load_context: &mut LoadContext,
+        name: String,
+        manifest: manifest::Barycenter,
+    ) -> Result<Self, error::SceneLoadError> {
+        Ok(Self {
+            name,
+            orbit: None,
+            orbit_line: None,
+            trail: None,
+            body: None,
+            satellites: Self::satellites(load_context, manifest.satellites)?,
+        })
+    }
+
+    fn satellites(
+        load_context: &mut LoadContext,
+        satellites: HashMap<String, manifest::Planet>,
+    ) -> Result<Vec<Self>, error::SceneLoadError> {
+        satellites
+            .into_iter()
+            .map(|(name, satellite)| Self::from_manifest(load_context, name, satellite))
+            .collect()
     }
 }
 
@@ -133,10 +481,9 @@ fn load_planet_config<Prec: GridPrecision>(
     root: &Planet,
     settings: &SolarSystemSettings,
 ) {
-    let sphere = load_context.add_labeled_asset(
-        "Sphere".to_string(),
-        Sphere::new(1.0).mesh().ico(64).unwrap(),
-    );
+    let sphere =
+        load_context.add_labeled_asset("Sphere".to_string(), mesh::equirectangular_sphere(128, 64));
+    let quad = load_context.add_labeled_asset("Quad".to_string(), Rectangle::new(2.0, 2.0).into());
     let mut commands = world.commands();
     commands.spawn_big_space(
         ReferenceFrame::<Prec>::new(settings.cell_length, settings.switching_threshold),
@@ -147,7 +494,7 @@ fn load_planet_config<Prec: GridPrecision>(
                 GridCell::<Prec>::default(),
                 Transform::default(),
             ));
-            load_planet_config_inner(&sphere, root_frame, root, true);
+            load_planet_config_inner(&sphere, &quad, root_frame, root, true);
         },
     );
     world.flush();
@@ -155,14 +502,15 @@ fn load_planet_config<Prec: GridPrecision>(
 
 fn load_planet_config_inner<Prec: GridPrecision>(
     mesh: &Handle<Mesh>,
+    quad: &Handle<Mesh>,
     frame: &mut ReferenceFrameCommands<Prec>,
     config: &Planet,
-    is_sun: bool,
+    is_root: bool,
 ) {
     let pos = config
         .orbit
         .as_ref()
-        .and_then(|orbit| orbit.point_on_orbit(Mjd::default()))
+        .map(|orbit| orbit.point_on_orbit(Mjd::default()))
         .unwrap_or(DVec3::ZERO);
     let (cell, local_pos) = frame.frame().translation_to_grid(pos);
     frame.with_frame_default(|planet| {
@@ -173,34 +521,219 @@ fn load_planet_config_inner<Prec: GridPrecision>(
             cell,
         ));
         planet.with_frame_default(|rot| {
-            body::spawn(
-                rot,
-                config.name.clone(),
-                mesh.clone(),
-                config.material.clone(),
-                config.rotation_speed,
-                config.radius,
-                config.inclination,
-            );
-            if is_sun {
-                rot.insert(sun::Sun(config.radius));
+            let Some(config_body) = &config.body else {
+                // Barycenters are only named, so that the camera can target them
+                rot.insert((Name::new(config.name.clone()), Visibility::Visible));
+                return;
+            };
+            let body = match &config_body.material {
+                BodyMaterial::Planet(material) => spawn_body(
+                    rot,
+                    mesh,
+                    &config.name,
+                    config_body,
+                    MeshMaterial3d(material.clone()),
+                ),
+                BodyMaterial::Star {
+                    material,
+                    corona,
+                    corona_mesh,
+                    ..
+                } => {
+                    rot.spawn_spatial((
+                        Name::new(format!("{} (Corona)", config.name)),
+                        GridCell::<Prec>::default(),
+                        Mesh3d(corona_mesh.clone()),
+                        MeshMaterial3d(corona.clone()),
+                        Transform::default(),
+                        // The quad is placed around the star in the vertex shader
+                        NoFrustumCulling,
+                        NotShadowCaster,
+                        NotShadowReceiver,
+                    ));
+                    spawn_body(
+                        rot,
+                        mesh,
+                        &config.name,
+                        config_body,
+                        (MeshMaterial3d(material.clone()), NotShadowCaster),
+                    )
+                }
+            };
+            // Every star lights the scene, as well as a root body without star parameters
+            let luminosity = match config_body.material {
+                BodyMaterial::Star { luminosity, .. } => Some(luminosity),
+                BodyMaterial::Planet(_) => is_root.then_some(sun::SOLAR_LUMINOSITY),
+            };
+            if let Some(luminosity) = luminosity {
+                rot.insert(sun::Sun {
+                    radius: config_body.radius,
+                    luminosity,
+                });
+            }
+            rot.spawn_spatial((
+                Name::new(format!("{} (Impostor)", config.name)),
+                Impostor {
+                    body,
+                    albedo: config_body.albedo,
+                    luminosity: luminosity.unwrap_or_default(),
+                },
+                GridCell::<Prec>::default(),
+                Mesh3d(quad.clone()),
+                MeshMaterial3d(config_body.impostor.clone()),
+                Transform::default(),
+                // The quad is placed in front of the body in the vertex shader
+                NoFrustumCulling,
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+            if let Some(terrain) = &config_body.terrain {
+                rot.insert(Terrain {
+                    body,
+                    orientation: DQuat::from_rotation_x(config_body.inclination.to_radians() as _),
+                    height_map: terrain.height_map.clone(),
+                    noise: terrain.noise,
+                    resolution: terrain.resolution,
+                    max_depth: terrain.max_depth,
+                    max_screen_error: terrain.max_screen_error,
+                });
+            }
+            if let Some(rings) = &config_body.rings {
+                rot.spawn_spatial((
+                    Name::new(format!("{} (Rings)", config.name)),
+                    PlanetRings {
+                        body,
+                        inner_radius: rings.inner_radius,
+                        outer_radius: rings.outer_radius,
+                    },
+                    GridCell::<Prec>::default(),
+                    Mesh3d(rings.mesh.clone()),
+                    MeshMaterial3d(rings.material.clone()),
+                    // Same as the body, so that the rings follow its equator
+                    Transform::from_scale(Vec3::splat(config_body.radius))
+                        .with_rotation(Quat::from_rotation_x(config_body.inclination.to_radians())),
+                    NotShadowCaster,
+                    NotShadowReceiver,
+                ));
+            }
+            if let Some(atmosphere) = &config_body.atmosphere {
+                rot.spawn_spatial((
+                    Name::new(format!("{} (Atmosphere)", config.name)),
+                    atmosphere.atmosphere,
+                    GridCell::<Prec>::default(),
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(atmosphere.material.clone()),
+                    Transform::from_scale(Vec3::splat(atmosphere.atmosphere.top_radius)),
+                    NotShadowCaster,
+                    NotShadowReceiver,
+                ));
             }
         });
 
         if let Some(orbit) = config.orbit {
             planet.insert(orbit);
         }
+        if let Some((mesh, material)) = &config.orbit_line {
+            planet.spawn_spatial((
+                Name::new(format!("{} (Orbit)", config.name)),
+                OrbitLine::default(),
+                GridCell::<Prec>::default(),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::default(),
+                // The mesh is moved to the camera every frame
+                NoFrustumCulling,
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+        }
+
+        if let Some(trail) = &config.trail {
+            planet.spawn_spatial((
+                Name::new(format!("{} (Trail)", config.name)),
+                Trail::new(trail.duration),
+                GridCell::<Prec>::default(),
+                Mesh3d(trail.mesh.clone()),
+                MeshMaterial3d(trail.material.clone()),
+                Transform::default(),
+                // The mesh is moved to the camera every frame
+                NoFrustumCulling,
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+        }
 
         for satellite in &config.satellites {
-            load_planet_config_inner(mesh, planet, satellite, false);
+            load_planet_config_inner(mesh, quad, planet, satellite, false);
         }
     });
 }
 
+fn spawn_body<Prec: GridPrecision>(
+    rot: &mut ReferenceFrameCommands<Prec>,
+    mesh: &Handle<Mesh>,
+    name: &str,
+    config: &PlanetBody,
+    material: impl Bundle,
+) -> Entity {
+    match &config.shape {
+        PlanetShape::Ellipsoid(radii) => body::spawn(
+            rot,
+            name.to_string(),
+            mesh.clone(),
+            material,
+            config.rotation_speed,
+            *radii,
+            config.inclination,
+            BodyShape::ellipsoid(*radii),
+        ),
+        PlanetShape::Mesh(shape_mesh, unit) => body::spawn(
+            rot,
+            name.to_string(),
+            shape_mesh.clone(),
+            material,
+            config.rotation_speed,
+            Vec3::splat(*unit),
+            config.inclination,
+            (BodyShape::sphere(config.radius), FitShapeToMesh),
+        ),
+    }
+}
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct CameraTarget;
 
+/// Points trails to the frame of the body they are recorded around, which is the parent of the
+/// frame holding its name.
+fn link_trail_references(world: &mut World, config: &Planet) -> Result<(), error::SceneLoadError> {
+    use error::SceneLoadError::*;
+    if let Some(reference) = config
+        .trail
+        .as_ref()
+        .and_then(|trail| trail.reference.as_ref())
+    {
+        let mut find = |name: &str| {
+            world
+                .query::<(Entity, &Name)>()
+                .iter(world)
+                .find_map(|(entity, entity_name)| (entity_name.as_str() == name).then_some(entity))
+        };
+        let trail = find(&format!("{} (Trail)", config.name));
+        let frame = find(reference)
+            .and_then(|entity| world.get::<Parent>(entity))
+            .map(Parent::get)
+            .ok_or(TrailReferenceNotFound(reference.clone()))?;
+        if let Some(mut trail) = trail.and_then(|trail| world.get_mut::<Trail>(trail)) {
+            trail.reference = Some(frame);
+        }
+    }
+    for satellite in &config.satellites {
+        link_trail_references(world, satellite)?;
+    }
+    Ok(())
+}
+
 fn setup_camera<Prec: GridPrecision>(
     world: &mut World,
     config: &CameraConfig,
@@ -223,13 +756,3 @@ fn setup_camera<Prec: GridPrecision>(
     });
     Ok(())
 }
-
-pub fn create_planet_material(material: &PlanetMaterial) -> StandardMaterial {
-    let base_color: LinearRgba = Srgba::from_f32_array_no_alpha(material.color.to_array()).into();
-
-    StandardMaterial {
-        base_color: base_color.into(),
-        emissive: base_color * material.emissive_power.as_base_value() as f32,
-        ..Default::default()
-    }
-}
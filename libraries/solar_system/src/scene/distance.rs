@@ -1,6 +1,12 @@
+use crate::scene::error::QuantityFromStrError;
+use crate::scene::quantity::{Dimension, Quantity};
 use crate::scene::si_prefix::SiPrefixed;
+use bevy::prelude::{Deref, Reflect};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum DistanceUnit {
@@ -23,6 +29,17 @@ impl fmt::Display for DistanceUnit {
     }
 }
 
+impl FromStr for DistanceUnit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL_DESCENDING_ORDER
+            .into_iter()
+            .find(|unit| unit.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
 impl DistanceUnit {
     pub const ALL_DESCENDING_ORDER: [Self; 5] = [
         Self::Parsecs,
@@ -80,6 +97,73 @@ impl From<SiPrefixed> for Distance {
     }
 }
 
+/// Parses a [`Quantity`] expression of length, e.g. `1.52 AU` or `2 * 696340 km`, or a number
+/// with a bare SI prefix, e.g. `2439.7k`. Plain numbers are in meters.
+fn parse_length(s: &str) -> Result<SiPrefixed, QuantityFromStrError> {
+    match Quantity::parse_dimension(s, Dimension::LENGTH) {
+        Ok(quantity) => Ok(SiPrefixed::from(quantity.value)),
+        // `3h` is 3 hours, not 300 meters
+        Err(err @ QuantityFromStrError::DimensionMismatch(..)) => Err(err),
+        Err(err) => SiPrefixed::from_bare_prefix(s).ok_or(err),
+    }
+}
+
+/// Parses a length, as [`parse_length`].
+impl FromStr for Distance {
+    type Err = QuantityFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_length(s).map(Self::from)
+    }
+}
+
+/// Length field of a manifest, which only accepts lengths (`384400 km`) and plain numbers in
+/// meters, with an optional bare SI prefix (`384.4M`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deref, Reflect)]
+pub struct Length(pub SiPrefixed);
+
+impl Length {
+    pub fn from_base_value(value: f64) -> Self {
+        Self(SiPrefixed::from_base_value(value))
+    }
+}
+
+impl FromStr for Length {
+    type Err = QuantityFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_length(s).map(Self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Expression(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(value) => Ok(Self(SiPrefixed::from(value))),
+            Repr::Expression(s) => s.parse().map_err(D::Error::custom),
+        }
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
 impl Distance {
     pub fn to_base_value(self) -> f64 {
         self.value * self.unit.factor()
@@ -101,3 +185,36 @@ impl PartialEq for Distance {
         *self == other.to_base_value()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_distance() {
+        let distance: Distance = "1.52 AU".parse().unwrap();
+        assert_eq!(distance.unit, DistanceUnit::AstronomicalUnits);
+        assert!((distance.value - 1.52).abs() < 1e-12);
+
+        let distance: Distance = "384400 km".parse().unwrap();
+        assert_eq!(distance.unit, DistanceUnit::Kilometers);
+        assert_eq!(distance, 384_400_000.0);
+
+        assert!("3 d".parse::<Distance>().is_err());
+        assert_eq!("pc".parse::<DistanceUnit>(), Ok(DistanceUnit::Parsecs));
+    }
+
+    #[test]
+    fn lengths_reject_other_dimensions() {
+        let length: Length = serde_yaml::from_str("10m").unwrap();
+        assert_eq!(length.as_base_value(), 10.0);
+        let length: Length = serde_yaml::from_str("6.4e6").unwrap();
+        assert_eq!(length.as_base_value(), 6.4e6);
+        let length: Length = serde_yaml::from_str("2439.7k").unwrap();
+        assert_eq!(length.as_base_value(), 2_439_700.0);
+        assert!("3h".parse::<Length>().is_err());
+
+        let err = serde_yaml::from_str::<Length>("3 d").unwrap_err();
+        assert!(err.to_string().contains("Cannot mix"), "{err}");
+    }
+}
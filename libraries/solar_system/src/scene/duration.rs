@@ -3,13 +3,18 @@ use serde::de::Error;
 use serde::Deserializer;
 use std::fmt;
 use std::fmt::Formatter;
+use std::ops;
 use std::str::FromStr;
 
+/// Duration as written in scene manifests.
+///
+/// Components all share the same sign; a duration is negative as a whole (e.g. for retrograde
+/// rotation periods).
 #[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
 pub struct Duration {
-    pub days: u32,
-    pub hours: u32,
-    pub minutes: u32,
+    pub days: i32,
+    pub hours: i32,
+    pub minutes: i32,
     pub seconds: f32,
 }
 
@@ -19,59 +24,176 @@ impl Default for Duration {
     }
 }
 
+const SECONDS_PER_MINUTE: f64 = 60.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+const SECONDS_PER_DAY: f64 = 86400.0;
+const SECONDS_PER_WEEK: f64 = 7.0 * SECONDS_PER_DAY;
+/// Julian year, as used for astronomical durations.
+const SECONDS_PER_YEAR: f64 = 365.25 * SECONDS_PER_DAY;
+const SECONDS_PER_MONTH: f64 = SECONDS_PER_YEAR / 12.0;
+
+/// Parses durations either as a list of `<number><unit>` components (`1d 2h 30m`, `1.5 y`, `-243d`),
+/// or as an ISO 8601 duration (`P1Y2M`, `PT36H`, `-P1W`). A leading `-` negates the whole duration,
+/// and numbers without a unit are seconds.
 impl FromStr for Duration {
     type Err = DurationFromStrError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut days = 0f64;
-        let mut hours = 0f64;
-        let mut minutes = 0f64;
-        let mut seconds = 0f64;
-        for s in s.split_whitespace() {
-            if let Some(pos_unit) = s.find(|c: char| !c.is_ascii_digit() && c != '.') {
-                let unit = &s[pos_unit..];
-                let value = &s[..pos_unit];
-                let value = value.parse::<f64>().map_err(|_| {
-                    DurationFromStrError::MalformedString(s.to_string(), 0..pos_unit)
-                })?;
-                match unit {
-                    "d" | "day" | "days" => days += value,
-                    "h" | "hour" | "hours" => hours += value,
-                    "m" | "min" | "minute" | "minutes" => minutes += value,
-                    "s" | "sec" | "second" | "seconds" => seconds += value,
-                    _ => {
-                        return Err(DurationFromStrError::MalformedString(
-                            s.to_string(),
-                            pos_unit..s.len(),
-                        ))
-                    }
-                }
-            } else {
-                seconds += s.parse::<f64>().map_err(|_| {
-                    DurationFromStrError::MalformedString(s.to_string(), 0..s.len())
-                })?;
+        let start = s.len() - s.trim_start().len();
+        let trimmed = s.trim();
+        let (negative, body, body_start) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest, start + 1),
+            None => (false, trimmed, start),
+        };
+        let seconds = if body.starts_with('P') {
+            parse_iso8601(s, body, body_start)?
+        } else {
+            parse_components(s, body, body_start)?
+        };
+        Ok(Self::from(if negative { -seconds } else { seconds }))
+    }
+}
+
+fn unit_seconds(unit: &str) -> Option<f64> {
+    Some(match unit {
+        "s" | "sec" | "second" | "seconds" => 1.0,
+        "m" | "min" | "minute" | "minutes" => SECONDS_PER_MINUTE,
+        "h" | "hr" | "hour" | "hours" => SECONDS_PER_HOUR,
+        "d" | "day" | "days" => SECONDS_PER_DAY,
+        "w" | "wk" | "week" | "weeks" => SECONDS_PER_WEEK,
+        "y" | "yr" | "year" | "years" | "a" => SECONDS_PER_YEAR,
+        _ => return None,
+    })
+}
+
+/// Parses `<number><unit>` components; `offset` is the position of `body` within `input`, so that
+/// error spans point into the full input.
+fn parse_components(input: &str, body: &str, offset: usize) -> Result<f64, DurationFromStrError> {
+    let mut tokens = body
+        .split_whitespace()
+        .map(|token| {
+            let start = offset + (token.as_ptr() as usize - body.as_ptr() as usize);
+            (token, start)
+        })
+        .peekable();
+    if tokens.peek().is_none() {
+        return Err(DurationFromStrError::MalformedString(
+            input.to_string(),
+            offset..input.len(),
+        ));
+    }
+
+    let mut total = 0.0;
+    while let Some((token, start)) = tokens.next() {
+        let pos_unit = token
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(token.len());
+        let value = token[..pos_unit].parse::<f64>().map_err(|_| {
+            DurationFromStrError::MalformedString(input.to_string(), start..start + pos_unit)
+        })?;
+
+        // The unit may be attached (`1.5d`) or be the next token (`1.5 d`)
+        let (unit, unit_span) = if pos_unit < token.len() {
+            (&token[pos_unit..], start + pos_unit..start + token.len())
+        } else if let Some(&(next, next_start)) = tokens
+            .peek()
+            .filter(|(next, _)| next.starts_with(|c: char| c.is_alphabetic()))
+        {
+            tokens.next();
+            (next, next_start..next_start + next.len())
+        } else {
+            ("s", start..start + token.len())
+        };
+        let scale = unit_seconds(unit)
+            .ok_or_else(|| DurationFromStrError::UnknownUnit(input.to_string(), unit_span))?;
+        total += value * scale;
+    }
+    Ok(total)
+}
+
+/// Parses an ISO 8601 duration (`PnYnMnWnDTnHnMnS`) starting at the `P` designator. Years and
+/// months are Julian years and twelfths of them.
+fn parse_iso8601(input: &str, body: &str, offset: usize) -> Result<f64, DurationFromStrError> {
+    const DATE_DESIGNATORS: [(char, f64); 4] = [
+        ('Y', SECONDS_PER_YEAR),
+        ('M', SECONDS_PER_MONTH),
+        ('W', SECONDS_PER_WEEK),
+        ('D', SECONDS_PER_DAY),
+    ];
+    const TIME_DESIGNATORS: [(char, f64); 3] = [
+        ('H', SECONDS_PER_HOUR),
+        ('M', SECONDS_PER_MINUTE),
+        ('S', 1.0),
+    ];
+    let error = |span: ops::Range<usize>| {
+        DurationFromStrError::MalformedIso8601(
+            input.to_string(),
+            offset + span.start..offset + span.end,
+        )
+    };
+
+    let mut total = 0.0;
+    let mut components = 0;
+    let mut in_time = false;
+    // Index of the next allowed designator, enforcing the standard order
+    let mut next_designator = 0;
+    let mut pos = 1;
+    while pos < body.len() {
+        let rest = &body[pos..];
+        if rest.starts_with('T') {
+            if in_time || rest.len() == 1 {
+                return Err(error(pos..pos + 1));
             }
+            in_time = true;
+            next_designator = 0;
+            pos += 1;
+            continue;
         }
-        Ok(Self {
-            days: days.floor() as _,
-            hours: (days.fract() * 60f64 + hours.floor()) as _,
-            minutes: (hours.fract() * 60f64 + minutes.floor()) as _,
-            seconds: (minutes.fract() * 60f64 + seconds) as _,
-        })
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+            .ok_or_else(|| error(pos..body.len()))?;
+        if len == 0 {
+            return Err(error(pos..pos + 1));
+        }
+        let value = rest[..len]
+            .replace(',', ".")
+            .parse::<f64>()
+            .map_err(|_| error(pos..pos + len))?;
+        let designator = rest[len..].chars().next().unwrap();
+        let designators: &[(char, f64)] = if in_time {
+            &TIME_DESIGNATORS
+        } else {
+            &DATE_DESIGNATORS
+        };
+        let index = designators[next_designator..]
+            .iter()
+            .position(|(c, _)| *c == designator)
+            .map(|i| i + next_designator)
+            .ok_or_else(|| error(pos + len..pos + len + designator.len_utf8()))?;
+        total += value * designators[index].1;
+        components += 1;
+        next_designator = index + 1;
+        pos += len + designator.len_utf8();
     }
+    if components == 0 {
+        return Err(error(0..body.len()));
+    }
+    Ok(total)
 }
 
 impl From<f64> for Duration {
     fn from(value: f64) -> Self {
-        let days = value / 86400f64;
-        let hours = days.fract() * 24f64;
-        let minutes = hours.fract() * 60f64;
-        let seconds = minutes.fract() * 60f64;
+        // Split off whole seconds first so that integer components are exact
+        let sign = if value < 0.0 { -1 } else { 1 };
+        let value = value.abs();
+        let whole = value.trunc();
+        let fraction = value - whole;
+        let whole = whole as i64;
         Self {
-            days: days.floor() as _,
-            hours: hours.floor() as _,
-            minutes: minutes.floor() as _,
-            seconds: seconds as _,
+            days: sign * (whole / 86400) as i32,
+            hours: sign * (whole % 86400 / 3600) as i32,
+            minutes: sign * (whole % 3600 / 60) as i32,
+            seconds: (sign as f64 * ((whole % 60) as f64 + fraction)) as f32,
         }
     }
 }
@@ -83,7 +205,7 @@ impl<'de> serde::Deserialize<'de> for Duration {
     {
         struct DurationVisitor;
 
-        impl<'de> serde::de::Visitor<'de> for DurationVisitor {
+        impl serde::de::Visitor<'_> for DurationVisitor {
             type Value = Duration;
             fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
                 write!(formatter, "Duration string or a number")
@@ -103,7 +225,21 @@ impl<'de> serde::Deserialize<'de> for Duration {
                 Ok(Duration::from(v))
             }
 
-            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                self.visit_f64(v as _)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                self.visit_f64(v as _)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
             where
                 E: Error,
             {
@@ -120,46 +256,33 @@ impl serde::Serialize for Duration {
     where
         S: serde::Serializer,
     {
-        let mut string = String::new();
-        if self.days > 0 {
-            string.push_str(&format!("{}d ", self.days));
-        }
-        if self.hours > 0 {
-            string.push_str(&format!("{}h ", self.hours));
-        }
-        if self.minutes > 0 {
-            string.push_str(&format!("{}m ", self.minutes));
-        }
-        if self.seconds > 0f32 {
-            string.push_str(&format!("{:.1}s", self.seconds));
-        }
-        serializer.serialize_str(&string)
+        serializer.collect_str(self)
     }
 }
 
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let mut written = false;
-        if self.days > 0 {
-            write!(f, "{}d ", self.days)?;
-            written = true;
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        let mut parts = Vec::with_capacity(4);
+        if self.days != 0 {
+            parts.push(format!("{}d", self.days.unsigned_abs()));
         }
-        if self.hours > 0 {
-            write!(f, "{}h ", self.hours)?;
-            written = true;
+        if self.hours != 0 {
+            parts.push(format!("{}h", self.hours.unsigned_abs()));
         }
-        if self.minutes > 0 {
-            write!(f, "{}m ", self.minutes)?;
-            written = true;
+        if self.minutes != 0 {
+            parts.push(format!("{}m", self.minutes.unsigned_abs()));
         }
-        if self.seconds > 0f32 {
-            write!(f, "{:.1}s", self.seconds)?;
-            written = true;
+        if self.seconds != 0f32 {
+            parts.push(format!("{:.1}s", self.seconds.abs()));
         }
-        if !written {
-            write!(f, "0")?;
+        if parts.is_empty() {
+            write!(f, "0")
+        } else {
+            write!(f, "{}", parts.join(" "))
         }
-        Ok(())
     }
 }
 
@@ -171,6 +294,10 @@ impl Duration {
         seconds: 0f32,
     };
 
+    pub fn is_negative(&self) -> bool {
+        self.days < 0 || self.hours < 0 || self.minutes < 0 || self.seconds < 0f32
+    }
+
     pub fn as_seconds(&self) -> f64 {
         self.days as f64 * 86400f64
             + self.hours as f64 * 3600f64
@@ -236,4 +363,54 @@ mod tests {
         assert_eq!(duration.minutes, 30);
         assert_eq!(duration.seconds, 45.5);
     }
+
+    #[test]
+    fn parse_negative_duration() {
+        let duration: Duration = "-243d 0h 36m".parse().unwrap();
+        assert_eq!(duration.days, -243);
+        assert_eq!(duration.minutes, -36);
+        assert!(duration.is_negative());
+        assert_eq!(duration.as_seconds(), -(243.0 * 86400.0 + 36.0 * 60.0));
+        assert_eq!(duration.to_string(), "-243d 36m");
+    }
+
+    #[test]
+    fn parse_years_and_weeks() {
+        let duration: Duration = "1y 2w".parse().unwrap();
+        assert_eq!(duration.as_seconds(), (365.25 + 14.0) * 86400.0);
+        let duration: Duration = "11.86 a".parse().unwrap();
+        assert_eq!(
+            duration.as_seconds().round(),
+            (11.86f64 * 365.25 * 86400.0).round()
+        );
+        let duration: Duration = "1.5 d".parse().unwrap();
+        assert_eq!(duration.days, 1);
+        assert_eq!(duration.hours, 12);
+    }
+
+    #[test]
+    fn parse_iso8601_duration() {
+        let duration: Duration = "P1Y2M".parse().unwrap();
+        assert_eq!(duration.as_seconds(), 365.25 * 86400.0 * 14.0 / 12.0);
+        let duration: Duration = "PT36H30M".parse().unwrap();
+        assert_eq!(duration.days, 1);
+        assert_eq!(duration.hours, 12);
+        assert_eq!(duration.minutes, 30);
+        let duration: Duration = "-P1DT0,5S".parse().unwrap();
+        assert_eq!(duration.as_seconds(), -86400.5);
+    }
+
+    #[test]
+    fn parse_errors_have_spans() {
+        let err = "1d 2x".parse::<Duration>().unwrap_err();
+        assert!(matches!(err, DurationFromStrError::UnknownUnit(..)));
+        assert_eq!(err.span(), 4..5);
+        let err = "1d 2.3.4h".parse::<Duration>().unwrap_err();
+        assert!(matches!(err, DurationFromStrError::MalformedString(..)));
+        assert_eq!(err.span(), 3..8);
+        let err = "P1D2Y".parse::<Duration>().unwrap_err();
+        assert!(matches!(err, DurationFromStrError::MalformedIso8601(..)));
+        assert_eq!(err.span(), 4..5);
+        assert!("PT".parse::<Duration>().is_err());
+    }
 }
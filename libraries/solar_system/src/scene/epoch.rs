@@ -0,0 +1,259 @@
+use crate::mjd::Mjd;
+use crate::scene::error::EpochFromStrError;
+use crate::time_scale::TimeScale;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::de::Error;
+use serde::{Deserializer, Serializer};
+use std::fmt::Formatter;
+use std::ops;
+use std::str::FromStr;
+
+/// Epoch as written in scene manifests.
+///
+/// Accepted forms are:
+/// - a bare number, or `MJD <number>`: Modified Julian Date,
+/// - `JD <number>`: Julian Date,
+/// - `J<year>`, e.g. `J2000`: Julian epoch,
+/// - ISO 8601 dates, e.g. `2000-01-01T12:00:00`, where a trailing `Z` means UTC.
+///
+/// Any of these can be followed by a time scale (`UTC`, `TAI`, `TT` or `TDB`). Without one, ISO 8601
+/// dates are read as UTC and everything else as TT.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Epoch(pub Mjd);
+
+impl From<Epoch> for Mjd {
+    fn from(value: Epoch) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for Epoch {
+    type Err = EpochFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s
+            .split_whitespace()
+            .map(|token| {
+                let start = token.as_ptr() as usize - s.as_ptr() as usize;
+                (token, start..start + token.len())
+            })
+            .collect::<Vec<_>>();
+
+        let mut scale = None;
+        if let [_, .., (last, span)] = tokens.as_slice() {
+            if last.chars().all(|c| c.is_ascii_alphabetic()) {
+                scale = Some(last.parse::<TimeScale>().map_err(|_| {
+                    EpochFromStrError::UnknownTimeScale(s.to_string(), span.clone())
+                })?);
+                tokens.pop();
+            }
+        }
+
+        let parse_number = |(token, span): &(&str, ops::Range<usize>)| {
+            token
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| EpochFromStrError::InvalidNumber(s.to_string(), span.clone()))
+        };
+        let default_scale = scale.unwrap_or(TimeScale::Tt);
+        match tokens.as_slice() {
+            [(kind, _), number] if kind.eq_ignore_ascii_case("jd") => {
+                Ok(Self(Mjd::from_jd(default_scale, parse_number(number)?)))
+            }
+            [(kind, _), number] if kind.eq_ignore_ascii_case("mjd") => {
+                Ok(Self(Mjd::from_mjd(default_scale, parse_number(number)?)))
+            }
+            [(token, span)] if token.starts_with('J') => {
+                let year = parse_number(&(&token[1..], span.start + 1..span.end))?;
+                let mjd = 51544.5 + (year - 2000.0) * 365.25;
+                Ok(Self(Mjd::from_mjd(default_scale, mjd)))
+            }
+            [number] if number.0.parse::<f64>().is_ok() => {
+                Ok(Self(Mjd::from_mjd(default_scale, parse_number(number)?)))
+            }
+            [(date, span)] => {
+                let (datetime, is_utc) = parse_iso8601(date)
+                    .ok_or_else(|| EpochFromStrError::InvalidDate(s.to_string(), span.clone()))?;
+                let scale = match (scale, is_utc) {
+                    (Some(scale), true) if scale != TimeScale::Utc => {
+                        return Err(EpochFromStrError::UnknownTimeScale(
+                            s.to_string(),
+                            span.end - 1..span.end,
+                        ));
+                    }
+                    (Some(scale), _) => scale,
+                    (None, _) => TimeScale::Utc,
+                };
+                Ok(Self(Mjd::from_datetime(scale, datetime)))
+            }
+            [(date, date_span), (time, time_span)] => {
+                let span = date_span.start..time_span.end;
+                let (datetime, is_utc) = parse_iso8601(&format!("{date}T{time}"))
+                    .ok_or_else(|| EpochFromStrError::InvalidDate(s.to_string(), span))?;
+                let scale = scale.unwrap_or(TimeScale::Utc);
+                if is_utc && scale != TimeScale::Utc {
+                    return Err(EpochFromStrError::UnknownTimeScale(
+                        s.to_string(),
+                        time_span.end - 1..time_span.end,
+                    ));
+                }
+                Ok(Self(Mjd::from_datetime(scale, datetime)))
+            }
+            _ => Err(EpochFromStrError::InvalidDate(s.to_string(), 0..s.len())),
+        }
+    }
+}
+
+/// Parses an ISO 8601 date or date and time, returning whether it was explicitly marked as UTC.
+fn parse_iso8601(s: &str) -> Option<(NaiveDateTime, bool)> {
+    const FORMATS: [&str; 3] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ];
+
+    let (s, is_utc) = match s.strip_suffix('Z') {
+        Some(s) => (s, true),
+        None => (s, false),
+    };
+    let datetime = FORMATS
+        .into_iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(Default::default()))
+        })?;
+    Some((datetime, is_utc))
+}
+
+impl<'de> serde::Deserialize<'de> for Epoch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct EpochVisitor;
+
+        impl serde::de::Visitor<'_> for EpochVisitor {
+            type Value = Epoch;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                write!(
+                    formatter,
+                    "an MJD number, or an epoch string such as \"J2000\", \"JD 2451545.0\" or \"2000-01-01T12:00:00 TT\""
+                )
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                if !v.is_finite() {
+                    return Err(E::custom("MJD must be finite"));
+                }
+                Ok(Epoch(Mjd::from(v)))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Epoch(Mjd::from_two_part(TimeScale::Tt, v, 0.0)))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                self.visit_i64(i64::try_from(v).map_err(E::custom)?)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(EpochVisitor)
+    }
+}
+
+impl serde::Serialize for Epoch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn parse(s: &str) -> Mjd {
+        s.parse::<Epoch>().unwrap().0
+    }
+
+    #[test]
+    fn parse_j2000_forms() {
+        let j2000 = Mjd::j2000();
+        for s in [
+            "J2000",
+            "J2000.0",
+            "JD 2451545.0",
+            "jd 2451545 TT",
+            "MJD 51544.5",
+            "51544.5",
+            "2000-01-01T12:00:00 TT",
+            "2000-01-01 12:00:00.000 tt",
+            "2000-01-01T11:58:55.816Z",
+            "2000-01-01T11:58:55.816 UTC",
+        ] {
+            assert_abs_diff_eq!(
+                parse(s).seconds_since(&j2000, TimeScale::Tt),
+                0.0,
+                epsilon = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn parse_other_scales() {
+        let tai = parse("2000-01-01T12:00:00 TAI");
+        assert_abs_diff_eq!(tai.j2000_seconds(TimeScale::Tt), 32.184, epsilon = 1e-6);
+        let tdb = parse("JD 2451545.0 TDB");
+        assert_abs_diff_eq!(tdb.j2000_seconds(TimeScale::Tdb), 0.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(
+            parse("2000-01-02").mjd_in(TimeScale::Utc),
+            51545.0,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn errors_point_at_span() {
+        let err = "JD 24x51545".parse::<Epoch>().unwrap_err();
+        assert!(matches!(err, EpochFromStrError::InvalidNumber(..)));
+        assert_eq!(err.span(), 3..11);
+
+        let err = "2000-01-01T12:00:00 GPS".parse::<Epoch>().unwrap_err();
+        assert!(matches!(err, EpochFromStrError::UnknownTimeScale(..)));
+        assert_eq!(err.span(), 20..23);
+
+        let err = "2000-13-01".parse::<Epoch>().unwrap_err();
+        assert!(matches!(err, EpochFromStrError::InvalidDate(..)));
+    }
+
+    #[test]
+    fn deserialize_epoch_from_yaml() {
+        let epoch: Epoch = serde_yaml::from_str("51544.5").unwrap();
+        assert_eq!(epoch.0, Mjd::j2000());
+        let epoch: Epoch = serde_yaml::from_str("J2000").unwrap();
+        assert_eq!(epoch.0, Mjd::j2000());
+    }
+}
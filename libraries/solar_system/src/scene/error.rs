@@ -1,3 +1,4 @@
+use crate::scene::quantity::Dimension;
 use std::ops;
 use thiserror::Error;
 
@@ -9,10 +10,80 @@ pub enum SceneLoadError {
     ParseError(#[from] serde_yaml::Error),
     #[error("Camera target not found: {0:?}")]
     CameraTargetNotFound(String),
+    #[error("Trail reference not found: {0:?}")]
+    TrailReferenceNotFound(String),
+    #[error("Unsupported mesh format, only glTF (.gltf, .glb) is supported: {0:?}")]
+    UnsupportedMesh(String),
 }
 
 #[derive(Debug, Error)]
 pub enum DurationFromStrError {
     #[error("Failed to parse duration string: {:?}", &.0[.1.clone()])]
     MalformedString(String, ops::Range<usize>),
+    #[error("Unknown duration unit: {:?}", &.0[.1.clone()])]
+    UnknownUnit(String, ops::Range<usize>),
+    #[error("Invalid ISO 8601 duration: {:?}", &.0[.1.clone()])]
+    MalformedIso8601(String, ops::Range<usize>),
+}
+
+impl DurationFromStrError {
+    /// Span of the offending part within the input string.
+    pub fn span(&self) -> ops::Range<usize> {
+        match self {
+            Self::MalformedString(_, span)
+            | Self::UnknownUnit(_, span)
+            | Self::MalformedIso8601(_, span) => span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EpochFromStrError {
+    #[error("Invalid number in epoch: {:?}", &.0[.1.clone()])]
+    InvalidNumber(String, ops::Range<usize>),
+    #[error("Invalid date in epoch: {:?}", &.0[.1.clone()])]
+    InvalidDate(String, ops::Range<usize>),
+    #[error("Unknown time scale: {:?}", &.0[.1.clone()])]
+    UnknownTimeScale(String, ops::Range<usize>),
+}
+
+impl EpochFromStrError {
+    /// Span of the offending part within the input string.
+    pub fn span(&self) -> ops::Range<usize> {
+        match self {
+            Self::InvalidNumber(_, span)
+            | Self::InvalidDate(_, span)
+            | Self::UnknownTimeScale(_, span) => span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QuantityFromStrError {
+    #[error("Invalid number: {:?}", &.0[.1.clone()])]
+    InvalidNumber(String, ops::Range<usize>),
+    #[error("Unexpected character: {:?}", &.0[.1.clone()])]
+    UnexpectedCharacter(String, ops::Range<usize>),
+    #[error("Unexpected token: {:?}", &.0[.1.clone()])]
+    UnexpectedToken(String, ops::Range<usize>),
+    #[error("Unexpected end of expression: {0:?}")]
+    UnexpectedEnd(String, ops::Range<usize>),
+    #[error("Unknown unit or constant: {:?}", &.0[.1.clone()])]
+    UnknownIdentifier(String, ops::Range<usize>),
+    #[error("Cannot mix {} and {} in {:?}", .2, .3, &.0[.1.clone()])]
+    DimensionMismatch(String, ops::Range<usize>, Dimension, Dimension),
+}
+
+impl QuantityFromStrError {
+    /// Span of the offending part within the input string.
+    pub fn span(&self) -> ops::Range<usize> {
+        match self {
+            Self::InvalidNumber(_, span)
+            | Self::UnexpectedCharacter(_, span)
+            | Self::UnexpectedToken(_, span)
+            | Self::UnexpectedEnd(_, span)
+            | Self::UnknownIdentifier(_, span)
+            | Self::DimensionMismatch(_, span, ..) => span.clone(),
+        }
+    }
 }
@@ -1,47 +1,333 @@
-use crate::mjd::Mjd;
 use crate::orbit;
 use crate::orbit::KeplerElements;
+use crate::planet_material::PlanetMaterialExtension;
+use crate::procedural;
+use crate::scene::distance::Length;
 use crate::scene::duration::Duration;
+use crate::scene::epoch::Epoch;
 use crate::scene::si_prefix::SiPrefixed;
+use crate::sun;
 use bevy::asset::Asset;
 use bevy::math::Vec3;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Physically based material of a body, either written inline in the scene or in its own
+/// `.material.yaml` file. Texture paths are relative to the assets folder.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct PlanetMaterial {
+    #[serde(default = "PlanetMaterial::default_color")]
     pub color: Vec3,
+    #[serde(default = "PlanetMaterial::default_alpha")]
+    pub alpha: f32,
     #[serde(default)]
     pub emissive_power: SiPrefixed,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Emission color, defaulting to the base color.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emissive_color: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emissive_texture: Option<String>,
+    /// Base color texture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub texture: Option<String>,
+    /// Surface generated at load time, used when there is no texture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub procedural: Option<ProceduralSurface>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal_map: Option<String>,
+    /// Ocean mask, white where the surface is glossy water.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub specular_map: Option<String>,
+    #[serde(default = "PlanetMaterial::default_ocean_roughness")]
+    pub ocean_roughness: f32,
+    #[serde(default = "PlanetMaterial::default_reflectance")]
+    pub ocean_reflectance: f32,
+    /// City lights, emitted on the night side.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub night_lights: Option<String>,
+    #[serde(default = "PlanetMaterial::default_night_lights_power")]
+    pub night_lights_power: SiPrefixed,
+    /// Sun elevation in degrees over which night lights fade out around the terminator.
+    #[serde(default = "PlanetMaterial::default_twilight_angle")]
+    pub twilight_angle: f32,
+    #[serde(default = "PlanetMaterial::default_roughness")]
+    pub roughness: f32,
+    #[serde(default)]
+    pub metallic: f32,
+    #[serde(default = "PlanetMaterial::default_reflectance")]
+    pub reflectance: f32,
+    #[serde(default)]
+    pub alpha_mode: MaterialAlphaMode,
+}
+
+impl Default for PlanetMaterial {
+    fn default() -> Self {
+        Self {
+            color: Self::default_color(),
+            alpha: Self::default_alpha(),
+            emissive_power: SiPrefixed::default(),
+            emissive_color: None,
+            emissive_texture: None,
+            texture: None,
+            procedural: None,
+            normal_map: None,
+            specular_map: None,
+            ocean_roughness: Self::default_ocean_roughness(),
+            ocean_reflectance: Self::default_reflectance(),
+            night_lights: None,
+            night_lights_power: Self::default_night_lights_power(),
+            twilight_angle: Self::default_twilight_angle(),
+            roughness: Self::default_roughness(),
+            metallic: 0.0,
+            reflectance: Self::default_reflectance(),
+            alpha_mode: MaterialAlphaMode::default(),
+        }
+    }
+}
+
+impl PlanetMaterial {
+    fn default_color() -> Vec3 {
+        Vec3::ONE
+    }
+
+    fn default_alpha() -> f32 {
+        1.0
+    }
+
+    fn default_roughness() -> f32 {
+        0.5
+    }
+
+    fn default_reflectance() -> f32 {
+        0.5
+    }
+
+    fn default_ocean_roughness() -> f32 {
+        0.1
+    }
+
+    fn default_night_lights_power() -> SiPrefixed {
+        SiPrefixed::from(1.0)
+    }
+
+    fn default_twilight_angle() -> f32 {
+        PlanetMaterialExtension::DEFAULT_TWILIGHT_ANGLE_DEG
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaterialAlphaMode {
+    #[default]
+    Opaque,
+    /// Alpha test against the given cutoff.
+    Mask(f32),
+    Blend,
+    Premultiplied,
+    Add,
+    Multiply,
+}
+
+impl From<MaterialAlphaMode> for AlphaMode {
+    fn from(value: MaterialAlphaMode) -> Self {
+        match value {
+            MaterialAlphaMode::Opaque => AlphaMode::Opaque,
+            MaterialAlphaMode::Mask(cutoff) => AlphaMode::Mask(cutoff),
+            MaterialAlphaMode::Blend => AlphaMode::Blend,
+            MaterialAlphaMode::Premultiplied => AlphaMode::Premultiplied,
+            MaterialAlphaMode::Add => AlphaMode::Add,
+            MaterialAlphaMode::Multiply => AlphaMode::Multiply,
+        }
+    }
+}
+
+/// Seeded procedural surface. The same seed always generates the same textures.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProceduralSurface {
+    #[serde(default)]
+    pub seed: u32,
+    /// Width of the generated textures, their height being half of it.
+    #[serde(default = "ProceduralSurface::default_resolution")]
+    pub resolution: u32,
+    #[serde(flatten)]
+    pub preset: SurfacePreset,
+}
+
+impl ProceduralSurface {
+    fn default_resolution() -> u32 {
+        1024
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "preset", rename_all = "kebab-case")]
+pub enum SurfacePreset {
+    Rocky(RockySurface),
+    GasGiant(GasGiantSurface),
+    Icy(IcySurface),
+    Lava(LavaSurface),
+}
+
+/// Cratered rock.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RockySurface {
+    /// Crater density, 1 being saturated.
+    pub craters: f32,
+    pub low_color: Vec3,
+    pub high_color: Vec3,
+}
+
+impl Default for RockySurface {
+    fn default() -> Self {
+        Self {
+            craters: 0.5,
+            low_color: Vec3::splat(0.35),
+            high_color: Vec3::splat(0.6),
+        }
+    }
+}
+
+/// Latitudinal bands with oval storms.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GasGiantSurface {
+    pub bands: u32,
+    pub turbulence: f32,
+    pub storms: u32,
+    /// Colors cycled through by the bands.
+    pub colors: Vec<Vec3>,
+    pub storm_color: Vec3,
+}
+
+impl Default for GasGiantSurface {
+    fn default() -> Self {
+        Self {
+            bands: 14,
+            turbulence: 0.5,
+            storms: 3,
+            colors: vec![
+                Vec3::new(0.9, 0.8, 0.6),
+                Vec3::new(0.75, 0.6, 0.4),
+                Vec3::new(0.95, 0.9, 0.8),
+                Vec3::new(0.6, 0.45, 0.3),
+            ],
+            storm_color: Vec3::new(0.8, 0.4, 0.3),
+        }
+    }
+}
+
+/// Smooth ice crossed by cracks.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct IcySurface {
+    /// Visibility of the cracks, in `[0, 1]`.
+    pub cracks: f32,
+    pub color: Vec3,
+    pub crack_color: Vec3,
+}
+
+impl Default for IcySurface {
+    fn default() -> Self {
+        Self {
+            cracks: 0.5,
+            color: Vec3::new(0.9, 0.92, 0.95),
+            crack_color: Vec3::new(0.55, 0.4, 0.3),
+        }
+    }
+}
+
+/// Dark crust with lakes of lava, which are emissive: set `emissive-power` to make them glow.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LavaSurface {
+    /// Approximate fraction of the surface covered with lava.
+    pub coverage: f32,
+    pub rock_color: Vec3,
+    pub lava_color: Vec3,
+}
+
+impl Default for LavaSurface {
+    fn default() -> Self {
+        Self {
+            coverage: 0.25,
+            rock_color: Vec3::new(0.15, 0.13, 0.12),
+            lava_color: Vec3::new(1.0, 0.45, 0.1),
+        }
+    }
+}
+
+impl From<ProceduralSurface> for procedural::ProceduralSurface {
+    fn from(val: ProceduralSurface) -> Self {
+        let color = |color: Vec3| Srgba::from_f32_array_no_alpha(color.to_array());
+        procedural::ProceduralSurface {
+            seed: val.seed,
+            resolution: val.resolution,
+            preset: match val.preset {
+                SurfacePreset::Rocky(rocky) => procedural::SurfacePreset::Rocky {
+                    craters: rocky.craters,
+                    low_color: color(rocky.low_color),
+                    high_color: color(rocky.high_color),
+                },
+                SurfacePreset::GasGiant(gas_giant) => procedural::SurfacePreset::GasGiant {
+                    bands: gas_giant.bands,
+                    turbulence: gas_giant.turbulence,
+                    storms: gas_giant.storms,
+                    colors: gas_giant.colors.into_iter().map(color).collect(),
+                    storm_color: color(gas_giant.storm_color),
+                },
+                SurfacePreset::Icy(icy) => procedural::SurfacePreset::Icy {
+                    cracks: icy.cracks,
+                    color: color(icy.color),
+                    crack_color: color(icy.crack_color),
+                },
+                SurfacePreset::Lava(lava) => procedural::SurfacePreset::Lava {
+                    coverage: lava.coverage,
+                    rock_color: color(lava.rock_color),
+                    lava_color: color(lava.lava_color),
+                },
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Component)]
 #[serde(untagged)]
 pub enum MaterialSource {
-    // Path(String),
+    /// Path to a `.material.yaml` file, relative to the assets folder. Bodies referencing the same
+    /// file share the material.
+    Path(String),
     Inline(PlanetMaterial),
 }
 
+impl Default for MaterialSource {
+    fn default() -> Self {
+        Self::Inline(PlanetMaterial::default())
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Component)]
 #[serde(rename_all = "kebab-case")]
 pub struct OrbitalElements {
-    pub epoch: Mjd,
+    pub epoch: Epoch,
     pub period: Duration,
-    pub semi_major_axis: SiPrefixed,
+    pub semi_major_axis: Length,
     pub eccentricity: f64,
     pub inclination: f64,
     pub longitude_of_ascending_node: f64,
     pub argument_of_periapsis: f64,
+    /// Color of the orbit line, yellow by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Vec3>,
 }
 
 impl From<OrbitalElements> for orbit::KeplerElements {
     fn from(val: OrbitalElements) -> Self {
         KeplerElements {
-            epoch: val.epoch,
+            epoch: val.epoch.0,
             period: val.period.as_seconds(),
             semi_major_axis: val.semi_major_axis.as_base_value(),
             eccentricity: val.eccentricity,
@@ -55,21 +341,341 @@ impl From<OrbitalElements> for orbit::KeplerElements {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Planet {
-    pub radius: SiPrefixed,
+    /// Mean radius of the body, also used for its shape when none is given.
+    pub radius: Length,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape: Option<Shape>,
     pub siderial_day: Duration,
+    /// Material of the body, ignored for stars.
+    #[serde(default)]
     pub material: MaterialSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub star: Option<Star>,
+    /// Geometric albedo, for the brightness of the body once it is smaller than a pixel.
+    #[serde(default = "Planet::default_albedo")]
+    pub albedo: f32,
     pub inclination: f32,
     pub orbit: Option<OrbitalElements>,
+    /// Trail of past positions drawn behind the body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail: Option<Trail>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rings: Option<Rings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub atmosphere: Option<Atmosphere>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terrain: Option<Terrain>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub satellites: HashMap<String, Planet>,
 }
 
+impl Planet {
+    fn default_albedo() -> f32 {
+        0.3
+    }
+}
+
+/// Star, drawn as a blackbody with limb darkening and granulation, surrounded by a corona.
+/// Defaults to the values of the Sun.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Star {
+    /// Effective temperature in kelvin, from which the color is derived.
+    #[serde(default = "Star::default_temperature")]
+    pub temperature: f32,
+    /// Radiated power in watts, which sets the illuminance of the bodies around the star.
+    #[serde(default = "Star::default_luminosity")]
+    pub luminosity: SiPrefixed,
+    #[serde(default = "Star::default_emissive_power")]
+    pub emissive_power: SiPrefixed,
+    /// Linear limb darkening coefficient: the edge of the disc is this much darker than its
+    /// center.
+    #[serde(default = "Star::default_limb_darkening")]
+    pub limb_darkening: f32,
+    /// Contrast of the granulation.
+    #[serde(default = "Star::default_granulation")]
+    pub granulation: f32,
+    /// Number of granulation cells along a radius.
+    #[serde(default = "Star::default_granulation_scale")]
+    pub granulation_scale: f32,
+    /// Extent of the corona, in star radii.
+    #[serde(default = "Star::default_corona_extent")]
+    pub corona_extent: f32,
+    /// Brightness of the corona at the edge of the disc, relative to the center of the disc.
+    #[serde(default = "Star::default_corona_intensity")]
+    pub corona_intensity: f32,
+}
+
+impl Star {
+    fn default_temperature() -> f32 {
+        5772.0
+    }
+
+    fn default_luminosity() -> SiPrefixed {
+        SiPrefixed::from_base_value(sun::SOLAR_LUMINOSITY)
+    }
+
+    fn default_emissive_power() -> SiPrefixed {
+        SiPrefixed::from(120e3)
+    }
+
+    fn default_limb_darkening() -> f32 {
+        0.6
+    }
+
+    fn default_granulation() -> f32 {
+        0.1
+    }
+
+    fn default_granulation_scale() -> f32 {
+        40.0
+    }
+
+    fn default_corona_extent() -> f32 {
+        4.0
+    }
+
+    fn default_corona_intensity() -> f32 {
+        0.05
+    }
+}
+
+/// Shape of a body which is not a sphere.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", untagged)]
+pub enum Shape {
+    /// Triaxial ellipsoid from its semi-axes `[a, b, c]`, where `a` points towards the prime
+    /// meridian, `b` is the other equatorial axis and `c` is the polar axis.
+    Ellipsoid { ellipsoid: [Length; 3] },
+    /// glTF file (`.gltf` or `.glb`), of which the first primitive is used unless the path has a
+    /// label. Other formats, such as OBJ, are rejected when loading the scene. `unit` is the length
+    /// of one unit of the mesh, defaulting to 1 m.
+    Mesh {
+        mesh: String,
+        #[serde(default = "Shape::default_unit")]
+        unit: Length,
+    },
+}
+
+impl Shape {
+    fn default_unit() -> Length {
+        Length::from_base_value(1.0)
+    }
+}
+
+/// Line following the past positions of a body.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Trail {
+    /// How far back positions are kept.
+    pub duration: Duration,
+    /// Color of the line, white by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Vec3>,
+    /// Body around which positions are recorded, without rotating with it. Defaults to the body
+    /// being orbited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+/// Rings in the equatorial plane of a body.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Rings {
+    pub inner_radius: Length,
+    pub outer_radius: Length,
+    /// Radial strip texture, from the inner edge on the left to the outer edge on the right, with
+    /// the opacity in the alpha channel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+    /// Radial gradient, used when there is no texture.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gradient: Vec<RingGradientStop>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RingGradientStop {
+    /// Position from the inner (0) to the outer (1) edge.
+    pub position: f32,
+    pub color: Vec3,
+    #[serde(default = "RingGradientStop::default_opacity")]
+    pub opacity: f32,
+}
+
+impl RingGradientStop {
+    fn default_opacity() -> f32 {
+        1.0
+    }
+}
+
+/// Scattering atmosphere around a body. Coefficients are per meter at ground level, and default to
+/// those of the Earth.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Atmosphere {
+    /// Radius of the ground, defaulting to the radius of the body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius: Option<Length>,
+    #[serde(default = "Atmosphere::default_height")]
+    pub height: Length,
+    #[serde(default = "Atmosphere::default_rayleigh_scattering")]
+    pub rayleigh_scattering: Vec3,
+    #[serde(default = "Atmosphere::default_rayleigh_scale_height")]
+    pub rayleigh_scale_height: Length,
+    #[serde(default = "Atmosphere::default_mie_scattering")]
+    pub mie_scattering: f32,
+    #[serde(default = "Atmosphere::default_mie_absorption")]
+    pub mie_absorption: f32,
+    #[serde(default = "Atmosphere::default_mie_scale_height")]
+    pub mie_scale_height: Length,
+    /// Asymmetry of the Mie phase function, from -1 (backwards) to 1 (forwards).
+    #[serde(default = "Atmosphere::default_mie_asymmetry")]
+    pub mie_asymmetry: f32,
+}
+
+impl Atmosphere {
+    fn default_height() -> Length {
+        Length::from_base_value(100e3)
+    }
+
+    fn default_rayleigh_scattering() -> Vec3 {
+        Vec3::new(5.802e-6, 13.558e-6, 33.1e-6)
+    }
+
+    fn default_rayleigh_scale_height() -> Length {
+        Length::from_base_value(8e3)
+    }
+
+    fn default_mie_scattering() -> f32 {
+        3.996e-6
+    }
+
+    fn default_mie_absorption() -> f32 {
+        4.4e-6
+    }
+
+    fn default_mie_scale_height() -> Length {
+        Length::from_base_value(1.2e3)
+    }
+
+    fn default_mie_asymmetry() -> f32 {
+        0.8
+    }
+}
+
+/// Level of detail terrain, replacing the mesh of the body up close. Elevations are relative to
+/// the shape of the body: its sphere, its ellipsoid, or the ellipsoid fitting its mesh.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Terrain {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height_map: Option<HeightMap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<TerrainNoise>,
+    /// Number of vertices along the side of a chunk.
+    #[serde(default = "Terrain::default_resolution")]
+    pub resolution: u32,
+    /// Deepest level of detail, clamped to 30.
+    #[serde(default = "Terrain::default_max_depth")]
+    pub max_depth: u8,
+    /// Screen-space error, in pixels, above which chunks are split.
+    #[serde(default = "Terrain::default_max_screen_error")]
+    pub max_screen_error: f32,
+}
+
+impl Terrain {
+    fn default_resolution() -> u32 {
+        33
+    }
+
+    fn default_max_depth() -> u8 {
+        16
+    }
+
+    fn default_max_screen_error() -> f32 {
+        2.0
+    }
+}
+
+/// Digital elevation model, as a grayscale image in an equirectangular projection.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HeightMap {
+    pub path: String,
+    /// Elevation of black.
+    #[serde(default)]
+    pub min: Length,
+    /// Elevation of white.
+    pub max: Length,
+}
+
+/// Fractal noise added to the elevation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TerrainNoise {
+    #[serde(default)]
+    pub seed: u32,
+    pub amplitude: Length,
+    /// Size of the largest features.
+    pub wavelength: Length,
+    #[serde(default = "TerrainNoise::default_octaves")]
+    pub octaves: u32,
+    #[serde(default = "TerrainNoise::default_lacunarity")]
+    pub lacunarity: f64,
+    #[serde(default = "TerrainNoise::default_persistence")]
+    pub persistence: f64,
+}
+
+impl TerrainNoise {
+    fn default_octaves() -> u32 {
+        6
+    }
+
+    fn default_lacunarity() -> f64 {
+        2.0
+    }
+
+    fn default_persistence() -> f64 {
+        0.5
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct RootPlanet {
     pub name: String,
     #[serde(flatten)]
-    pub planet: Planet,
+    pub body: RootBody,
+}
+
+/// Root of the scene: either a body, usually the star, or the barycenter of a multiple system.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RootBody {
+    Barycenter(Barycenter),
+    Planet(Planet),
+}
+
+/// Massless center of a multiple system, around which its stars orbit.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Barycenter {
+    /// Marks the root as a barycenter, only `true` is accepted.
+    #[serde(deserialize_with = "deserialize_marker")]
+    pub barycenter: bool,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub satellites: HashMap<String, Planet>,
+}
+
+/// Reads a marker field, which must be `true`, so that `barycenter: false` is not taken for a
+/// barycenter.
+fn deserialize_marker<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    if bool::deserialize(deserializer)? {
+        Ok(true)
+    } else {
+        Err(serde::de::Error::custom("marker can only be `true`"))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Component, Reflect)]
@@ -77,7 +683,7 @@ pub struct RootPlanet {
 #[serde(rename_all = "kebab-case")]
 pub struct CameraConfig {
     pub target: String,
-    pub radius: SiPrefixed,
+    pub radius: Length,
     #[serde(default)]
     pub rotation: [f32; 2],
 }
@@ -88,3 +694,121 @@ pub struct SolarSystem {
     pub root: RootPlanet,
     pub camera: CameraConfig,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_material_sources() {
+        let source: MaterialSource =
+            serde_yaml::from_str("materials/grey-rock.material.yaml").unwrap();
+        assert!(
+            matches!(source, MaterialSource::Path(path) if path == "materials/grey-rock.material.yaml")
+        );
+
+        let source: MaterialSource = serde_yaml::from_str(
+            "{ color: [0.5, 0.5, 0.5], roughness: 0.9, alpha-mode: { mask: 0.5 } }",
+        )
+        .unwrap();
+        let MaterialSource::Inline(material) = source else {
+            panic!("Expected an inline material");
+        };
+        assert_eq!(material.roughness, 0.9);
+        assert_eq!(material.reflectance, 0.5);
+        assert_eq!(material.alpha_mode, MaterialAlphaMode::Mask(0.5));
+    }
+
+    #[test]
+    fn parse_procedural_surfaces() {
+        let material: PlanetMaterial = serde_yaml::from_str(
+            "{ procedural: { preset: gas-giant, seed: 7, bands: 20 }, roughness: 0.8 }",
+        )
+        .unwrap();
+        assert_eq!(material.color, Vec3::ONE);
+        let procedural = material.procedural.unwrap();
+        assert_eq!(procedural.seed, 7);
+        assert_eq!(procedural.resolution, 1024);
+        let SurfacePreset::GasGiant(gas_giant) = procedural.preset else {
+            panic!("Expected a gas giant");
+        };
+        assert_eq!(gas_giant.bands, 20);
+        assert_eq!(gas_giant.storms, 3);
+
+        let procedural: ProceduralSurface = serde_yaml::from_str("preset: rocky").unwrap();
+        assert!(matches!(procedural.preset, SurfacePreset::Rocky(_)));
+    }
+
+    #[test]
+    fn parse_stars() {
+        let planet: Planet = serde_yaml::from_str(
+            "{ radius: 696340 km, siderial-day: 24.47d, inclination: 0, star: { temperature: 3042 } }",
+        )
+        .unwrap();
+        let star = planet.star.unwrap();
+        assert_eq!(star.temperature, 3042.0);
+        assert_eq!(star.limb_darkening, 0.6);
+        assert!(matches!(planet.material, MaterialSource::Inline(_)));
+        assert_eq!(planet.albedo, 0.3);
+    }
+
+    #[test]
+    fn parse_barycenters() {
+        let root: RootPlanet = serde_yaml::from_str(
+            r#"
+name: Alpha Centauri
+barycenter: true
+satellites:
+  Alpha Centauri A: { radius: 851000 km, siderial-day: 22d, inclination: 0, star: {} }
+  Alpha Centauri B: { radius: 600000 km, siderial-day: 41d, inclination: 0, star: {} }
+"#,
+        )
+        .unwrap();
+        let RootBody::Barycenter(barycenter) = root.body else {
+            panic!("Expected a barycenter");
+        };
+        assert_eq!(barycenter.satellites.len(), 2);
+
+        let root: RootPlanet = serde_yaml::from_str(
+            "{ name: Sun, radius: 696340 km, siderial-day: 24.47d, inclination: 0 }",
+        )
+        .unwrap();
+        assert!(matches!(root.body, RootBody::Planet(_)));
+
+        let root = serde_yaml::from_str::<RootPlanet>(
+            "{ name: Alpha Centauri, barycenter: false, satellites: {} }",
+        );
+        assert!(root.is_err(), "`barycenter: false` is not a barycenter");
+    }
+
+    #[test]
+    fn parse_shapes() {
+        let shape: Shape = serde_yaml::from_str("{ ellipsoid: [13 km, 11.4 km, 9.1 km] }").unwrap();
+        let Shape::Ellipsoid {
+            ellipsoid: [a, _, c],
+        } = shape
+        else {
+            panic!("Expected an ellipsoid");
+        };
+        assert_eq!(a.as_base_value(), 13e3);
+        assert!((c.as_base_value() - 9.1e3).abs() < 1e-9);
+
+        let shape: Shape = serde_yaml::from_str("{ mesh: models/phobos.glb, unit: 1 km }").unwrap();
+        let Shape::Mesh { mesh, unit } = shape else {
+            panic!("Expected a mesh");
+        };
+        assert_eq!(mesh, "models/phobos.glb");
+        assert_eq!(unit.as_base_value(), 1e3);
+    }
+
+    #[test]
+    fn parse_atmosphere_defaults() {
+        let atmosphere: Atmosphere =
+            serde_yaml::from_str("{ height: 11 km, rayleigh-scale-height: 10 km }").unwrap();
+        assert!(atmosphere.radius.is_none());
+        assert_eq!(atmosphere.height.as_base_value(), 11e3);
+        assert_eq!(atmosphere.rayleigh_scale_height.as_base_value(), 10e3);
+        assert!((atmosphere.mie_scale_height.as_base_value() - 1.2e3).abs() < 1e-9);
+        assert_eq!(atmosphere.mie_asymmetry, 0.8);
+    }
+}
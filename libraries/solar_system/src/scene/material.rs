@@ -0,0 +1,117 @@
+use crate::planet_material::{PlanetMaterialExtension, PlanetStandardMaterial};
+use crate::procedural::ProceduralSurface;
+use crate::scene::error;
+use crate::scene::manifest::PlanetMaterial;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::image::ImageLoaderSettings;
+use bevy::prelude::*;
+use bevy::utils::ConditionalSendFuture;
+
+/// Loads `.material.yaml` files into [`PlanetStandardMaterial`]s.
+#[derive(Debug, Default)]
+pub struct PlanetMaterialLoader;
+
+impl AssetLoader for PlanetMaterialLoader {
+    type Asset = PlanetStandardMaterial;
+    type Settings = ();
+    type Error = error::SceneLoadError;
+
+    fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext,
+    ) -> impl ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
+        async move {
+            let input = {
+                let mut buf = String::new();
+                reader.read_to_string(&mut buf).await?;
+                buf
+            };
+            let material = serde_yaml::from_str::<PlanetMaterial>(&input)?;
+            Ok(create_planet_material(
+                load_context,
+                "Procedural",
+                &material,
+            ))
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["material.yaml"]
+    }
+}
+
+/// Creates the material, loading its textures as dependencies of the asset being loaded.
+/// Procedurally generated textures are added as labeled assets under `label`.
+pub fn create_planet_material(
+    load_context: &mut LoadContext,
+    label: &str,
+    material: &PlanetMaterial,
+) -> PlanetStandardMaterial {
+    let base_color: LinearRgba = Srgba::from_f32_array_no_alpha(material.color.to_array()).into();
+    let emissive_color: LinearRgba = material
+        .emissive_color
+        .map(|color| Srgba::from_f32_array_no_alpha(color.to_array()).into())
+        .unwrap_or(base_color);
+
+    let mut base_color_texture = material
+        .texture
+        .as_ref()
+        .map(|path| load_context.load(path));
+    let mut emissive_texture = material
+        .emissive_texture
+        .as_ref()
+        .map(|path| load_context.load(path));
+    if let (None, Some(procedural)) = (&base_color_texture, &material.procedural) {
+        let surface = ProceduralSurface::from(procedural.clone()).generate();
+        base_color_texture =
+            Some(load_context.add_labeled_asset(format!("{label}/Color"), surface.color));
+        if let Some(emissive) = surface.emissive {
+            emissive_texture =
+                Some(load_context.add_labeled_asset(format!("{label}/Emissive"), emissive));
+        }
+    }
+
+    let base = StandardMaterial {
+        base_color: base_color.with_alpha(material.alpha).into(),
+        base_color_texture,
+        emissive: emissive_color * material.emissive_power.as_base_value() as f32,
+        emissive_texture,
+        normal_map_texture: material
+            .normal_map
+            .as_ref()
+            .map(|path| load_linear(load_context, path)),
+        perceptual_roughness: material.roughness,
+        metallic: material.metallic,
+        reflectance: material.reflectance,
+        alpha_mode: material.alpha_mode.into(),
+        ..Default::default()
+    };
+
+    let mut extension =
+        PlanetMaterialExtension::default().with_twilight_angle(material.twilight_angle);
+    if let Some(path) = &material.night_lights {
+        extension = extension.with_night_lights(
+            load_context.load(path),
+            material.night_lights_power.as_base_value() as f32,
+        );
+    }
+    if let Some(path) = &material.specular_map {
+        extension = extension.with_ocean_mask(
+            load_linear(load_context, path),
+            material.ocean_roughness,
+            material.ocean_reflectance,
+        );
+    }
+    PlanetStandardMaterial { base, extension }
+}
+
+/// Loads a non-color texture, such as a normal map or a mask.
+pub(crate) fn load_linear(load_context: &mut LoadContext, path: &str) -> Handle<Image> {
+    load_context
+        .loader()
+        .with_settings(|settings: &mut ImageLoaderSettings| settings.is_srgb = false)
+        .load(path)
+}
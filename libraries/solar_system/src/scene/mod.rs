@@ -7,8 +7,11 @@ mod asset;
 pub mod components;
 pub mod distance;
 mod duration;
+mod epoch;
 mod error;
 mod manifest;
+mod material;
+pub mod quantity;
 pub mod si_prefix;
 
 use crate::scene::components::SolarSystemRoot;
@@ -28,6 +31,7 @@ impl<Prec: GridPrecision> Plugin for PlanetScenePlugin<Prec> {
     fn build(&self, app: &mut App) {
         app.init_asset::<asset::SolarSystem>()
             .init_asset_loader::<asset::SolarSystemLoader<Prec>>()
+            .init_asset_loader::<material::PlanetMaterialLoader>()
             .register_type::<components::SolarSystemRoot>()
             .register_type::<components::SceneCamera>()
             .register_type::<manifest::CameraConfig>()
@@ -0,0 +1,480 @@
+use crate::scene::error::QuantityFromStrError;
+use crate::scene::si_prefix::SiPrefix;
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops;
+use std::str::FromStr;
+
+/// Physical dimension of a [`Quantity`], as exponents of the SI base dimensions.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Dimension {
+    pub length: i8,
+    pub mass: i8,
+    pub time: i8,
+}
+
+impl Dimension {
+    pub const NONE: Self = Self::new(0, 0, 0);
+    pub const LENGTH: Self = Self::new(1, 0, 0);
+    pub const MASS: Self = Self::new(0, 1, 0);
+    pub const TIME: Self = Self::new(0, 0, 1);
+    pub const POWER: Self = Self::new(2, 1, -3);
+
+    pub const fn new(length: i8, mass: i8, time: i8) -> Self {
+        Self { length, mass, time }
+    }
+}
+
+impl ops::Mul for Dimension {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.length + rhs.length,
+            self.mass + rhs.mass,
+            self.time + rhs.time,
+        )
+    }
+}
+
+impl ops::Div for Dimension {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.length - rhs.length,
+            self.mass - rhs.mass,
+            self.time - rhs.time,
+        )
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if *self == Self::NONE {
+            return write!(f, "dimensionless");
+        }
+        let mut first = true;
+        for (symbol, exponent) in [("m", self.length), ("kg", self.mass), ("s", self.time)] {
+            if exponent == 0 {
+                continue;
+            }
+            if !first {
+                write!(f, " ")?;
+            }
+            first = false;
+            write!(f, "{symbol}")?;
+            if exponent != 1 {
+                write!(f, "^{exponent}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value in SI base units together with its dimension.
+///
+/// Quantities parse from simple arithmetic expressions over numbers, units and named constants,
+/// e.g. `1.52 AU`, `384400 km`, `2 * 696340 km` or `(1 + 0.0167) * 1 AU`. A unit or constant
+/// directly following a value multiplies it, binding tighter than `*` and `/`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    pub const fn new(value: f64, dimension: Dimension) -> Self {
+        Self { value, dimension }
+    }
+
+    pub const fn scalar(value: f64) -> Self {
+        Self::new(value, Dimension::NONE)
+    }
+
+    /// Parses an expression which has to be of the given dimension. Plain numbers are accepted as
+    /// well, in SI base units.
+    pub fn parse_dimension(s: &str, expected: Dimension) -> Result<Self, QuantityFromStrError> {
+        let quantity = s.parse::<Self>()?;
+        if quantity.dimension != expected && quantity.dimension != Dimension::NONE {
+            return Err(QuantityFromStrError::DimensionMismatch(
+                s.to_string(),
+                0..s.len(),
+                quantity.dimension,
+                expected,
+            ));
+        }
+        Ok(quantity)
+    }
+}
+
+/// Units recognized in expressions, with their factor to SI base units.
+const UNITS: [(&str, f64, Dimension); 15] = [
+    ("m", 1.0, Dimension::LENGTH),
+    ("AU", 149_597_870_700.0, Dimension::LENGTH),
+    ("au", 149_597_870_700.0, Dimension::LENGTH),
+    ("ly", 9_460_730_472_580_800.0, Dimension::LENGTH),
+    ("pc", 308_567_758_149_136_730.0, Dimension::LENGTH),
+    ("g", 1e-3, Dimension::MASS),
+    ("s", 1.0, Dimension::TIME),
+    ("min", 60.0, Dimension::TIME),
+    ("h", 3600.0, Dimension::TIME),
+    ("d", 86400.0, Dimension::TIME),
+    ("day", 86400.0, Dimension::TIME),
+    ("days", 86400.0, Dimension::TIME),
+    ("yr", 31_557_600.0, Dimension::TIME),
+    ("a", 31_557_600.0, Dimension::TIME),
+    ("W", 1.0, Dimension::POWER),
+];
+
+/// Units that accept an SI prefix, as in `km`, `kpc`, `Myr` or `kW`.
+const PREFIXABLE_UNITS: [&str; 7] = ["m", "g", "s", "W", "pc", "ly", "yr"];
+
+/// Named constants usable in expressions.
+const CONSTANTS: [(&str, f64, Dimension); 8] = [
+    ("pi", std::f64::consts::PI, Dimension::NONE),
+    ("tau", std::f64::consts::TAU, Dimension::NONE),
+    ("R_sun", 6.957e8, Dimension::LENGTH),
+    ("R_earth", 6.3781e6, Dimension::LENGTH),
+    ("R_jupiter", 7.1492e7, Dimension::LENGTH),
+    ("M_sun", 1.988_41e30, Dimension::MASS),
+    ("M_earth", 5.9722e24, Dimension::MASS),
+    ("L_sun", 3.828e26, Dimension::POWER),
+];
+
+fn lookup_identifier(name: &str) -> Option<Quantity> {
+    let find = |table: &[(&str, f64, Dimension)], name: &str| {
+        table
+            .iter()
+            .find(|(n, ..)| *n == name)
+            .map(|&(_, value, dimension)| Quantity::new(value, dimension))
+    };
+    if let Some(quantity) = find(&UNITS, name).or_else(|| find(&CONSTANTS, name)) {
+        return Some(quantity);
+    }
+
+    // `d` is ambiguous between deci and deca, so it is not accepted as a prefix
+    let mut chars = name.chars();
+    let prefix = chars
+        .next()
+        .filter(|&c| c != 'd')
+        .and_then(SiPrefix::from_char)?;
+    let unit = chars.as_str();
+    if !PREFIXABLE_UNITS.contains(&unit) {
+        return None;
+    }
+    let quantity = find(&UNITS, unit)?;
+    Some(Quantity::new(
+        quantity.value * prefix.factor(),
+        quantity.dimension,
+    ))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Token<'a> {
+    Number(f64),
+    Identifier(&'a str),
+    Operator(char),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token<'_>, ops::Range<usize>)>, QuantityFromStrError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let c = input[pos..].chars().next().unwrap();
+        let start = pos;
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+        let token = if c.is_ascii_digit() || c == '.' {
+            while pos < input.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                pos += 1;
+            }
+            // Exponent, only when actually followed by digits so that `2e` stays an identifier
+            if pos < input.len() && matches!(bytes[pos], b'e' | b'E') {
+                let mut end = pos + 1;
+                if end < input.len() && matches!(bytes[end], b'+' | b'-') {
+                    end += 1;
+                }
+                if end < input.len() && bytes[end].is_ascii_digit() {
+                    pos = end;
+                    while pos < input.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+            let value = input[start..pos]
+                .parse::<f64>()
+                .map_err(|_| QuantityFromStrError::InvalidNumber(input.to_string(), start..pos))?;
+            Token::Number(value)
+        } else if c.is_alphabetic() || c == '_' {
+            pos += input[pos..]
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(input.len() - pos);
+            Token::Identifier(&input[start..pos])
+        } else {
+            pos += c.len_utf8();
+            match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => {
+                    return Err(QuantityFromStrError::UnexpectedCharacter(
+                        input.to_string(),
+                        start..pos,
+                    ))
+                }
+            }
+        };
+        tokens.push((token, start..pos));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the token list. Each rule returns the evaluated quantity along
+/// with its span in the input.
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token<'a>, ops::Range<usize>)>,
+    pos: usize,
+}
+
+type ParseResult = Result<(Quantity, ops::Range<usize>), QuantityFromStrError>;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&(Token<'a>, ops::Range<usize>)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn unexpected(&self) -> QuantityFromStrError {
+        match self.peek() {
+            Some((_, span)) => {
+                QuantityFromStrError::UnexpectedToken(self.input.to_string(), span.clone())
+            }
+            None => QuantityFromStrError::UnexpectedEnd(
+                self.input.to_string(),
+                self.input.len()..self.input.len(),
+            ),
+        }
+    }
+
+    fn mismatch(
+        &self,
+        span: ops::Range<usize>,
+        lhs: Dimension,
+        rhs: Dimension,
+    ) -> QuantityFromStrError {
+        QuantityFromStrError::DimensionMismatch(self.input.to_string(), span, lhs, rhs)
+    }
+
+    /// `expr := term (('+' | '-') term)*`
+    fn expression(&mut self) -> ParseResult {
+        let (mut lhs, mut span) = self.term()?;
+        while let Some(&(Token::Operator(op @ ('+' | '-')), _)) = self.peek() {
+            self.pos += 1;
+            let (rhs, rhs_span) = self.term()?;
+            span = span.start..rhs_span.end;
+            if lhs.dimension != rhs.dimension {
+                return Err(self.mismatch(span, lhs.dimension, rhs.dimension));
+            }
+            lhs.value = if op == '+' {
+                lhs.value + rhs.value
+            } else {
+                lhs.value - rhs.value
+            };
+        }
+        Ok((lhs, span))
+    }
+
+    /// `term := implicit (('*' | '/') implicit)*`
+    fn term(&mut self) -> ParseResult {
+        let (mut lhs, mut span) = self.implicit()?;
+        while let Some(&(Token::Operator(op @ ('*' | '/')), _)) = self.peek() {
+            self.pos += 1;
+            let (rhs, rhs_span) = self.implicit()?;
+            span = span.start..rhs_span.end;
+            lhs = if op == '*' {
+                Quantity::new(lhs.value * rhs.value, lhs.dimension * rhs.dimension)
+            } else {
+                Quantity::new(lhs.value / rhs.value, lhs.dimension / rhs.dimension)
+            };
+        }
+        Ok((lhs, span))
+    }
+
+    /// `implicit := unary power*`, where each following power starts with a unit or constant.
+    ///
+    /// Implicit multiplication binds tighter than explicit operators, so that `1 km / 1 s` is a
+    /// speed.
+    fn implicit(&mut self) -> ParseResult {
+        let (mut lhs, mut span) = self.unary()?;
+        while let Some((Token::Identifier(_), _)) = self.peek() {
+            let (rhs, rhs_span) = self.power()?;
+            span = span.start..rhs_span.end;
+            lhs = Quantity::new(lhs.value * rhs.value, lhs.dimension * rhs.dimension);
+        }
+        Ok((lhs, span))
+    }
+
+    /// `unary := '-' unary | power`
+    fn unary(&mut self) -> ParseResult {
+        match self.peek() {
+            Some(&(Token::Operator(op @ ('-' | '+')), ref span)) => {
+                let start = span.start;
+                self.pos += 1;
+                let (quantity, span) = self.unary()?;
+                let value = if op == '-' {
+                    -quantity.value
+                } else {
+                    quantity.value
+                };
+                Ok((Quantity::new(value, quantity.dimension), start..span.end))
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// `power := primary ('^' '-'? integer)?`
+    fn power(&mut self) -> ParseResult {
+        let (base, span) = self.primary()?;
+        let Some((Token::Operator('^'), _)) = self.peek() else {
+            return Ok((base, span));
+        };
+        self.pos += 1;
+        let negative = matches!(self.peek(), Some((Token::Operator('-'), _)));
+        if negative {
+            self.pos += 1;
+        }
+        let Some(&(Token::Number(exponent), ref exponent_span)) = self.peek() else {
+            return Err(self.unexpected());
+        };
+        let exponent_span = exponent_span.clone();
+        if exponent.fract() != 0.0 || exponent > i8::MAX as f64 {
+            return Err(QuantityFromStrError::InvalidNumber(
+                self.input.to_string(),
+                exponent_span,
+            ));
+        }
+        self.pos += 1;
+        let exponent = if negative { -exponent } else { exponent } as i8;
+        let dimension = Dimension::new(
+            base.dimension.length * exponent,
+            base.dimension.mass * exponent,
+            base.dimension.time * exponent,
+        );
+        Ok((
+            Quantity::new(base.value.powi(exponent as i32), dimension),
+            span.start..exponent_span.end,
+        ))
+    }
+
+    /// `primary := number | identifier | '(' expr ')'`
+    fn primary(&mut self) -> ParseResult {
+        let Some((token, span)) = self.peek().cloned() else {
+            return Err(self.unexpected());
+        };
+        match token {
+            Token::Number(value) => {
+                self.pos += 1;
+                Ok((Quantity::scalar(value), span))
+            }
+            Token::Identifier(name) => {
+                self.pos += 1;
+                let quantity = lookup_identifier(name).ok_or_else(|| {
+                    QuantityFromStrError::UnknownIdentifier(self.input.to_string(), span.clone())
+                })?;
+                Ok((quantity, span))
+            }
+            Token::Open => {
+                self.pos += 1;
+                let (quantity, _) = self.expression()?;
+                let Some(&(Token::Close, ref close)) = self.peek() else {
+                    return Err(self.unexpected());
+                };
+                let end = close.end;
+                self.pos += 1;
+                Ok((quantity, span.start..end))
+            }
+            Token::Operator(_) | Token::Close => Err(self.unexpected()),
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = QuantityFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let (quantity, _) = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parser.unexpected());
+        }
+        if !quantity.value.is_finite() {
+            return Err(QuantityFromStrError::InvalidNumber(
+                s.to_string(),
+                0..s.len(),
+            ));
+        }
+        Ok(quantity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn parse(s: &str) -> Quantity {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_units() {
+        assert_relative_eq!(parse("1.52 AU").value, 1.52 * 149_597_870_700.0);
+        assert_relative_eq!(parse("384400 km").value, 384_400_000.0);
+        assert_relative_eq!(parse("0.3ly").value, 0.3 * 9_460_730_472_580_800.0);
+        assert_relative_eq!(parse("5 pc").value, 5.0 * 308_567_758_149_136_730.0);
+        assert_relative_eq!(parse("8 kpc").value, 8e3 * 308_567_758_149_136_730.0);
+        assert_eq!(parse("5 pc").dimension, Dimension::LENGTH);
+        assert_eq!(parse("3.828e26 W").dimension, Dimension::POWER);
+        assert_eq!(parse("1 km / 1 s").dimension, Dimension::new(1, 0, -1));
+    }
+
+    #[test]
+    fn parse_expressions() {
+        assert_relative_eq!(parse("2 * 696340 km").value, 1_392_680_000.0);
+        assert_relative_eq!(parse("1 AU - 1000 km").value, 149_596_870_700.0);
+        assert_relative_eq!(parse("-(1 + 2) * 3").value, -9.0);
+        assert_relative_eq!(parse("2 R_sun").value, 2.0 * 6.957e8);
+        assert_relative_eq!(parse("0.5 L_sun").value, 1.914e26);
+        assert_relative_eq!(parse("2^-2").value, 0.25);
+        assert_eq!(parse("pi * (2 m)^2").dimension, Dimension::new(2, 0, 0));
+    }
+
+    #[test]
+    fn errors_point_at_span() {
+        let err = "2 * 696340 kmh".parse::<Quantity>().unwrap_err();
+        assert!(matches!(err, QuantityFromStrError::UnknownIdentifier(..)));
+        assert_eq!(err.span(), 11..14);
+
+        let err = "1 AU + 3 s".parse::<Quantity>().unwrap_err();
+        assert!(matches!(err, QuantityFromStrError::DimensionMismatch(..)));
+        assert_eq!(err.span(), 0..10);
+
+        let err = "(1 + 2".parse::<Quantity>().unwrap_err();
+        assert!(matches!(err, QuantityFromStrError::UnexpectedEnd(..)));
+        assert_eq!(err.span(), 6..6);
+
+        let err = "1 ? 2".parse::<Quantity>().unwrap_err();
+        assert!(matches!(err, QuantityFromStrError::UnexpectedCharacter(..)));
+        assert_eq!(err.span(), 2..3);
+    }
+}
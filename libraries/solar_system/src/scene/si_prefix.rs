@@ -1,3 +1,5 @@
+use crate::scene::error::QuantityFromStrError;
+use crate::scene::quantity::Quantity;
 use bevy::prelude::Reflect;
 use serde::de::Error;
 use serde::{Deserializer, Serializer};
@@ -168,7 +170,21 @@ impl<'de> serde::Deserialize<'de> for SiPrefixed {
             type Value = SiPrefixed;
 
             fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
-                formatter.write_str("a number with optional SI prefix")
+                formatter.write_str("a number with optional SI prefix, or a quantity expression")
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                self.visit_f64(v as _)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                self.visit_f64(v as _)
             }
 
             fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
@@ -196,26 +212,31 @@ impl serde::Serialize for SiPrefixed {
         S: Serializer,
     {
         let s = self.to_string();
-        serializer.serialize_str(&s)
+        // Prefixes which are also units, such as `m`, would be read back as the unit
+        if s.parse::<Self>()
+            .is_ok_and(|parsed| parsed.as_base_value() == self.as_base_value())
+        {
+            serializer.serialize_str(&s)
+        } else {
+            serializer.serialize_f64(self.as_base_value())
+        }
     }
 }
 
+/// Parses either a unit-aware [`Quantity`] expression (`2439.7 km`, `1.52 AU`), which is converted
+/// to SI base units, or a number with a bare SI prefix (`2439.7k`).
+///
+/// Units take precedence: letters which are both a prefix and a unit are read as the unit, so
+/// that `10m` is 10 meters, `3d` 3 days, `5h` 5 hours and `1a` one year, where these used to be
+/// milli, deca, hecto and atto. Fields of a given dimension should use a type which checks it,
+/// such as [`Length`](crate::scene::distance::Length).
 impl FromStr for SiPrefixed {
-    type Err = <f64 as FromStr>::Err;
+    type Err = QuantityFromStrError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(prefix) = s.chars().next_back().and_then(SiPrefix::from_char) {
-            let value = &s[..s.len() - 1];
-            Ok(Self {
-                value: value.parse()?,
-                prefix: Some(prefix),
-            })
-        } else {
-            s.parse().map(|value| Self {
-                value,
-                prefix: None,
-            })
-        }
+        s.parse::<Quantity>()
+            .map(|quantity| Self::from(quantity.value))
+            .or_else(|err| Self::from_bare_prefix(s).ok_or(err))
     }
 }
 
@@ -259,6 +280,17 @@ impl SiPrefixed {
         }
     }
 
+    /// Parses a number followed by a bare SI prefix, such as `2439.7k` or `1.23 k`.
+    pub(crate) fn from_bare_prefix(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let prefix = s.chars().next_back().and_then(SiPrefix::from_char)?;
+        let value = s[..s.len() - 1].trim_end().parse().ok()?;
+        Some(Self {
+            value,
+            prefix: Some(prefix),
+        })
+    }
+
     pub fn as_base_value(&self) -> f64 {
         self.value * self.prefix.map(|p| p.factor()).unwrap_or(1.0)
     }
@@ -279,6 +311,28 @@ mod tests {
         assert_eq!(si_prefixed.prefix, None);
     }
 
+    #[test]
+    fn test_from_str_with_units() {
+        let si_prefixed: SiPrefixed = "2439.7 km".parse().unwrap();
+        assert_eq!(si_prefixed.as_base_value(), 2_439_700.0);
+
+        let si_prefixed: SiPrefixed = "2 * 696340 km".parse().unwrap();
+        assert_eq!(si_prefixed.as_base_value(), 1_392_680_000.0);
+
+        let err = "1.52 UA".parse::<SiPrefixed>().unwrap_err();
+        assert_eq!(err.span(), 5..7);
+    }
+
+    #[test]
+    fn test_from_str_units_before_prefixes() {
+        let si_prefixed: SiPrefixed = "10m".parse().unwrap();
+        assert_eq!(si_prefixed.as_base_value(), 10.0);
+        assert_eq!(si_prefixed.prefix, None);
+
+        let si_prefixed: SiPrefixed = "1.23 k".parse().unwrap();
+        assert_eq!(si_prefixed.prefix, Some(SiPrefix::Kilo));
+    }
+
     #[test]
     fn test_to_string() {
         let si_prefixed = SiPrefixed {
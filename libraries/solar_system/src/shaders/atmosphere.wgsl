@@ -0,0 +1,134 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+const PI: f32 = 3.141592653589793;
+const SAMPLE_COUNT: u32 = 32u;
+
+struct AtmosphereUniform {
+    center: vec3<f32>,
+    bottom_radius: f32,
+    sun_position: vec3<f32>,
+    top_radius: f32,
+    rayleigh_scattering: vec3<f32>,
+    rayleigh_scale_height: f32,
+    mie_scattering: f32,
+    mie_absorption: f32,
+    mie_scale_height: f32,
+    mie_asymmetry: f32,
+    illuminance: f32,
+}
+
+@group(2) @binding(0) var<uniform> atmosphere: AtmosphereUniform;
+@group(2) @binding(1) var transmittance_lut: texture_2d<f32>;
+@group(2) @binding(2) var transmittance_sampler: sampler;
+
+struct Medium {
+    rayleigh_scattering: vec3<f32>,
+    mie_scattering: f32,
+    extinction: vec3<f32>,
+}
+
+fn sample_medium(altitude: f32) -> Medium {
+    let rayleigh_density = exp(-altitude / atmosphere.rayleigh_scale_height);
+    let mie_density = exp(-altitude / atmosphere.mie_scale_height);
+    var medium: Medium;
+    medium.rayleigh_scattering = atmosphere.rayleigh_scattering * rayleigh_density;
+    medium.mie_scattering = atmosphere.mie_scattering * mie_density;
+    medium.extinction = medium.rayleigh_scattering
+        + (atmosphere.mie_scattering + atmosphere.mie_absorption) * mie_density;
+    return medium;
+}
+
+/// Distances along the ray to the entry and exit points of a sphere centered on the origin, or
+/// negative values when the ray misses it.
+fn ray_sphere(origin: vec3<f32>, direction: vec3<f32>, radius: f32) -> vec2<f32> {
+    let b = dot(origin, direction);
+    let c = dot(origin, origin) - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return vec2(-1.0);
+    }
+    let s = sqrt(discriminant);
+    return vec2(-b - s, -b + s);
+}
+
+fn rayleigh_phase(cos_theta: f32) -> f32 {
+    return 3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta);
+}
+
+/// Cornette-Shanks phase function.
+fn mie_phase(cos_theta: f32, g: f32) -> f32 {
+    let g2 = g * g;
+    let denominator = (2.0 + g2) * pow(1.0 + g2 - 2.0 * g * cos_theta, 1.5);
+    return 3.0 / (8.0 * PI) * (1.0 - g2) * (1.0 + cos_theta * cos_theta) / denominator;
+}
+
+/// Parametrization of the transmittance lookup table, mirrored in `atmosphere.rs`. Differences of
+/// squares are factored to keep precision at planetary scales.
+fn transmittance_lut_uv(r: f32, mu: f32) -> vec2<f32> {
+    let bottom = atmosphere.bottom_radius;
+    let top = atmosphere.top_radius;
+    let horizon = sqrt((top - bottom) * (top + bottom));
+    let rho = sqrt(max((r - bottom) * (r + bottom), 0.0));
+    let discriminant = (top - r) * (top + r) + r * r * mu * mu;
+    let d = max(-r * mu + sqrt(max(discriminant, 0.0)), 0.0);
+    let d_min = top - r;
+    let d_max = rho + horizon;
+    return vec2((d - d_min) / (d_max - d_min), rho / horizon);
+}
+
+/// Transmittance of sunlight down to `position`, relative to the center of the body.
+fn sun_transmittance(position: vec3<f32>, to_sun: vec3<f32>) -> vec3<f32> {
+    // Sunlight is blocked by the ground past the terminator
+    if ray_sphere(position, to_sun, atmosphere.bottom_radius).x > 0.0 {
+        return vec3(0.0);
+    }
+    let r = length(position);
+    let mu = dot(position, to_sun) / r;
+    let uv = transmittance_lut_uv(r, mu);
+    return textureSampleLevel(transmittance_lut, transmittance_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Everything is relative to the center of the body, and world positions are relative to the
+    // floating origin, which keeps them within single precision.
+    let origin = view.world_position - atmosphere.center;
+    let direction = normalize(in.world_position.xyz - view.world_position);
+    let top = ray_sphere(origin, direction, atmosphere.top_radius);
+    if top.y <= 0.0 {
+        discard;
+    }
+    let start = max(top.x, 0.0);
+    var end = top.y;
+    let ground = ray_sphere(origin, direction, atmosphere.bottom_radius);
+    if ground.x > 0.0 {
+        end = min(end, ground.x);
+    }
+
+    let to_sun = normalize(atmosphere.sun_position - atmosphere.center);
+    let cos_theta = dot(direction, to_sun);
+    let rayleigh = rayleigh_phase(cos_theta);
+    let mie = mie_phase(cos_theta, atmosphere.mie_asymmetry);
+
+    let dt = (end - start) / f32(SAMPLE_COUNT);
+    var transmittance = vec3(1.0);
+    var radiance = vec3(0.0);
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        let position = origin + (start + (f32(i) + 0.5) * dt) * direction;
+        let medium = sample_medium(length(position) - atmosphere.bottom_radius);
+        let sample_transmittance = exp(-medium.extinction * dt);
+        let scattering = (medium.rayleigh_scattering * rayleigh + medium.mie_scattering * mie)
+            * sun_transmittance(position, to_sun);
+        // Integrates the scattering over the segment, as if the medium were homogeneous over it
+        let extinction = max(medium.extinction, vec3(1e-12));
+        radiance += transmittance * (scattering - scattering * sample_transmittance) / extinction;
+        transmittance *= sample_transmittance;
+    }
+
+    // Premultiplied alpha: the background is attenuated by the average transmittance
+    let alpha = 1.0 - dot(transmittance, vec3(1.0 / 3.0));
+    return vec4(radiance * atmosphere.illuminance * view.exposure, alpha);
+}
@@ -0,0 +1,57 @@
+#import bevy_pbr::{
+    mesh_functions::get_world_from_local,
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+
+/// Radial falloff exponent of the corona brightness.
+const FALLOFF: f32 = 3.0;
+
+struct CoronaMaterialUniform {
+    color: vec3<f32>,
+    radius: f32,
+    extent: f32,
+    min_angular_extent: f32,
+}
+
+@group(2) @binding(0) var<uniform> corona: CoronaMaterialUniform;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    /// Position on the quad, from -1 to 1.
+    @location(0) quad: vec2<f32>,
+    /// Position on the quad, in star radii.
+    @location(1) offset: vec2<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let center = get_world_from_local(vertex.instance_index)[3].xyz;
+    let distance = length(center - view.world_position);
+    let angular_radius = asin(min(corona.radius / distance, 1.0));
+    let angular_extent = min(max(angular_radius * corona.extent, corona.min_angular_extent), 1.5);
+    let half_size = distance * tan(angular_extent);
+
+    let right = view.world_from_view[0].xyz;
+    let up = view.world_from_view[1].xyz;
+    let world_position = center + (right * vertex.position.x + up * vertex.position.y) * half_size;
+
+    var out: VertexOutput;
+    out.position = position_world_to_clip(world_position);
+    out.quad = vertex.position.xy;
+    out.offset = vertex.position.xy * half_size / corona.radius;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let r = max(length(in.offset), 1.0);
+    let fade = 1.0 - smoothstep(0.5, 1.0, length(in.quad));
+    let glow = pow(1.0 / r, FALLOFF) * fade;
+    return vec4(corona.color * glow * view.exposure, 0.0);
+}
@@ -0,0 +1,70 @@
+#import bevy_pbr::{
+    mesh_functions::get_world_from_local,
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+
+const PI: f32 = 3.141592653589793;
+/// Half-width of the quad, in pixels.
+const HALF_SIZE_PIXELS: f32 = 3.0;
+/// Standard deviation of the gaussian spot, in pixels.
+const SIGMA_PIXELS: f32 = 1.0;
+/// Diameters of the disc of the body, in pixels, between which the impostor fades out.
+const FADE_START_PIXELS: f32 = 2.0;
+const FADE_END_PIXELS: f32 = 4.0;
+
+struct ImpostorMaterialUniform {
+    illuminance: f32,
+    radius: f32,
+}
+
+@group(2) @binding(0) var<uniform> impostor: ImpostorMaterialUniform;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    /// Position on the quad, in pixels from its center.
+    @location(0) offset: vec2<f32>,
+    /// Radiance at the center of the spot, faded out as the disc of the body gets resolved.
+    @location(1) @interpolate(flat) radiance: f32,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let center = get_world_from_local(vertex.instance_index)[3].xyz;
+    let to_center = center - view.world_position;
+    let distance = length(to_center);
+
+    // Angle covered by a pixel at the center of the screen
+    let pixel_angle = 2.0 / (view.clip_from_view[1][1] * view.viewport.w);
+    let disc_pixels = 2.0 * asin(min(impostor.radius / distance, 1.0)) / pixel_angle;
+    let fade = 1.0 - smoothstep(FADE_START_PIXELS, FADE_END_PIXELS, disc_pixels);
+
+    // In front of the body, so that its own mesh does not hide the spot
+    let front = center - to_center / distance * min(impostor.radius * 1.01, 0.5 * distance);
+    let half_size = length(front - view.world_position) * tan(pixel_angle * HALF_SIZE_PIXELS);
+    let right = view.world_from_view[0].xyz;
+    let up = view.world_from_view[1].xyz;
+    let world_position = front + (right * vertex.position.x + up * vertex.position.y) * half_size;
+
+    // Spreads the illuminance over the solid angle of the spot
+    let sigma = SIGMA_PIXELS * pixel_angle;
+    let radiance = impostor.illuminance / (2.0 * PI * sigma * sigma);
+
+    var out: VertexOutput;
+    out.position = position_world_to_clip(world_position);
+    out.offset = vertex.position.xy * HALF_SIZE_PIXELS;
+    out.radiance = radiance * fade;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let r2 = dot(in.offset, in.offset);
+    let spot = exp(-0.5 * r2 / (SIGMA_PIXELS * SIGMA_PIXELS));
+    return vec4(vec3(in.radiance * spot * view.exposure), 0.0);
+}
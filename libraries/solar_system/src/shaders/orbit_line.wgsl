@@ -0,0 +1,88 @@
+#import bevy_pbr::{
+    mesh_functions::get_world_from_local,
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+
+struct OrbitLineMaterialUniform {
+    color: vec4<f32>,
+    width: f32,
+    min_alpha: f32,
+}
+
+@group(2) @binding(0) var<uniform> line: OrbitLineMaterialUniform;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) start: vec3<f32>,
+    @location(1) end: vec3<f32>,
+    /// Side of the line, whether this is the end of the segment, and brightness.
+    @location(2) line: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    /// Position across the line, from -1 to 1.
+    @location(0) side: f32,
+    @location(1) brightness: f32,
+}
+
+/// Moves `clip` along the segment towards `other` until it is in front of the near plane.
+fn clip_to_near(clip: vec4<f32>, other: vec4<f32>, near: f32) -> vec4<f32> {
+    if clip.w >= near {
+        return clip;
+    }
+    let t = (near - clip.w) / (other.w - clip.w);
+    return mix(clip, other, t);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    let start_world = (world_from_local * vec4(vertex.start, 1.0)).xyz;
+    let end_world = (world_from_local * vec4(vertex.end, 1.0)).xyz;
+    var start = position_world_to_clip(start_world);
+    var end = position_world_to_clip(end_world);
+
+    var out: VertexOutput;
+    out.side = vertex.line.x;
+    out.brightness = vertex.line.z;
+
+    // Segments entirely behind the camera are dropped, the others cut at the near plane
+    let near = view.clip_from_view[3][2];
+    if start.w < near && end.w < near {
+        out.position = vec4(0.0, 0.0, 2.0, 1.0);
+        return out;
+    }
+    let clipped_start = clip_to_near(start, end, near);
+    let clipped_end = clip_to_near(end, start, near);
+
+    // Offsets the vertex perpendicularly to the segment on screen, by half the width in pixels,
+    // plus a pixel for the antialiased edge
+    let viewport_size = view.viewport.zw;
+    let start_screen = clipped_start.xy / clipped_start.w * viewport_size;
+    let end_screen = clipped_end.xy / clipped_end.w * viewport_size;
+    var direction = end_screen - start_screen;
+    if dot(direction, direction) < 1e-12 {
+        direction = vec2(1.0, 0.0);
+    }
+    let normal = normalize(vec2(-direction.y, direction.x));
+    let half_width = 0.5 * line.width + 1.0;
+
+    // NDC spans 2 units across the viewport
+    let offset = normal * vertex.line.x * 2.0 * half_width / viewport_size;
+    var clip = select(clipped_start, clipped_end, vertex.line.y > 0.5);
+    clip = vec4(clip.xy + offset * clip.w, clip.zw);
+    out.position = clip;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Antialiased edges, the quad being one pixel wider than the line on each side
+    let half_width = 0.5 * line.width + 1.0;
+    let distance = abs(in.side) * half_width;
+    let coverage = saturate(0.5 * line.width + 0.5 - distance);
+    let alpha = line.color.a * mix(line.min_alpha, 1.0, in.brightness) * coverage;
+    return vec4(line.color.rgb, alpha);
+}
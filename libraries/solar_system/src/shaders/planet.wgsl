@@ -0,0 +1,160 @@
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
+}
+
+const FLAG_NIGHT_LIGHTS: u32 = 1u;
+const FLAG_OCEAN_MASK: u32 = 2u;
+const FLAG_RINGS: u32 = 4u;
+const MAX_OCCLUDERS: u32 = 8u;
+const PI: f32 = 3.141592653589793;
+
+struct PlanetMaterialUniform {
+    sun_position: vec3<f32>,
+    twilight_width: f32,
+    night_lights_power: f32,
+    ocean_roughness: f32,
+    ocean_reflectance: f32,
+    flags: u32,
+    ring_center: vec3<f32>,
+    ring_inner_radius: f32,
+    ring_normal: vec3<f32>,
+    ring_outer_radius: f32,
+    sun_radius: f32,
+    occluder_count: u32,
+    occluders: array<vec4<f32>, MAX_OCCLUDERS>,
+}
+
+@group(2) @binding(100) var<uniform> planet: PlanetMaterialUniform;
+@group(2) @binding(101) var night_lights_texture: texture_2d<f32>;
+@group(2) @binding(102) var night_lights_sampler: sampler;
+@group(2) @binding(103) var ocean_mask_texture: texture_2d<f32>;
+@group(2) @binding(104) var ocean_mask_sampler: sampler;
+@group(2) @binding(105) var ring_texture: texture_2d<f32>;
+@group(2) @binding(106) var ring_sampler: sampler;
+
+/// 1 on the night side, 0 on the day side, with a smooth band around the terminator.
+fn night_factor(world_position: vec3<f32>, world_normal: vec3<f32>) -> f32 {
+    let sun_direction = normalize(planet.sun_position - world_position);
+    let n_dot_l = dot(normalize(world_normal), sun_direction);
+    return 1.0 - smoothstep(-planet.twilight_width, planet.twilight_width, n_dot_l);
+}
+
+/// Fraction of sunlight going through the rings on its way to `world_position`.
+fn ring_transmission(world_position: vec3<f32>) -> f32 {
+    let to_sun = normalize(planet.sun_position - world_position);
+    let denominator = dot(to_sun, planet.ring_normal);
+    if abs(denominator) < 1e-6 {
+        return 1.0;
+    }
+    let t = dot(planet.ring_center - world_position, planet.ring_normal) / denominator;
+    if t <= 0.0 {
+        return 1.0;
+    }
+    let radius = length(world_position + t * to_sun - planet.ring_center);
+    let u = (radius - planet.ring_inner_radius) / (planet.ring_outer_radius - planet.ring_inner_radius);
+    if u < 0.0 || u > 1.0 {
+        return 1.0;
+    }
+    // Sampled in non-uniform control flow, hence the explicit level
+    return 1.0 - textureSampleLevel(ring_texture, ring_sampler, vec2(u, 0.5), 0.0).a;
+}
+
+/// Area of the intersection of two discs of radii `a` and `b` whose centers are `d` apart.
+fn disc_overlap(a: f32, b: f32, d: f32) -> f32 {
+    if d >= a + b {
+        return 0.0;
+    }
+    if d <= abs(a - b) {
+        let r = min(a, b);
+        return PI * r * r;
+    }
+    let alpha = acos(clamp((d * d + a * a - b * b) / (2.0 * d * a), -1.0, 1.0));
+    let beta = acos(clamp((d * d + b * b - a * a) / (2.0 * d * b), -1.0, 1.0));
+    let kite = sqrt(max((-d + a + b) * (d + a - b) * (d - a + b) * (d + a + b), 0.0));
+    return a * a * alpha + b * b * beta - 0.5 * kite;
+}
+
+/// Fraction of the sun disc visible from `world_position`, past the occluding bodies. Discs are
+/// compared by their angular radii, which gives the umbra and penumbra at any distance.
+fn eclipse_visibility(world_position: vec3<f32>) -> f32 {
+    let to_sun = planet.sun_position - world_position;
+    let sun_distance = length(to_sun);
+    let sun_direction = to_sun / sun_distance;
+    let sun_angle = asin(min(planet.sun_radius / sun_distance, 1.0));
+    if sun_angle <= 0.0 {
+        return 1.0;
+    }
+    var visibility = 1.0;
+    for (var i = 0u; i < min(planet.occluder_count, MAX_OCCLUDERS); i++) {
+        let occluder = planet.occluders[i];
+        let to_occluder = occluder.xyz - world_position;
+        let occluder_distance = length(to_occluder);
+        // Only bodies between the point and the sun, which also skips the body the point is on:
+        // on bodies which are not spheres, the point can be inside its own bounding sphere
+        if dot(to_occluder, sun_direction) <= 0.0 || occluder_distance >= sun_distance
+            || occluder_distance < occluder.w {
+            continue;
+        }
+        let occluder_direction = to_occluder / occluder_distance;
+        let occluder_angle = asin(min(occluder.w / occluder_distance, 1.0));
+        let separation = atan2(
+            length(cross(sun_direction, occluder_direction)),
+            dot(sun_direction, occluder_direction),
+        );
+        let hidden = disc_overlap(sun_angle, occluder_angle, separation) / (PI * sun_angle * sun_angle);
+        visibility *= 1.0 - saturate(hidden);
+    }
+    return visibility;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef VERTEX_UVS_A
+    // Specular glint only on water: oceans get their own roughness and reflectance, land none
+    if (planet.flags & FLAG_OCEAN_MASK) != 0u {
+        let ocean = textureSample(ocean_mask_texture, ocean_mask_sampler, in.uv).r;
+        pbr_input.material.perceptual_roughness = mix(
+            pbr_input.material.perceptual_roughness,
+            planet.ocean_roughness,
+            ocean,
+        );
+        pbr_input.material.reflectance = planet.ocean_reflectance * ocean;
+    }
+
+    if (planet.flags & FLAG_NIGHT_LIGHTS) != 0u {
+        let lights = textureSample(night_lights_texture, night_lights_sampler, in.uv).rgb;
+        let night = night_factor(in.world_position.xyz, in.world_normal);
+        pbr_input.material.emissive += vec4(lights * planet.night_lights_power * night, 0.0);
+    }
+#endif
+
+    // Dims the surface as a whole, as there is no hook to only shadow the direct light
+    var transmission = eclipse_visibility(in.world_position.xyz);
+    if (planet.flags & FLAG_RINGS) != 0u {
+        transmission *= ring_transmission(in.world_position.xyz);
+    }
+    pbr_input.material.base_color = vec4(
+        pbr_input.material.base_color.rgb * transmission,
+        pbr_input.material.base_color.a,
+    );
+    pbr_input.material.reflectance *= transmission;
+
+    var out: FragmentOutput;
+    if (pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u {
+        out.color = apply_pbr_lighting(pbr_input);
+    } else {
+        out.color = pbr_input.material.base_color;
+    }
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+
+    return out;
+}
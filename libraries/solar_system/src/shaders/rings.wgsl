@@ -0,0 +1,45 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+const PI: f32 = 3.141592653589793;
+
+struct RingMaterialUniform {
+    sun_position: vec3<f32>,
+    illuminance: f32,
+    planet_center: vec3<f32>,
+    planet_radius: f32,
+    ring_normal: vec3<f32>,
+}
+
+@group(2) @binding(0) var<uniform> ring: RingMaterialUniform;
+@group(2) @binding(1) var ring_texture: texture_2d<f32>;
+@group(2) @binding(2) var ring_sampler: sampler;
+
+/// Fraction of sunlight reaching `position` past the planet, with a slightly soft edge.
+fn planet_shadow(position: vec3<f32>, to_sun: vec3<f32>) -> f32 {
+    let to_center = ring.planet_center - position;
+    let along = dot(to_center, to_sun);
+    if along <= 0.0 {
+        return 1.0;
+    }
+    let distance = sqrt(max(dot(to_center, to_center) - along * along, 0.0));
+    return smoothstep(ring.planet_radius * 0.99, ring.planet_radius * 1.01, distance);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sample = textureSample(ring_texture, ring_sampler, vec2(in.uv.x, 0.5));
+    let position = in.world_position.xyz;
+    let to_sun = normalize(ring.sun_position - position);
+    let to_view = normalize(view.world_position - position);
+    let n_dot_l = dot(ring.ring_normal, to_sun);
+    let n_dot_v = dot(ring.ring_normal, to_view);
+
+    // From the unlit side, only the light making it through the ring is visible
+    let transmission = select(1.0 - sample.a, 1.0, sign(n_dot_l) == sign(n_dot_v));
+    let radiance = sample.rgb * ring.illuminance / PI * abs(n_dot_l) * transmission
+        * planet_shadow(position, to_sun);
+    return vec4(radiance * view.exposure, sample.a);
+}
@@ -0,0 +1,80 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{globals, view},
+}
+
+const PI: f32 = 3.141592653589793;
+/// Speed at which granulation cells drift, in radians of their phase per second.
+const GRANULATION_SPEED: f32 = 0.2;
+
+struct StarMaterialUniform {
+    color: vec3<f32>,
+    limb_darkening: f32,
+    granulation: f32,
+    granulation_scale: f32,
+}
+
+@group(2) @binding(0) var<uniform> star: StarMaterialUniform;
+
+fn hash3(p: vec3<f32>) -> vec3<f32> {
+    let q = vec3(
+        dot(p, vec3(127.1, 311.7, 74.7)),
+        dot(p, vec3(269.5, 183.3, 246.1)),
+        dot(p, vec3(113.5, 271.9, 124.6)),
+    );
+    return fract(sin(q) * 43758.5453);
+}
+
+/// Distances to the closest and second closest feature points of cellular noise, whose points
+/// wander around the center of their cell over time.
+fn cellular(p: vec3<f32>, time: f32) -> vec2<f32> {
+    let cell = floor(p);
+    var closest = vec2(8.0);
+    for (var z = -1; z <= 1; z++) {
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let neighbor = cell + vec3(f32(x), f32(y), f32(z));
+                let h = hash3(neighbor);
+                let point = neighbor + 0.5 + 0.4 * sin(time * (0.5 + h) + 2.0 * PI * h);
+                let d = distance(p, point);
+                if d < closest.x {
+                    closest = vec2(d, closest.x);
+                } else if d < closest.y {
+                    closest.y = d;
+                }
+            }
+        }
+    }
+    return closest;
+}
+
+/// Direction from the center of the star in its own frame, from equirectangular texture
+/// coordinates.
+fn local_direction(uv: vec2<f32>) -> vec3<f32> {
+    let longitude = uv.x * 2.0 * PI - PI;
+    let latitude = PI / 2.0 - uv.y * PI;
+    return vec3(
+        cos(latitude) * cos(longitude),
+        sin(latitude),
+        -cos(latitude) * sin(longitude),
+    );
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+    let to_view = normalize(view.world_position - in.world_position.xyz);
+    let mu = saturate(dot(normal, to_view));
+    let limb = 1.0 - star.limb_darkening * (1.0 - mu);
+
+    // Bright granules separated by dark lanes, faded out once cells get smaller than a pixel
+    let p = local_direction(in.uv) * star.granulation_scale;
+    let cells = cellular(p, globals.time * GRANULATION_SPEED);
+    let lanes = smoothstep(0.0, 0.15, cells.y - cells.x);
+    let granules = lanes * (1.0 - 0.5 * cells.x);
+    let footprint = length(fwidth(p));
+    let contrast = star.granulation * (1.0 - smoothstep(0.3, 1.0, footprint));
+    let granulation = 1.0 + contrast * (granules - 0.5);
+
+    return vec4(star.color * limb * granulation * view.exposure, 1.0);
+}
@@ -0,0 +1,127 @@
+use bevy::asset::embedded_asset;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError,
+};
+
+pub struct StarPlugin;
+
+impl Plugin for StarPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/star.wgsl");
+        embedded_asset!(app, "shaders/corona.wgsl");
+        app.add_plugins((
+            MaterialPlugin::<StarMaterial>::default(),
+            MaterialPlugin::<CoronaMaterial>::default(),
+        ));
+    }
+}
+
+/// Linear color of a blackbody at the given temperature, in kelvin.
+pub fn temperature_color(temperature: f32) -> LinearRgba {
+    starrynight::blackbody_color(temperature).into()
+}
+
+#[derive(Debug, Clone, Copy, Default, ShaderType, Reflect)]
+pub struct StarMaterialUniform {
+    /// Emitted radiance at the center of the disc.
+    pub color: Vec3,
+    /// Linear limb darkening coefficient: the edge of the disc is this much darker than its
+    /// center.
+    pub limb_darkening: f32,
+    /// Contrast of the granulation.
+    pub granulation: f32,
+    /// Number of granulation cells along a radius.
+    pub granulation_scale: f32,
+}
+
+/// Surface of a star: a blackbody with limb darkening and animated granulation.
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+pub struct StarMaterial {
+    #[uniform(0)]
+    pub uniform: StarMaterialUniform,
+}
+
+impl Material for StarMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/star.wgsl".into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ShaderType, Reflect)]
+pub struct CoronaMaterialUniform {
+    /// Radiance of the corona at the edge of the disc.
+    pub color: Vec3,
+    /// Radius of the star.
+    pub radius: f32,
+    /// Extent of the corona, in star radii.
+    pub extent: f32,
+    /// Minimum angular extent of the corona, in radians, so that distant stars keep a glow.
+    pub min_angular_extent: f32,
+}
+
+/// Glow around a star, on a camera facing quad sized from the angular radius of the star in the
+/// vertex shader. The quad is expected to be a 2×2 rectangle centered on the star.
+#[derive(Debug, Clone, Asset, AsBindGroup, Reflect)]
+pub struct CoronaMaterial {
+    #[uniform(0)]
+    pub uniform: CoronaMaterialUniform,
+}
+
+impl CoronaMaterial {
+    pub fn new(color: LinearRgba, radius: f32, extent: f32) -> Self {
+        Self {
+            uniform: CoronaMaterialUniform {
+                color: color.to_vec3(),
+                radius,
+                extent,
+                min_angular_extent: 2e-3,
+            },
+        }
+    }
+}
+
+impl Material for CoronaMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/corona.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "embedded://solar_system/shaders/corona.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Add
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout
+            .0
+            .get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cooler_stars_are_redder() {
+        let sun = temperature_color(5772.0);
+        let red_dwarf = temperature_color(3000.0);
+        let blue_giant = temperature_color(20000.0);
+        assert!(red_dwarf.red > red_dwarf.blue);
+        assert!(blue_giant.blue > blue_giant.red);
+        assert!(red_dwarf.blue / red_dwarf.red < sun.blue / sun.red);
+    }
+}
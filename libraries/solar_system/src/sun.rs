@@ -1,4 +1,6 @@
-use bevy::pbr::CascadeShadowConfigBuilder;
+use crate::body::BodyShape;
+use crate::scene::components::SceneCamera;
+use bevy::pbr::{CascadeShadowConfig, CascadeShadowConfigBuilder};
 use bevy::prelude::*;
 
 pub struct SunPlugin;
@@ -7,10 +9,13 @@ impl Plugin for SunPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Sun>()
             .register_type::<Sunlight>()
-            .add_systems(Startup, setup_sunlight)
+            .init_resource::<SunPosition>()
+            .init_resource::<SunIlluminance>()
+            .init_resource::<SunRadius>()
             .add_systems(
                 Update,
-                sun_lighting
+                (sync_sunlights, sun_lighting)
+                    .chain()
                     .in_set(TransformSystem::TransformPropagate)
                     .after(bevy::transform::systems::sync_simple_transforms)
                     .after(bevy::transform::systems::propagate_transforms)
@@ -19,46 +24,198 @@ impl Plugin for SunPlugin {
     }
 }
 
+/// Luminosity of the Sun, in watts.
+pub const SOLAR_LUMINOSITY: f64 = 3.828e26;
+/// Luminous efficacy of sunlight, in lumens per watt, to convert irradiance into illuminance.
+pub const LUMINOUS_EFFICACY: f64 = 93.0;
+
 #[derive(Debug, Clone, Copy, Component, Reflect)]
 #[reflect(Component)]
-pub struct Sun(pub(crate) f32);
+pub struct Sun {
+    pub radius: f32,
+    /// Radiated power, in watts.
+    pub luminosity: f64,
+}
+
+impl Sun {
+    /// Illuminance, in lux, at the given distance from the center of the star.
+    pub fn illuminance_at(&self, distance: f64) -> f64 {
+        let irradiance = self.luminosity / (4.0 * std::f64::consts::PI * distance * distance);
+        irradiance * LUMINOUS_EFFICACY
+    }
+}
 
+/// Directional light standing in for a [`Sun`].
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct Sunlight;
-
-fn setup_sunlight(mut commands: Commands) {
-    commands.spawn((
-        Sunlight,
-        DirectionalLight {
-            color: Color::WHITE,
-            illuminance: 120e3,
-            shadows_enabled: true,
-            ..Default::default()
-        },
-        CascadeShadowConfigBuilder {
-            num_cascades: 4,
-            minimum_distance: 0.1,
-            maximum_distance: 10_000.0,
-            first_cascade_far_bound: 100.0,
-            overlap_proportion: 0.2,
+pub(crate) struct Sunlight {
+    sun: Entity,
+}
+
+/// World-space position of the primary sun, the brightest one at the focus of the camera, updated
+/// by [`sun_lighting`].
+///
+/// Planet materials only know of this sun: in multiple systems, the night side, night lights and
+/// eclipses follow the brightest sun, while the other suns still light bodies through their own
+/// directional light.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct SunPosition(pub Vec3);
+
+/// Illuminance of the primary sun at the focus of the camera, in lux, updated by
+/// [`sun_lighting`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct SunIlluminance(pub f32);
+
+/// Radius of the primary sun, updated by [`sun_lighting`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct SunRadius(pub f32);
+
+/// Gives each sun its own light, and removes the lights of despawned suns.
+fn sync_sunlights(
+    mut commands: Commands,
+    q_added: Query<Entity, Added<Sun>>,
+    q_suns: Query<(), With<Sun>>,
+    q_lights: Query<(Entity, &Sunlight)>,
+) {
+    for sun in &q_added {
+        commands.spawn((
+            Sunlight { sun },
+            DirectionalLight {
+                color: Color::WHITE,
+                illuminance: 0.0,
+                shadows_enabled: false,
+                ..Default::default()
+            },
+            planetary_cascades(1.0, 1.0),
+        ));
+    }
+    for (entity, light) in &q_lights {
+        if !q_suns.contains(light.sun) {
+            commands.entity(entity).despawn();
         }
-        .build(),
-    ));
+    }
+}
+
+/// Shadow cascades covering a body of the given radius, seen from `distance` to its center: the
+/// first cascade ends at twice the altitude of the camera, for nearby terrain, and the last one
+/// covers the far side of the body.
+pub fn planetary_cascades(distance: f32, radius: f32) -> CascadeShadowConfig {
+    let altitude = (distance - radius).max(1.0);
+    let maximum_distance = (distance + radius).max(4.0 * altitude);
+    CascadeShadowConfigBuilder {
+        num_cascades: 4,
+        minimum_distance: (altitude * 1e-3).min(0.1),
+        maximum_distance,
+        first_cascade_far_bound: (2.0 * altitude).min(maximum_distance / 2.0),
+        overlap_proportion: 0.2,
+    }
+    .build()
 }
 
+/// Lights the scene from each sun as a point light: its directional light is aimed from the sun
+/// at the body the camera focuses on, with the illuminance falling off with the square of its
+/// distance to the sun. Only the brightest sun casts shadows, with cascades fitted to that body.
 #[allow(clippy::type_complexity)]
-fn sun_lighting(
-    mut queries: ParamSet<(
-        Query<(&mut Transform, &mut GlobalTransform), With<Sunlight>>,
-        Query<&GlobalTransform, With<Sun>>,
-    )>,
+pub(crate) fn sun_lighting(
+    mut sun_position: ResMut<SunPosition>,
+    mut sun_illuminance: ResMut<SunIlluminance>,
+    mut sun_radius: ResMut<SunRadius>,
+    q_suns: Query<(&Sun, &GlobalTransform), Without<Sunlight>>,
+    q_camera: Query<(&GlobalTransform, &Parent), (With<SceneCamera>, Without<Sunlight>)>,
+    q_focus: Query<(&GlobalTransform, Option<&Children>), Without<Sunlight>>,
+    q_shapes: Query<(&GlobalTransform, &BodyShape), Without<Sunlight>>,
+    mut q_lights: Query<
+        (
+            &Sunlight,
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut DirectionalLight,
+            &mut CascadeShadowConfig,
+        ),
+        Without<Sun>,
+    >,
 ) {
-    let Ok(sun_pos) = queries.p1().get_single().map(|tr| tr.translation()) else {
+    let camera = q_camera.get_single().ok();
+    let focus = camera.and_then(|(_, parent)| q_focus.get(parent.get()).ok());
+    let focus_pos = focus.map_or(Vec3::ZERO, |(transform, _)| transform.translation());
+
+    let mut primary: Option<(Entity, f32, Vec3, f32)> = None;
+    for (sunlight, mut light_tr, mut light_gt, mut light, _) in &mut q_lights {
+        let Ok((sun, sun_transform)) = q_suns.get(sunlight.sun) else {
+            continue;
+        };
+        let sun_pos = sun_transform.translation();
+        // When looking at the sun itself, the camera is the one being lit
+        let target = match camera {
+            Some((camera_transform, _)) if focus_pos.distance(sun_pos) <= sun.radius => {
+                camera_transform.translation()
+            }
+            _ => focus_pos,
+        };
+        let direction = target - sun_pos;
+        if let Ok(direction) = Dir3::new(direction) {
+            *light_tr = Transform::default().looking_to(direction, Vec3::Y);
+            *light_gt = (*light_tr).into();
+        }
+        let distance = direction.length().max(sun.radius);
+        light.illuminance = sun.illuminance_at(distance as f64) as f32;
+        if primary.map_or(true, |(_, _, _, illuminance)| {
+            light.illuminance > illuminance
+        }) {
+            primary = Some((sunlight.sun, sun.radius, sun_pos, light.illuminance));
+        }
+    }
+    let Some((primary_sun, primary_radius, primary_pos, primary_illuminance)) = primary else {
         return;
     };
-    let mut light_query = queries.p0();
-    let (mut light_tr, mut light_gt) = light_query.single_mut();
-    light_tr.look_at(-sun_pos, Vec3::Y);
-    *light_gt = (*light_tr).into();
+    sun_position.set_if_neq(SunPosition(primary_pos));
+    sun_illuminance.set_if_neq(SunIlluminance(primary_illuminance));
+    sun_radius.set_if_neq(SunRadius(primary_radius));
+
+    let cascades = camera
+        .zip(focus)
+        .map(|((camera_transform, _), (_, children))| {
+            let radius = children
+                .iter()
+                .flat_map(|children| children.iter())
+                .find_map(|child| q_shapes.get(*child).ok())
+                .map_or(0.0, |(_, shape)| shape.radii.max_element());
+            planetary_cascades(camera_transform.translation().distance(focus_pos), radius)
+        });
+    for (sunlight, _, _, mut light, mut light_cascades) in &mut q_lights {
+        let is_primary = sunlight.sun == primary_sun;
+        light.shadows_enabled = is_primary;
+        if let (true, Some(cascades)) = (is_primary, &cascades) {
+            *light_cascades = cascades.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illuminance_follows_inverse_square_law() {
+        let sun = Sun {
+            radius: 696_340e3,
+            luminosity: SOLAR_LUMINOSITY,
+        };
+        const AU: f64 = 149_597_870_700.0;
+        let at_earth = sun.illuminance_at(AU);
+        assert!((at_earth - 127e3).abs() < 5e3, "{at_earth}");
+        let at_pluto = sun.illuminance_at(39.5 * AU);
+        assert!((at_pluto * 39.5 * 39.5 / at_earth - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cascades_cover_the_focused_body() {
+        let radius = 6_371e3;
+        let cascades = planetary_cascades(radius + 1e3, radius);
+        assert!(cascades.bounds[0] <= 2e3 + 1.0);
+        assert!(*cascades.bounds.last().unwrap() >= 2.0 * radius);
+
+        let cascades = planetary_cascades(10.0 * radius, radius);
+        assert!(*cascades.bounds.last().unwrap() >= 11.0 * radius);
+    }
 }
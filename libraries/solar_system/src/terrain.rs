@@ -0,0 +1,651 @@
+use crate::body::{BodyShape, FitShapeToMesh};
+use crate::noise::Fbm;
+use crate::planet_material::PlanetStandardMaterial;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::math::{DQuat, DVec2, DVec3};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+use bevy::utils::{HashMap, HashSet};
+use big_space::precision::GridPrecision;
+use big_space::ReferenceFrame;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+pub struct TerrainPlugin<Prec: GridPrecision>(PhantomData<Prec>);
+
+impl<Prec: GridPrecision> Default for TerrainPlugin<Prec> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Prec: GridPrecision> Plugin for TerrainPlugin<Prec> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Terrain>()
+            .register_type::<TerrainChunk>()
+            .add_systems(
+                Update,
+                (
+                    prepare_height_fields,
+                    update_terrain_lod,
+                    spawn_ready_chunks::<Prec>,
+                    replace_stale_chunks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Procedural elevation, added on top of the height map.
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct TerrainNoise {
+    pub seed: u32,
+    pub octaves: u32,
+    /// Elevation of the first octave, in meters.
+    pub amplitude: f64,
+    /// Size of the features of the first octave, in meters.
+    pub wavelength: f64,
+    pub lacunarity: f64,
+    pub persistence: f64,
+}
+
+/// Height map in an equirectangular projection, mapping black to `min_elevation` and white to
+/// `max_elevation`, in meters.
+#[derive(Debug, Clone, Reflect)]
+pub struct TerrainHeightMap {
+    pub image: Handle<Image>,
+    pub min_elevation: f32,
+    pub max_elevation: f32,
+}
+
+/// Quadtree terrain on a cube-sphere, replacing the mesh of the body when seen up close. It is put
+/// on the reference frame of the body, and its chunks are spawned as children of the frame.
+///
+/// The cube-sphere is stretched to the [`BodyShape`] of the body, so that the terrain matches the
+/// ellipsoid used for picking and the near plane.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Terrain {
+    /// Entity holding the mesh, the material and the shape of the body.
+    pub body: Entity,
+    /// Orientation of the body within its frame.
+    pub orientation: DQuat,
+    pub height_map: Option<TerrainHeightMap>,
+    pub noise: Option<TerrainNoise>,
+    /// Number of vertices along the side of a chunk.
+    pub resolution: u32,
+    /// Deepest level of the quadtrees, at most [`Terrain::MAX_DEPTH`].
+    pub max_depth: u8,
+    /// Screen-space error, in pixels, above which chunks are split.
+    pub max_screen_error: f32,
+}
+
+impl MapEntities for Terrain {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.body = entity_mapper.map_entity(self.body);
+    }
+}
+
+impl Terrain {
+    /// Deepest level supported, as the coordinates of chunks within their face are `u32`. Chunks
+    /// are about a centimeter wide at this level on an Earth-sized body.
+    pub const MAX_DEPTH: u8 = 30;
+
+    /// Upper bound on the elevation of the surface above the shape of the body, in meters.
+    pub fn max_elevation(&self) -> f64 {
+        let height_map = self
+            .height_map
+            .as_ref()
+            .map_or(0.0, |height_map| height_map.max_elevation.max(0.0) as f64);
+        let noise = self.noise.map_or(0.0, |noise| {
+            (0..noise.octaves)
+                .map(|octave| noise.amplitude * noise.persistence.powi(octave as _))
+                .sum()
+        });
+        height_map + noise
+    }
+}
+
+/// Position of a chunk in the quadtree of a cube face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub struct TerrainChunk {
+    pub face: u8,
+    pub level: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TerrainChunk {
+    pub fn root(face: u8) -> Self {
+        Self {
+            face,
+            level: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    pub fn children(&self) -> [Self; 4] {
+        let level = self.level + 1;
+        let (x, y) = (2 * self.x, 2 * self.y);
+        [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)].map(|(x, y)| Self {
+            face: self.face,
+            level,
+            x,
+            y,
+        })
+    }
+
+    /// Whether `other` is this chunk or one of its descendants.
+    pub fn contains(&self, other: &Self) -> bool {
+        if self.face != other.face || self.level > other.level {
+            return false;
+        }
+        let shift = other.level - self.level;
+        other.x >> shift == self.x && other.y >> shift == self.y
+    }
+
+    /// Angle covered by the side of the chunk, approximately.
+    pub fn angular_size(&self) -> f64 {
+        FRAC_PI_2 / (1u64 << self.level) as f64
+    }
+
+    /// Direction from the center of the body, for coordinates within the chunk going from 0 to 1.
+    /// Coordinates outside of that range extend the chunk past its edges.
+    pub fn direction(&self, local: DVec2) -> DVec3 {
+        let size = 2.0 / (1u64 << self.level) as f64;
+        let face = DVec2::new(self.x as f64, self.y as f64) + local;
+        cube_to_sphere(self.face, face * size - 1.0)
+    }
+}
+
+/// Maps a point of a cube face, with coordinates in `[-1, 1]`, to the unit sphere. The mapping
+/// spreads vertices more evenly than normalizing the point.
+fn cube_to_sphere(face: u8, uv: DVec2) -> DVec3 {
+    // Normal, then the axes of u and v, with u × v = normal so that faces wind outwards
+    let (normal, u, v) = match face {
+        0 => (DVec3::X, DVec3::NEG_Z, DVec3::Y),
+        1 => (DVec3::NEG_X, DVec3::Z, DVec3::Y),
+        2 => (DVec3::Y, DVec3::X, DVec3::NEG_Z),
+        3 => (DVec3::NEG_Y, DVec3::X, DVec3::Z),
+        4 => (DVec3::Z, DVec3::X, DVec3::Y),
+        _ => (DVec3::NEG_Z, DVec3::NEG_X, DVec3::Y),
+    };
+    let p = normal + u * uv.x + v * uv.y;
+    let p2 = p * p;
+    DVec3::new(
+        p.x * (1.0 - p2.y / 2.0 - p2.z / 2.0 + p2.y * p2.z / 3.0).sqrt(),
+        p.y * (1.0 - p2.z / 2.0 - p2.x / 2.0 + p2.z * p2.x / 3.0).sqrt(),
+        p.z * (1.0 - p2.x / 2.0 - p2.y / 2.0 + p2.x * p2.y / 3.0).sqrt(),
+    )
+}
+
+/// Point of the ellipsoid with semi-axes `radii` which the unit sphere maps to in `direction`, raised
+/// by `elevation` along the normal of the ellipsoid.
+fn surface_point(radii: DVec3, direction: DVec3, elevation: f64) -> DVec3 {
+    direction * radii + ellipsoid_normal(radii, direction) * elevation
+}
+
+/// Normal of the ellipsoid with semi-axes `radii` at the point the unit sphere maps to in
+/// `direction`.
+fn ellipsoid_normal(radii: DVec3, direction: DVec3) -> DVec3 {
+    (direction / radii).normalize()
+}
+
+/// Equirectangular texture coordinates of a direction, matching
+/// [`equirectangular_sphere`](crate::mesh::equirectangular_sphere).
+fn equirectangular_uv(direction: DVec3) -> DVec2 {
+    let longitude = (-direction.z).atan2(direction.x);
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    DVec2::new((longitude + PI) / TAU, (FRAC_PI_2 - latitude) / PI)
+}
+
+/// Elevations decoded from a height map.
+#[derive(Debug, Clone)]
+pub struct HeightMap {
+    width: u32,
+    height: u32,
+    elevations: Vec<f32>,
+}
+
+impl HeightMap {
+    pub fn from_image(image: &Image, min_elevation: f32, max_elevation: f32) -> Option<Self> {
+        let (width, height) = (image.width(), image.height());
+        let elevations = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let value = image.get_color_at(x, y).ok()?.to_linear().red;
+                Some(min_elevation + value * (max_elevation - min_elevation))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            width,
+            height,
+            elevations,
+        })
+    }
+
+    /// Bilinear sample, wrapping around in longitude.
+    pub fn sample(&self, uv: DVec2) -> f64 {
+        let x = uv.x * self.width as f64 - 0.5;
+        let y = (uv.y * self.height as f64 - 0.5).clamp(0.0, (self.height - 1) as f64);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let texel = |x: f64, y: f64| {
+            let x = (x as i64).rem_euclid(self.width as i64) as u32;
+            let y = (y as u32).min(self.height - 1);
+            self.elevations[(y * self.width + x) as usize] as f64
+        };
+        let top = texel(x0, y0) * (1.0 - tx) + texel(x0 + 1.0, y0) * tx;
+        let bottom = texel(x0, y0 + 1.0) * (1.0 - tx) + texel(x0 + 1.0, y0 + 1.0) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+/// Elevation of the surface, shared with the tasks generating the chunks.
+#[derive(Debug, Clone, Default)]
+pub struct HeightField {
+    pub height_map: Option<HeightMap>,
+    pub noise: Option<Fbm>,
+}
+
+impl HeightField {
+    /// Elevation above the shape of the body, in meters, in the given direction.
+    pub fn elevation(&self, direction: DVec3) -> f64 {
+        let map = self
+            .height_map
+            .as_ref()
+            .map(|map| map.sample(equirectangular_uv(direction)))
+            .unwrap_or_default();
+        let noise = self
+            .noise
+            .as_ref()
+            .map(|noise| noise.sample(direction))
+            .unwrap_or_default();
+        map + noise
+    }
+}
+
+/// Runtime state of a terrain: the chunks in use, and the ones being generated.
+#[derive(Default, Component)]
+pub struct TerrainChunks {
+    field: Option<Arc<HeightField>>,
+    /// Semi-axes of the body at zero elevation, taken from its shape along with the field.
+    radii: DVec3,
+    desired: HashSet<TerrainChunk>,
+    chunks: HashMap<TerrainChunk, Entity>,
+    tasks: HashMap<TerrainChunk, Task<ChunkMesh>>,
+    covers_body: bool,
+}
+
+struct ChunkMesh {
+    /// Position of the chunk in the body, in meters, which its vertices are relative to.
+    center: DVec3,
+    mesh: Mesh,
+}
+
+fn prepare_height_fields(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    q_added: Query<Entity, Added<Terrain>>,
+    mut q_terrains: Query<(&Terrain, &mut TerrainChunks)>,
+    // Shapes fitted to a mesh are final once the mesh is loaded
+    q_shapes: Query<&BodyShape, Without<FitShapeToMesh>>,
+) {
+    for entity in &q_added {
+        commands.entity(entity).insert(TerrainChunks::default());
+    }
+    for (terrain, mut state) in &mut q_terrains {
+        if state.field.is_some() {
+            continue;
+        }
+        let Ok(shape) = q_shapes.get(terrain.body) else {
+            continue;
+        };
+        let radii = shape.radii.as_dvec3();
+        let height_map = match &terrain.height_map {
+            Some(source) => {
+                let Some(image) = images.get(&source.image) else {
+                    continue;
+                };
+                HeightMap::from_image(image, source.min_elevation, source.max_elevation)
+            }
+            None => None,
+        };
+        let noise = terrain.noise.map(|noise| {
+            let mut fbm = Fbm::new(noise.seed);
+            fbm.octaves = noise.octaves;
+            // Sampled on the unit sphere
+            fbm.frequency = radii.max_element() / noise.wavelength;
+            fbm.amplitude = noise.amplitude;
+            fbm.lacunarity = noise.lacunarity;
+            fbm.persistence = noise.persistence;
+            fbm
+        });
+        state.radii = radii;
+        state.field = Some(Arc::new(HeightField { height_map, noise }));
+    }
+}
+
+fn update_terrain_lod(
+    q_cameras: Query<(&Camera, &GlobalTransform, &Projection)>,
+    mut q_terrains: Query<(&Terrain, &GlobalTransform, &mut TerrainChunks)>,
+) {
+    let Some((camera_position, focal_length)) =
+        q_cameras
+            .iter()
+            .find_map(|(camera, transform, projection)| {
+                let Projection::Perspective(perspective) = projection else {
+                    return None;
+                };
+                let height = camera.physical_viewport_size()?.y as f64;
+                camera.is_active.then(|| {
+                    let scale = height / (2.0 * (perspective.fov as f64 / 2.0).tan());
+                    (transform.translation(), scale)
+                })
+            })
+    else {
+        return;
+    };
+
+    for (terrain, transform, mut state) in &mut q_terrains {
+        let Some(field) = state.field.clone() else {
+            continue;
+        };
+        // Only used to pick the level of detail, where single precision is enough
+        let camera = terrain.orientation.inverse()
+            * transform
+                .affine()
+                .inverse()
+                .transform_point3(camera_position)
+                .as_dvec3();
+        let mut desired = HashSet::default();
+        let mut pending = (0..6).map(TerrainChunk::root).collect::<Vec<_>>();
+        while let Some(chunk) = pending.pop() {
+            let size = state.radii.max_element() * chunk.angular_size();
+            let center = chunk.direction(DVec2::splat(0.5)) * state.radii;
+            let distance = (camera.distance(center) - size).max(1.0);
+            let error = size / (terrain.resolution - 1) as f64 * focal_length / distance;
+            if error > terrain.max_screen_error as f64 && chunk.level < terrain.max_depth {
+                pending.extend(chunk.children());
+            } else {
+                desired.insert(chunk);
+            }
+        }
+
+        // Dropping a task cancels it
+        state.tasks.retain(|chunk, _| desired.contains(chunk));
+        for &chunk in &desired {
+            if state.chunks.contains_key(&chunk) || state.tasks.contains_key(&chunk) {
+                continue;
+            }
+            let field = field.clone();
+            let (radii, resolution) = (state.radii, terrain.resolution);
+            let task = AsyncComputeTaskPool::get()
+                .spawn(async move { chunk_mesh(&field, chunk, radii, resolution) });
+            state.tasks.insert(chunk, task);
+        }
+        state.desired = desired;
+    }
+}
+
+fn spawn_ready_chunks<Prec: GridPrecision>(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut q_terrains: Query<(Entity, &Terrain, &ReferenceFrame<Prec>, &mut TerrainChunks)>,
+    q_materials: Query<&MeshMaterial3d<PlanetStandardMaterial>>,
+) {
+    for (entity, terrain, frame, mut state) in &mut q_terrains {
+        let Ok(material) = q_materials.get(terrain.body) else {
+            continue;
+        };
+        let ready = state
+            .tasks
+            .iter_mut()
+            .filter_map(|(chunk, task)| Some((*chunk, block_on(poll_once(task))?)))
+            .collect::<Vec<_>>();
+        for (chunk, ChunkMesh { center, mesh }) in ready {
+            state.tasks.remove(&chunk);
+            // Placed in double precision within the frame of the body, with vertices relative to
+            // the chunk, so that the surface does not jitter up close
+            let (cell, translation) = frame.translation_to_grid(terrain.orientation * center);
+            let chunk_entity = commands
+                .spawn((
+                    Name::new(format!(
+                        "Terrain Chunk {}/{}/{}/{}",
+                        chunk.face, chunk.level, chunk.x, chunk.y
+                    )),
+                    chunk,
+                    cell,
+                    Transform::from_translation(translation)
+                        .with_rotation(terrain.orientation.as_quat()),
+                    Mesh3d(meshes.add(mesh)),
+                    material.clone(),
+                    Visibility::Hidden,
+                ))
+                .set_parent(entity)
+                .id();
+            state.chunks.insert(chunk, chunk_entity);
+        }
+    }
+}
+
+/// Removes chunks which are no longer wanted once all of their replacements are ready, so that
+/// the surface never has holes, and shows the new chunks at the same time.
+fn replace_stale_chunks(
+    mut commands: Commands,
+    mut q_terrains: Query<(&Terrain, &mut TerrainChunks)>,
+    mut q_visibility: Query<&mut Visibility>,
+) {
+    for (terrain, mut state) in &mut q_terrains {
+        let state = &mut *state;
+        let overlaps = |a: &TerrainChunk, b: &TerrainChunk| a.contains(b) || b.contains(a);
+        let stale = state
+            .chunks
+            .keys()
+            .filter(|chunk| !state.desired.contains(chunk))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut remaining = Vec::new();
+        for chunk in stale {
+            let replaced = state
+                .desired
+                .iter()
+                .filter(|desired| overlaps(desired, &chunk))
+                .all(|desired| state.chunks.contains_key(desired));
+            if replaced {
+                if let Some(entity) = state.chunks.remove(&chunk) {
+                    commands.entity(entity).despawn_recursive();
+                }
+            } else {
+                remaining.push(chunk);
+            }
+        }
+
+        for (chunk, entity) in &state.chunks {
+            let visible = state.desired.contains(chunk)
+                && !remaining.iter().any(|stale| overlaps(stale, chunk));
+            if let Ok(mut visibility) = q_visibility.get_mut(*entity) {
+                visibility.set_if_neq(if visible {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                });
+            }
+        }
+
+        // Stale chunks are only removed once replaced, so after the first full cover the body
+        // mesh is never needed again
+        if !state.covers_body
+            && !state.desired.is_empty()
+            && state
+                .desired
+                .iter()
+                .all(|chunk| state.chunks.contains_key(chunk))
+        {
+            state.covers_body = true;
+            if let Ok(mut visibility) = q_visibility.get_mut(terrain.body) {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
+
+/// Generates the mesh of a chunk, with skirts hanging from its edges to hide the cracks with
+/// neighbors at other levels of detail.
+fn chunk_mesh(
+    field: &HeightField,
+    chunk: TerrainChunk,
+    radii: DVec3,
+    resolution: u32,
+) -> ChunkMesh {
+    let n = resolution.max(2) as i32;
+    let step = 1.0 / (n - 1) as f64;
+    let center = chunk.direction(DVec2::splat(0.5)) * radii;
+    let size = radii.max_element() * chunk.angular_size();
+    let skirt_depth = 2.0 * size * step;
+
+    // Surface positions, with a border of one vertex around the chunk for the normals
+    let stride = (n + 2) as usize;
+    let index = |i: i32, j: i32| (j + 1) as usize * stride + (i + 1) as usize;
+    let mut directions = Vec::with_capacity(stride * stride);
+    let mut surface = Vec::with_capacity(stride * stride);
+    for j in -1..=n {
+        for i in -1..=n {
+            let direction = chunk.direction(DVec2::new(i as f64, j as f64) * step);
+            directions.push(direction);
+            surface.push(surface_point(radii, direction, field.elevation(direction)));
+        }
+    }
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    for j in 0..n {
+        for i in 0..n {
+            let tangent = surface[index(i + 1, j)] - surface[index(i - 1, j)];
+            let bitangent = surface[index(i, j + 1)] - surface[index(i, j - 1)];
+            positions.push((surface[index(i, j)] - center).as_vec3().to_array());
+            normals.push(tangent.cross(bitangent).normalize().as_vec3().to_array());
+            uvs.push(equirectangular_uv(directions[index(i, j)]));
+        }
+    }
+
+    let grid = |i: i32, j: i32| (j * n + i) as u32;
+    let mut indices = Vec::new();
+    for j in 0..n - 1 {
+        for i in 0..n - 1 {
+            let (a, b, c, d) = (
+                grid(i, j),
+                grid(i + 1, j),
+                grid(i + 1, j + 1),
+                grid(i, j + 1),
+            );
+            indices.extend([a, b, c, a, c, d]);
+        }
+    }
+
+    // Border vertices in counter-clockwise order seen from above
+    let border = (0..n - 1)
+        .map(|i| (i, 0))
+        .chain((0..n - 1).map(|j| (n - 1, j)))
+        .chain((1..n).rev().map(|i| (i, n - 1)))
+        .chain((1..n).rev().map(|j| (0, j)))
+        .collect::<Vec<_>>();
+    let skirt_start = positions.len() as u32;
+    for &(i, j) in &border {
+        let top = grid(i, j) as usize;
+        let direction = directions[index(i, j)];
+        let bottom = surface[index(i, j)] - ellipsoid_normal(radii, direction) * skirt_depth;
+        positions.push((bottom - center).as_vec3().to_array());
+        normals.push(normals[top]);
+        uvs.push(uvs[top]);
+    }
+    for k in 0..border.len() {
+        let next = (k + 1) % border.len();
+        let (top_a, top_b) = (
+            grid(border[k].0, border[k].1),
+            grid(border[next].0, border[next].1),
+        );
+        let (bottom_a, bottom_b) = (skirt_start + k as u32, skirt_start + next as u32);
+        indices.extend([bottom_a, bottom_b, top_b, bottom_a, top_b, top_a]);
+    }
+
+    // Keeps the longitude continuous for chunks across the antimeridian
+    if uvs.iter().any(|uv| uv.x < 0.25) && uvs.iter().any(|uv| uv.x > 0.75) {
+        for uv in &mut uvs {
+            if uv.x < 0.5 {
+                uv.x += 1.0;
+            }
+        }
+    }
+    let uvs = uvs
+        .into_iter()
+        .map(|uv| uv.as_vec2().to_array())
+        .collect::<Vec<_>>();
+
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices));
+    // Tangents are needed for normal maps; they only fail to generate on malformed meshes
+    let mesh = mesh.clone().with_generated_tangents().unwrap_or(mesh);
+    ChunkMesh { center, mesh }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_cover_their_children() {
+        let root = TerrainChunk::root(3);
+        let [_, _, _, child] = root.children();
+        let grandchild = child.children()[1];
+        assert!(root.contains(&grandchild));
+        assert!(child.contains(&grandchild));
+        assert!(!grandchild.contains(&child));
+        assert!(!root.children()[0].contains(&grandchild));
+        assert!(!TerrainChunk::root(2).contains(&grandchild));
+
+        // Children share the corners of their parent
+        let corner = |chunk: TerrainChunk, local: DVec2| chunk.direction(local);
+        assert!(corner(root, DVec2::ONE).distance(corner(child, DVec2::ONE)) < 1e-12);
+        assert!((root.direction(DVec2::splat(0.5)) - DVec3::NEG_Y).length() < 1e-12);
+    }
+
+    #[test]
+    fn cube_faces_wind_outwards_on_the_unit_sphere() {
+        for face in 0..6 {
+            let chunk = TerrainChunk::root(face);
+            let origin = chunk.direction(DVec2::splat(0.3));
+            let u = chunk.direction(DVec2::new(0.4, 0.3)) - origin;
+            let v = chunk.direction(DVec2::new(0.3, 0.4)) - origin;
+            assert!(u.cross(v).dot(origin) > 0.0, "face {face}");
+            assert!((origin.length() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn surface_follows_the_ellipsoid() {
+        let radii = DVec3::new(5e3, 3e3, 4e3);
+        assert!(
+            surface_point(radii, DVec3::Y, 100.0).abs_diff_eq(DVec3::new(0.0, 3.1e3, 0.0), 1e-9)
+        );
+        assert!(surface_point(radii, DVec3::X, 0.0).abs_diff_eq(DVec3::new(5e3, 0.0, 0.0), 1e-9));
+
+        // Spheres are raised along the direction
+        let direction = DVec3::new(1.0, 2.0, 3.0).normalize();
+        let point = surface_point(DVec3::splat(10.0), direction, 1.0);
+        assert!(point.abs_diff_eq(direction * 11.0, 1e-12));
+    }
+}
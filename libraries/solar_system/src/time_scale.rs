@@ -0,0 +1,265 @@
+use crate::mjd::J2000_DAY;
+use bevy::prelude::Reflect;
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// Astronomical time scales supported by the simulation.
+///
+/// The simulation timeline itself is kept in [`TimeScale::Tt`]; the other scales are derived from
+/// it on demand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Reflect)]
+pub enum TimeScale {
+    /// Coordinated Universal Time, following the leap seconds of [`LEAP_SECONDS`].
+    Utc,
+    /// International Atomic Time.
+    Tai,
+    /// Terrestrial Time, `TAI + 32.184 s`.
+    #[default]
+    Tt,
+    /// Barycentric Dynamical Time, approximated from TT with the usual two-term periodic series.
+    Tdb,
+}
+
+impl fmt::Display for TimeScale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeScale::Utc => write!(f, "UTC"),
+            TimeScale::Tai => write!(f, "TAI"),
+            TimeScale::Tt => write!(f, "TT"),
+            TimeScale::Tdb => write!(f, "TDB"),
+        }
+    }
+}
+
+impl FromStr for TimeScale {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scale| scale.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl TimeScale {
+    pub const ALL: [Self; 4] = [Self::Utc, Self::Tai, Self::Tt, Self::Tdb];
+
+    /// Converts a date expressed in this time scale into TT.
+    ///
+    /// Dates are given as an MJD day number and the number of seconds elapsed since the start of
+    /// that day. In UTC, the seconds go up to 86401 on days ending with a leap second.
+    pub fn to_tt(self, day: i64, seconds: f64) -> (i64, f64) {
+        match self {
+            TimeScale::Utc => {
+                let (day, seconds) = utc_to_tai(day, seconds);
+                tai_to_tt(day, seconds)
+            }
+            TimeScale::Tai => tai_to_tt(day, seconds),
+            TimeScale::Tt => normalize(day, seconds),
+            TimeScale::Tdb => tdb_to_tt(day, seconds),
+        }
+    }
+
+    /// Converts a TT date into this time scale. See [`Self::to_tt`] for the date representation.
+    pub fn from_tt(self, day: i64, seconds: f64) -> (i64, f64) {
+        match self {
+            TimeScale::Utc => {
+                let (day, seconds) = tt_to_tai(day, seconds);
+                tai_to_utc(day, seconds)
+            }
+            TimeScale::Tai => tt_to_tai(day, seconds),
+            TimeScale::Tt => normalize(day, seconds),
+            TimeScale::Tdb => tt_to_tdb(day, seconds),
+        }
+    }
+}
+
+/// Leap second table, as pairs of (MJD of the UTC day the offset takes effect, TAI − UTC in
+/// seconds).
+///
+/// Source: IERS Bulletin C. Before 1972, UTC is approximated with the initial 10 s offset.
+pub const LEAP_SECONDS: [(i64, i64); 28] = [
+    (41317, 10), // 1972-01-01
+    (41499, 11), // 1972-07-01
+    (41683, 12), // 1973-01-01
+    (42048, 13), // 1974-01-01
+    (42413, 14), // 1975-01-01
+    (42778, 15), // 1976-01-01
+    (43144, 16), // 1977-01-01
+    (43509, 17), // 1978-01-01
+    (43874, 18), // 1979-01-01
+    (44239, 19), // 1980-01-01
+    (44786, 20), // 1981-07-01
+    (45151, 21), // 1982-07-01
+    (45516, 22), // 1983-07-01
+    (46247, 23), // 1985-07-01
+    (47161, 24), // 1988-01-01
+    (47892, 25), // 1990-01-01
+    (48257, 26), // 1991-01-01
+    (48804, 27), // 1992-07-01
+    (49169, 28), // 1993-07-01
+    (49534, 29), // 1994-07-01
+    (50083, 30), // 1996-01-01
+    (50630, 31), // 1997-07-01
+    (51179, 32), // 1999-01-01
+    (53736, 33), // 2006-01-01
+    (54832, 34), // 2009-01-01
+    (56109, 35), // 2012-07-01
+    (57204, 36), // 2015-07-01
+    (57754, 37), // 2017-01-01
+];
+
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// TT − TAI, in seconds.
+const TT_MINUS_TAI: f64 = 32.184;
+
+/// Carries whole days out of `seconds` so that it lies within `[0, 86400)`.
+pub(crate) fn normalize(day: i64, seconds: f64) -> (i64, f64) {
+    let carry = (seconds / SECONDS_PER_DAY).floor();
+    let (day, seconds) = (day + carry as i64, seconds - carry * SECONDS_PER_DAY);
+    // Rounding can land a tiny negative value right on the next day
+    if seconds >= SECONDS_PER_DAY {
+        (day + 1, seconds - SECONDS_PER_DAY)
+    } else {
+        (day, seconds)
+    }
+}
+
+/// TAI − UTC in seconds for the given UTC day, expressed as an MJD day number.
+pub fn tai_minus_utc(mjd_day: i64) -> i64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= mjd_day)
+        .map(|(_, offset)| *offset)
+        .unwrap_or(LEAP_SECONDS[0].1)
+}
+
+fn utc_to_tai(day: i64, seconds: f64) -> (i64, f64) {
+    normalize(day, seconds + tai_minus_utc(day) as f64)
+}
+
+fn tai_to_utc(day: i64, seconds: f64) -> (i64, f64) {
+    // In TAI, each offset takes effect `offset` seconds into its starting day
+    let index = LEAP_SECONDS
+        .iter()
+        .rposition(|&(start, offset)| day > start || (day == start && seconds >= offset as f64));
+    let offset = index.map_or(LEAP_SECONDS[0].1, |i| LEAP_SECONDS[i].1);
+    let (utc_day, utc_seconds) = normalize(day, seconds - offset as f64);
+
+    let next = index.map_or(0, |i| i + 1);
+    match LEAP_SECONDS.get(next) {
+        // Inside the inserted leap second, which is the 86401st second of the previous day
+        Some(&(next_start, _)) if utc_day >= next_start => (
+            next_start - 1,
+            SECONDS_PER_DAY + (utc_day - next_start) as f64 * SECONDS_PER_DAY + utc_seconds,
+        ),
+        _ => (utc_day, utc_seconds),
+    }
+}
+
+fn tai_to_tt(day: i64, seconds: f64) -> (i64, f64) {
+    normalize(day, seconds + TT_MINUS_TAI)
+}
+
+fn tt_to_tai(day: i64, seconds: f64) -> (i64, f64) {
+    normalize(day, seconds - TT_MINUS_TAI)
+}
+
+/// TDB − TT in seconds, from the two main periodic terms (accurate to ~30 µs).
+fn tdb_minus_tt(day: i64, seconds: f64) -> f64 {
+    let days = (day - J2000_DAY) as f64 + (seconds / SECONDS_PER_DAY - 0.5);
+    let g = (357.53 + 0.985_600_28 * days).to_radians();
+    0.001_657 * g.sin() + 0.000_014 * (2.0 * g).sin()
+}
+
+fn tt_to_tdb(day: i64, seconds: f64) -> (i64, f64) {
+    normalize(day, seconds + tdb_minus_tt(day, seconds))
+}
+
+fn tdb_to_tt(day: i64, seconds: f64) -> (i64, f64) {
+    // The correction drifts by less than a picosecond over its own magnitude, so two fixed-point
+    // iterations are more than enough
+    let mut tt = normalize(day, seconds - tdb_minus_tt(day, seconds));
+    for _ in 0..2 {
+        tt = normalize(day, seconds - tdb_minus_tt(tt.0, tt.1));
+    }
+    tt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn seconds_between(a: (i64, f64), b: (i64, f64)) -> f64 {
+        (a.0 - b.0) as f64 * SECONDS_PER_DAY + (a.1 - b.1)
+    }
+
+    #[test]
+    fn leap_second_table_lookup() {
+        assert_eq!(tai_minus_utc(41317), 10);
+        assert_eq!(tai_minus_utc(51544), 32);
+        assert_eq!(tai_minus_utc(60000), 37);
+        assert_eq!(tai_minus_utc(0), 10);
+        assert_eq!(tai_minus_utc(-1_000_000), 10);
+    }
+
+    #[test]
+    fn j2000_is_64_184_seconds_after_utc_noon() {
+        let tt = TimeScale::Utc.to_tt(J2000_DAY, 43200.0 - 64.184);
+        assert_eq!(tt.0, J2000_DAY);
+        assert_abs_diff_eq!(tt.1, 43200.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn leap_second_round_trips() {
+        // 2016-12-31T23:59:60.5 UTC
+        let leap = (57753, 86400.5);
+        let tt_leap = TimeScale::Utc.to_tt(leap.0, leap.1);
+        let tai = TimeScale::Tai.from_tt(tt_leap.0, tt_leap.1);
+        assert_eq!(tai.0, 57754);
+        assert_abs_diff_eq!(tai.1, 36.5, epsilon = 1e-9);
+        let utc = TimeScale::Utc.from_tt(tt_leap.0, tt_leap.1);
+        assert_eq!(utc.0, leap.0);
+        assert_abs_diff_eq!(utc.1, leap.1, epsilon = 1e-9);
+
+        let after = (57754, 0.0);
+        let tt_after = TimeScale::Utc.to_tt(after.0, after.1);
+        assert_abs_diff_eq!(seconds_between(tt_after, tt_leap), 0.5, epsilon = 1e-9);
+        let utc = TimeScale::Utc.from_tt(tt_after.0, tt_after.1);
+        assert_eq!(utc.0, after.0);
+        assert_abs_diff_eq!(utc.1, after.1, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn all_scales_round_trip() {
+        let samples = [
+            (-365_250_000, 12.5),
+            (-2_000, 18_367.000_000_008),
+            (J2000_DAY, 0.0),
+            (J2000_DAY, 86_399.999_999),
+            (60_491, 3_723.456_789_012),
+            (365_250_000, 43_200.0),
+        ];
+        for scale in TimeScale::ALL {
+            for sample in samples {
+                let there = scale.from_tt(sample.0, sample.1);
+                let back = scale.to_tt(there.0, there.1);
+                assert_abs_diff_eq!(seconds_between(back, sample), 0.0, epsilon = 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn tdb_stays_within_two_milliseconds_of_tt() {
+        for day in (J2000_DAY..J2000_DAY + 800).step_by(7) {
+            let tdb = TimeScale::Tdb.from_tt(day, 0.0);
+            let delta = seconds_between(tdb, (day, 0.0));
+            assert!(delta.abs() < 2e-3, "{delta}");
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::mjd::Mjd;
+use crate::orbit_line::{orbit_line_mesh, LineViewpoint, OrbitLineMaterial};
+use crate::scene::components::SceneCamera;
+use crate::time_scale::TimeScale;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::math::{DQuat, DVec3};
+use bevy::prelude::*;
+use bevy::render::view::VisibilitySystems;
+use big_space::precision::GridPrecision;
+use big_space::{GridCell, ReferenceFrame};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+pub struct TrailPlugin<Prec: GridPrecision>(PhantomData<Prec>);
+
+impl<Prec: GridPrecision> Default for TrailPlugin<Prec> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Prec: GridPrecision> Plugin for TrailPlugin<Prec> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Trail>().add_systems(
+            PostUpdate,
+            (record_trails::<Prec>, update_trail_lines::<Prec>)
+                .chain()
+                .run_if(resource_exists::<Time<Mjd>>)
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::CheckVisibility),
+        );
+    }
+}
+
+/// Line following the past positions of its parent over the last `duration` seconds, fading out
+/// with age. Positions are recorded in double precision in the reference frame, so that the trail
+/// shows the actual motion of the body, whatever moves it.
+///
+/// The entity is drawn with an [`OrbitLineMaterial`], and its mesh is regenerated around the
+/// camera whenever the camera or the body move noticeably, or a position is recorded.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct Trail {
+    /// How long positions are kept, in seconds.
+    pub duration: f64,
+    /// Largest number of positions kept, spread evenly over the duration.
+    pub max_samples: usize,
+    /// Frame in which positions are recorded, the frame the body moves in when `None`.
+    pub reference: Option<Entity>,
+    /// Recorded positions, oldest first.
+    #[reflect(ignore)]
+    samples: VecDeque<(Mjd, DVec3)>,
+    /// Viewpoint the mesh was last generated from, and the date of the newest position then.
+    #[reflect(ignore)]
+    drawn: Option<(LineViewpoint, Option<Mjd>)>,
+}
+
+impl MapEntities for Trail {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        if let Some(reference) = &mut self.reference {
+            *reference = entity_mapper.map_entity(*reference);
+        }
+    }
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Self::new(86400.0)
+    }
+}
+
+impl Trail {
+    pub fn new(duration: f64) -> Self {
+        Self {
+            duration,
+            max_samples: 512,
+            reference: None,
+            samples: VecDeque::new(),
+            drawn: None,
+        }
+    }
+
+    pub fn with_reference(mut self, reference: Entity) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Recorded positions with their date, oldest first.
+    pub fn samples(&self) -> impl DoubleEndedIterator<Item = &(Mjd, DVec3)> {
+        self.samples.iter()
+    }
+
+    /// Forgets every recorded position.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Records the position at the given date, if enough time has passed since the last one, and
+    /// forgets positions which are too old. Positions recorded after the date are forgotten as
+    /// well, so that the trail follows time going backwards.
+    pub fn record(&mut self, t: Mjd, position: DVec3) {
+        while self.samples.back().is_some_and(|(time, _)| *time > t) {
+            self.samples.pop_back();
+        }
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| t.seconds_since(time, TimeScale::Tdb) > self.duration)
+        {
+            self.samples.pop_front();
+        }
+        let interval = self.duration / self.max_samples.max(1) as f64;
+        let too_soon = self
+            .samples
+            .back()
+            .is_some_and(|(time, _)| t.seconds_since(time, TimeScale::Tdb) < interval);
+        if !too_soon {
+            self.samples.push_back((t, position));
+        }
+        while self.samples.len() > self.max_samples.max(1) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+type SpatialQuery<'w, 's, Prec> = Query<
+    'w,
+    's,
+    (
+        Option<&'static Parent>,
+        &'static Transform,
+        Option<&'static GridCell<Prec>>,
+    ),
+    Without<Trail>,
+>;
+
+/// Position and orientation of an entity relative to the root of its big space, in double
+/// precision.
+fn root_position<Prec: GridPrecision>(
+    entity: Entity,
+    q_spatial: &SpatialQuery<Prec>,
+    q_frames: &Query<&ReferenceFrame<Prec>>,
+) -> Option<(DVec3, DQuat)> {
+    let mut position = DVec3::ZERO;
+    let mut rotation = DQuat::IDENTITY;
+    let mut current = entity;
+    loop {
+        let (parent, transform, cell) = q_spatial.get(current).ok()?;
+        let Some(parent) = parent else {
+            // The root of the big space is the origin
+            return Some((position, rotation));
+        };
+        let local = match (cell, q_frames.get(parent.get())) {
+            (Some(cell), Ok(frame)) => frame.grid_position_double(cell, transform),
+            _ => transform.translation.as_dvec3(),
+        };
+        let local_rotation = transform.rotation.as_dquat();
+        position = local + local_rotation * position;
+        rotation = local_rotation * rotation;
+        current = parent.get();
+    }
+}
+
+/// Position of `entity` in the frame of `reference`.
+fn relative_position<Prec: GridPrecision>(
+    entity: Entity,
+    reference: Entity,
+    q_spatial: &SpatialQuery<Prec>,
+    q_frames: &Query<&ReferenceFrame<Prec>>,
+) -> Option<DVec3> {
+    let (position, _) = root_position(entity, q_spatial, q_frames)?;
+    let (origin, rotation) = root_position(reference, q_spatial, q_frames)?;
+    Some(rotation.inverse() * (position - origin))
+}
+
+/// Frame a trail is recorded in, given the body it follows.
+fn trail_reference<Prec: GridPrecision>(
+    trail: &Trail,
+    body: Entity,
+    q_spatial: &SpatialQuery<Prec>,
+) -> Option<Entity> {
+    trail.reference.or_else(|| {
+        let (parent, ..) = q_spatial.get(body).ok()?;
+        parent.map(Parent::get)
+    })
+}
+
+fn record_trails<Prec: GridPrecision>(
+    time: Res<Time<Mjd>>,
+    mut q_trails: Query<(&Parent, &mut Trail)>,
+    q_spatial: SpatialQuery<Prec>,
+    q_frames: Query<&ReferenceFrame<Prec>>,
+) {
+    let t = *time.context();
+    for (parent, mut trail) in &mut q_trails {
+        let Some(reference) = trail_reference(&trail, parent.get(), &q_spatial) else {
+            continue;
+        };
+        let Some(position) = relative_position(parent.get(), reference, &q_spatial, &q_frames)
+        else {
+            continue;
+        };
+        trail.record(t, position);
+    }
+}
+
+/// Regenerates trail lines around the camera, from the current position of the body to the
+/// oldest recorded one, and moves the lines to the camera. Meshes are kept until the camera or
+/// the body move noticeably, or a position is recorded.
+#[allow(clippy::type_complexity)]
+fn update_trail_lines<Prec: GridPrecision>(
+    time: Res<Time<Mjd>>,
+    q_camera: Query<(Entity, &GlobalTransform), (With<SceneCamera>, Without<Trail>)>,
+    q_global: Query<&GlobalTransform, Without<Trail>>,
+    mut q_trails: Query<
+        (&Parent, &mut Trail, &Mesh3d, &mut GlobalTransform),
+        With<MeshMaterial3d<OrbitLineMaterial>>,
+    >,
+    q_spatial: SpatialQuery<Prec>,
+    q_frames: Query<&ReferenceFrame<Prec>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+    let t = *time.context();
+    for (parent, mut trail, mesh, mut transform) in &mut q_trails {
+        let Some(reference) = trail_reference(&trail, parent.get(), &q_spatial) else {
+            continue;
+        };
+        let Ok(reference_transform) = q_global.get(reference) else {
+            continue;
+        };
+        let (Some(body), Some(camera)) = (
+            relative_position(parent.get(), reference, &q_spatial, &q_frames),
+            relative_position(camera, reference, &q_spatial, &q_frames),
+        ) else {
+            continue;
+        };
+        *transform = GlobalTransform::from(
+            Transform::from_translation(camera_transform.translation())
+                .with_rotation(reference_transform.rotation()),
+        );
+        let viewpoint = LineViewpoint { camera, body };
+        let newest = trail.samples().next_back().map(|(time, _)| *time);
+        if trail.drawn.is_some_and(|(drawn, drawn_newest)| {
+            drawn_newest == newest && drawn.is_close_to(&viewpoint)
+        }) {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+        trail.drawn = Some((viewpoint, newest));
+        let points = std::iter::once((t, body))
+            .chain(trail.samples().rev().copied())
+            .map(|(time, point)| {
+                let age = t.seconds_since(&time, TimeScale::Tdb) / trail.duration;
+                (
+                    (point - camera).as_vec3(),
+                    (1.0 - age).clamp(0.0, 1.0) as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+        *mesh = orbit_line_mesh(&points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: f64) -> Mjd {
+        Mjd::from_j2000_seconds(TimeScale::Tdb, seconds)
+    }
+
+    #[test]
+    fn old_samples_are_forgotten() {
+        let mut trail = Trail::new(100.0);
+        for i in 0..=300 {
+            trail.record(at(i as f64), DVec3::new(i as f64, 0.0, 0.0));
+        }
+        let (oldest, _) = trail.samples().next().unwrap();
+        assert!(at(300.0).seconds_since(oldest, TimeScale::Tdb) <= 100.0);
+        assert!(trail.samples().count() <= trail.max_samples);
+    }
+
+    #[test]
+    fn samples_are_spread_over_the_duration() {
+        let mut trail = Trail::new(100.0);
+        trail.max_samples = 10;
+        for i in 0..1000 {
+            trail.record(at(i as f64 * 0.1), DVec3::ZERO);
+        }
+        assert_eq!(trail.samples().count(), 10);
+    }
+
+    #[test]
+    fn going_back_in_time_forgets_the_future() {
+        let mut trail = Trail::new(100.0);
+        for i in 0..50 {
+            trail.record(at(i as f64), DVec3::ZERO);
+        }
+        trail.record(at(20.5), DVec3::ONE);
+        let (newest, position) = trail.samples().next_back().unwrap();
+        assert_eq!(*newest, at(20.5));
+        assert_eq!(*position, DVec3::ONE);
+    }
+}
@@ -0,0 +1,324 @@
+//! Binary format of the Yale Bright Star Catalog, 5th revised edition, as distributed by the
+//! Smithsonian Astrophysical Observatory with the `BSC5` file: a 28-byte header followed by
+//! fixed-size entries whose layout is given by the header.
+
+use super::{absolute_from_apparent, equatorial_position, StarSelection, UNKNOWN_DISTANCE_PARSECS};
+use crate::{InvalidCatalogFormat, Star, Stars};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::color::Color;
+use bevy::utils::ConditionalSendFuture;
+use std::f64::consts::PI;
+
+const HEADER_LEN: usize = 28;
+
+/// Loads the binary Bright Star Catalog. The catalog has no parallaxes, so stars are put at an
+/// unknown distance, in their direction from the Sun, and their color is estimated from their
+/// spectral type.
+#[derive(Default)]
+pub struct Bsc5AssetLoader;
+
+impl AssetLoader for Bsc5AssetLoader {
+    type Asset = Stars;
+    type Settings = StarSelection;
+    type Error = InvalidCatalogFormat;
+
+    fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext,
+    ) -> impl ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
+        async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(Stars::new(parse(&bytes)?, settings, load_context))
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bsc5"]
+    }
+}
+
+/// Reads values in the byte order of the catalog, which depends on the machine it was written on.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Cursor<'a> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (head, tail) = self.bytes.split_at(N);
+        self.bytes = tail;
+        head.try_into().unwrap()
+    }
+
+    fn skip(&mut self, len: usize) -> &'a [u8] {
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        head
+    }
+
+    fn i16(&mut self) -> i16 {
+        let bytes = self.take();
+        if self.big_endian {
+            i16::from_be_bytes(bytes)
+        } else {
+            i16::from_le_bytes(bytes)
+        }
+    }
+
+    fn i32(&mut self) -> i32 {
+        let bytes = self.take();
+        if self.big_endian {
+            i32::from_be_bytes(bytes)
+        } else {
+            i32::from_le_bytes(bytes)
+        }
+    }
+
+    fn f32(&mut self) -> f32 {
+        let bytes = self.take();
+        if self.big_endian {
+            f32::from_be_bytes(bytes)
+        } else {
+            f32::from_le_bytes(bytes)
+        }
+    }
+
+    fn f64(&mut self) -> f64 {
+        let bytes = self.take();
+        if self.big_endian {
+            f64::from_be_bytes(bytes)
+        } else {
+            f64::from_le_bytes(bytes)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    /// Number of stars, negative when coordinates are J2000 rather than B1950.
+    star_count: i32,
+    /// 0 without catalog numbers, positive with catalog numbers, negative with names of this
+    /// length.
+    star_numbers: i32,
+    /// 0 without proper motion, 1 with proper motion, 2 with radial velocity as well.
+    proper_motion: i32,
+    /// Number of magnitudes, negative when coordinates are J2000 rather than B1950.
+    magnitudes: i32,
+    /// Length of an entry, in bytes.
+    entry_len: i32,
+}
+
+impl Header {
+    fn read(bytes: &[u8], big_endian: bool) -> Self {
+        let mut cursor = Cursor { bytes, big_endian };
+        // Offset and first star number, useless when reading the whole catalog
+        cursor.skip(8);
+        Self {
+            star_count: cursor.i32(),
+            star_numbers: cursor.i32(),
+            proper_motion: cursor.i32(),
+            magnitudes: cursor.i32(),
+            entry_len: cursor.i32(),
+        }
+    }
+
+    /// Length of an entry, from its contents.
+    fn expected_entry_len(&self) -> usize {
+        let number = if self.star_numbers > 0 { 4 } else { 0 };
+        let name = if self.star_numbers < 0 {
+            self.star_numbers.unsigned_abs() as usize
+        } else {
+            0
+        };
+        let proper_motion = match self.proper_motion {
+            1 => 8,
+            2 => 16,
+            _ => 0,
+        };
+        number + 8 + 8 + 2 + 2 * self.magnitudes.unsigned_abs() as usize + proper_motion + name
+    }
+}
+
+fn parse(bytes: &[u8]) -> Result<Vec<Star>, InvalidCatalogFormat> {
+    if bytes.len() < HEADER_LEN {
+        return Err(InvalidCatalogFormat::InvalidHeader("file is too short"));
+    }
+    let plausible = |header: &Header| (1..=1024).contains(&header.entry_len);
+    let (header, big_endian) = [false, true]
+        .into_iter()
+        .map(|big_endian| (Header::read(bytes, big_endian), big_endian))
+        .find(|(header, _)| plausible(header))
+        .ok_or(InvalidCatalogFormat::InvalidHeader("unknown byte order"))?;
+    if header.star_count > 0 {
+        return Err(InvalidCatalogFormat::InvalidHeader(
+            "B1950 coordinates are not supported",
+        ));
+    }
+    if header.magnitudes == 0 {
+        return Err(InvalidCatalogFormat::InvalidHeader(
+            "catalog has no magnitudes",
+        ));
+    }
+    let expected = header.expected_entry_len();
+    let entry_len = header.entry_len as usize;
+    if entry_len != expected {
+        return Err(InvalidCatalogFormat::InvalidEntrySize {
+            expected,
+            found: entry_len,
+        });
+    }
+    let count = header.star_count.unsigned_abs() as usize;
+    let entries = &bytes[HEADER_LEN..];
+    if entries.len() < count * entry_len {
+        return Err(InvalidCatalogFormat::InvalidHeader(
+            "file ends before the last star",
+        ));
+    }
+    Ok(entries
+        .chunks_exact(entry_len)
+        .take(count)
+        .filter_map(|entry| {
+            parse_entry(
+                &header,
+                Cursor {
+                    bytes: entry,
+                    big_endian,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Reads a star, skipping the entries of objects removed from the catalog. The header must have
+/// at least one magnitude.
+fn parse_entry(header: &Header, mut cursor: Cursor) -> Option<Star> {
+    let number = (header.star_numbers > 0).then(|| cursor.f32());
+    let right_ascension = cursor.f64();
+    let declination = cursor.f64();
+    let spectral_type = cursor.take::<2>();
+    let relative_magnitude = cursor.i16() as f64 / 100.0;
+    cursor.skip(2 * (header.magnitudes.unsigned_abs() as usize - 1));
+    cursor.skip(match header.proper_motion {
+        1 => 8,
+        2 => 16,
+        _ => 0,
+    });
+    let name = if header.star_numbers < 0 {
+        let name = cursor.skip(header.star_numbers.unsigned_abs() as usize);
+        Some(String::from_utf8_lossy(name).trim().to_string()).filter(|name| !name.is_empty())
+    } else {
+        number.map(|number| format!("HR {number}"))
+    };
+    if right_ascension == 0.0 && declination == 0.0 {
+        return None;
+    }
+    let distance_parsecs = UNKNOWN_DISTANCE_PARSECS;
+    Some(Star {
+        name,
+        right_ascension: right_ascension * 12.0 / PI,
+        declination: declination.to_degrees(),
+        relative_magnitude,
+        absolute_magnitude: absolute_from_apparent(relative_magnitude, distance_parsecs),
+        distance_parsecs,
+        position: equatorial_position(right_ascension, declination, distance_parsecs),
+        color_index: spectral_color_index(spectral_type) as f32,
+        color: Color::WHITE,
+    })
+}
+
+/// B-V color index of a main sequence star of the given spectral type, such as `A1`.
+fn spectral_color_index(spectral_type: [u8; 2]) -> f64 {
+    const CLASSES: &[u8] = b"OBAFGKM";
+    // Color index at subclass 0 of each class, and at the end of class M
+    const COLOR_INDICES: [f64; 8] = [-0.32, -0.30, 0.0, 0.30, 0.58, 0.81, 1.40, 2.0];
+    let Some(class) = CLASSES.iter().position(|c| *c == spectral_type[0]) else {
+        return 0.0;
+    };
+    let subclass = match spectral_type[1] {
+        digit @ b'0'..=b'9' => (digit - b'0') as f64 / 10.0,
+        _ => 0.0,
+    };
+    COLOR_INDICES[class] + subclass * (COLOR_INDICES[class + 1] - COLOR_INDICES[class])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Catalog with Sirius and a removed entry, with catalog numbers, proper motions and J2000
+    /// coordinates, as in the distributed file.
+    fn catalog(big_endian: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        macro_rules! put {
+            ($($value:expr),*) => {$(
+                if big_endian {
+                    bytes.extend($value.to_be_bytes());
+                } else {
+                    bytes.extend($value.to_le_bytes());
+                }
+            )*};
+        }
+        put!(0i32, 1i32, -2i32, 1i32, 1i32, -1i32, 32i32);
+        for (number, ra, dec, magnitude) in [
+            (2491.0f32, 1.767_791f64, -0.291_751f64, -146i16),
+            (92.0, 0.0, 0.0, 0),
+        ] {
+            put!(number, ra, dec);
+            bytes.extend(b"A1");
+            put!(magnitude, 0f32, 0f32);
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_both_byte_orders() {
+        for big_endian in [false, true] {
+            let stars = parse(&catalog(big_endian)).unwrap();
+            assert_eq!(stars.len(), 1, "removed entries are skipped");
+            let sirius = &stars[0];
+            assert_eq!(sirius.name.as_deref(), Some("HR 2491"));
+            assert_eq!(sirius.relative_magnitude, -1.46);
+            assert!((sirius.right_ascension - 6.752).abs() < 1e-3);
+            assert!((sirius.declination + 16.716).abs() < 1e-3);
+            assert!((sirius.color_index - 0.03).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn entry_size_must_match_the_header() {
+        let mut bytes = catalog(false);
+        bytes[24] = 36;
+        assert!(matches!(
+            parse(&bytes),
+            Err(InvalidCatalogFormat::InvalidEntrySize {
+                expected: 32,
+                found: 36
+            })
+        ));
+    }
+
+    #[test]
+    fn unsupported_headers_are_errors() {
+        // B1950 coordinates
+        let mut bytes = catalog(false);
+        bytes[8] = 2;
+        bytes[9..12].fill(0);
+        assert!(matches!(
+            parse(&bytes),
+            Err(InvalidCatalogFormat::InvalidHeader(_))
+        ));
+
+        // No magnitudes
+        let mut bytes = catalog(false);
+        bytes[20..24].fill(0);
+        bytes[24] = 30;
+        assert!(matches!(
+            parse(&bytes),
+            Err(InvalidCatalogFormat::InvalidHeader(_))
+        ));
+    }
+}
@@ -0,0 +1,154 @@
+//! Fixed-width format of the Hipparcos main catalog (ESA 1997), as distributed by CDS with the
+//! `hip_main.dat` file of catalog I/239, one star per line. The file is loaded with the `.hip`
+//! extension, as `.dat` is shared by too many formats.
+
+use super::{
+    absolute_from_apparent, equatorial_position, parse_field, DistanceUnit, StarSelection,
+    UNKNOWN_DISTANCE_PARSECS,
+};
+use crate::{InvalidCatalogFormat, Star, Stars};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::color::Color;
+use bevy::utils::ConditionalSendFuture;
+
+/// Character columns of the fields, starting at 1 and inclusive, as in the description of the
+/// catalog.
+const HIP: (usize, usize) = (9, 14);
+const VMAG: (usize, usize) = (42, 46);
+const RA_DEGREES: (usize, usize) = (52, 63);
+const DEC_DEGREES: (usize, usize) = (65, 76);
+const PARALLAX: (usize, usize) = (80, 86);
+const B_MINUS_V: (usize, usize) = (246, 251);
+
+/// Loads the Hipparcos main catalog. Stars without astrometry or photometry are skipped.
+#[derive(Default)]
+pub struct HipparcosAssetLoader;
+
+impl AssetLoader for HipparcosAssetLoader {
+    type Asset = Stars;
+    type Settings = StarSelection;
+    type Error = InvalidCatalogFormat;
+
+    fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext,
+    ) -> impl ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
+        async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+            let stars = text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| parse_line(line).transpose())
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Stars::new(stars, settings, load_context))
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hip"]
+    }
+}
+
+fn field(line: &str, (start, end): (usize, usize)) -> Option<&str> {
+    line.get(start - 1..end)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+fn parse_optional(line: &str, column: (usize, usize)) -> Result<Option<f64>, InvalidCatalogFormat> {
+    field(line, column)
+        .map(|text| parse_field(text, column.0))
+        .transpose()
+}
+
+fn parse_line(line: &str) -> Result<Option<Star>, InvalidCatalogFormat> {
+    if line.len() < PARALLAX.1 {
+        return Err(InvalidCatalogFormat::MissingColumns {
+            expected: PARALLAX.1,
+            num_columns: line.len(),
+        });
+    }
+    let (Some(relative_magnitude), Some(right_ascension), Some(declination)) = (
+        parse_optional(line, VMAG)?,
+        parse_optional(line, RA_DEGREES)?,
+        parse_optional(line, DEC_DEGREES)?,
+    ) else {
+        return Ok(None);
+    };
+    let distance_parsecs = parse_optional(line, PARALLAX)?
+        .map_or(UNKNOWN_DISTANCE_PARSECS, |plx| {
+            DistanceUnit::ParallaxMilliarcseconds.to_parsecs(plx)
+        });
+    Ok(Some(Star {
+        name: field(line, HIP).map(|hip| format!("HIP {hip}")),
+        right_ascension: right_ascension / 15.0,
+        declination,
+        relative_magnitude,
+        absolute_magnitude: absolute_from_apparent(relative_magnitude, distance_parsecs),
+        distance_parsecs,
+        position: equatorial_position(
+            right_ascension.to_radians(),
+            declination.to_radians(),
+            distance_parsecs,
+        ),
+        // Some stars have no color index
+        color_index: parse_optional(line, B_MINUS_V)?.unwrap_or(0.0) as f32,
+        color: Color::WHITE,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line of the catalog for Sirius, with fields placed at their columns.
+    fn sirius() -> String {
+        let mut line = vec![b' '; 450];
+        let mut put = |(start, _): (usize, usize), value: &str| {
+            line[start - 1..start - 1 + value.len()].copy_from_slice(value.as_bytes());
+        };
+        put((1, 1), "H|");
+        put(HIP, " 32349");
+        put(VMAG, "-1.44");
+        put(RA_DEGREES, "101.28854105");
+        put(DEC_DEGREES, "-16.71314306");
+        put(PARALLAX, " 379.21");
+        put(B_MINUS_V, " 0.009");
+        String::from_utf8(line).unwrap()
+    }
+
+    #[test]
+    fn reads_fixed_width_fields() {
+        let star = parse_line(&sirius()).unwrap().unwrap();
+        assert_eq!(star.name.as_deref(), Some("HIP 32349"));
+        assert_eq!(star.relative_magnitude, -1.44);
+        assert!((star.distance_parsecs - 2.637).abs() < 1e-3);
+        assert!((star.absolute_magnitude - 1.45).abs() < 1e-2);
+        assert!((star.right_ascension - 6.7526).abs() < 1e-4);
+        assert!((star.color_index - 0.009).abs() < 1e-6);
+
+        let mut line = sirius();
+        line.replace_range(B_MINUS_V.0 - 1..B_MINUS_V.1, &" ".repeat(6));
+        assert_eq!(parse_line(&line).unwrap().unwrap().color_index, 0.0);
+        line.replace_range(B_MINUS_V.0 - 1..B_MINUS_V.1, " 0.0x9");
+        assert!(matches!(
+            parse_line(&line),
+            Err(InvalidCatalogFormat::FieldParseError { .. })
+        ));
+    }
+
+    #[test]
+    fn stars_without_astrometry_are_skipped() {
+        let mut line = sirius();
+        line.replace_range(RA_DEGREES.0 - 1..RA_DEGREES.1, &" ".repeat(12));
+        assert!(parse_line(&line).unwrap().is_none());
+        assert!(matches!(
+            parse_line(&line[..40]),
+            Err(InvalidCatalogFormat::MissingColumns { .. })
+        ));
+    }
+}
@@ -0,0 +1,488 @@
+//! Star catalog formats. CSV catalogs such as HYG and Gaia extracts are read by
+//! [`StarsAssetLoader`](crate::StarsAssetLoader) with [`StarsLoaderSettings`], and the binary
+//! Yale Bright Star Catalog and the fixed-width Hipparcos main catalog have their own loaders.
+
+pub mod bsc5;
+pub mod hipparcos;
+
+use crate::{InvalidCatalogFormat, Star};
+use bevy::color::Color;
+use bevy::math::{dvec3, DVec3};
+use csv_async::StringRecord;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Distance given to stars whose distance is unknown, in parsecs, as in the HYG database.
+pub(crate) const UNKNOWN_DISTANCE_PARSECS: f64 = 100_000.0;
+
+/// Which stars of a catalog are spawned in its scene. Every star is kept in the
+/// [`Stars`](crate::Stars) asset.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarSelection {
+    /// Stars dimmer than this apparent magnitude, seen from the Sun, are not spawned.
+    pub magnitude_limit: f64,
+    /// Distance under which stars are spawned regardless of their magnitude, in parsecs, as they
+    /// get brighter when travelling to them.
+    pub nearby_distance: f64,
+}
+
+impl Default for StarSelection {
+    fn default() -> Self {
+        Self {
+            magnitude_limit: 6.5,
+            nearby_distance: 10.0,
+        }
+    }
+}
+
+impl StarSelection {
+    pub fn contains(&self, star: &Star) -> bool {
+        star.relative_magnitude < self.magnitude_limit
+            || star.distance_parsecs <= self.nearby_distance
+    }
+}
+
+/// Settings of [`StarsAssetLoader`](crate::StarsAssetLoader), defaulting to the layout of the
+/// HYG database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarsLoaderSettings {
+    /// Field delimiter of the catalog.
+    pub delimiter: u8,
+    pub selection: StarSelection,
+    pub columns: CatalogColumns,
+    pub units: CatalogUnits,
+    /// Photometric system of the magnitude and color index columns.
+    pub photometry: Photometry,
+}
+
+impl Default for StarsLoaderSettings {
+    fn default() -> Self {
+        Self::hyg()
+    }
+}
+
+impl StarsLoaderSettings {
+    /// [HYG database](https://github.com/astronexus/HYG-Database), v3.
+    pub fn hyg() -> Self {
+        Self {
+            delimiter: b',',
+            selection: StarSelection::default(),
+            columns: CatalogColumns {
+                proper_name: Some("proper".into()),
+                designation: Some("bf".into()),
+                right_ascension: "ra".into(),
+                declination: "dec".into(),
+                distance: "dist".into(),
+                magnitude: "mag".into(),
+                absolute_magnitude: Some("absmag".into()),
+                color_index: Some("ci".into()),
+                position: Some(["x".into(), "y".into(), "z".into()]),
+            },
+            units: CatalogUnits {
+                right_ascension: AngleUnit::Hours,
+                declination: AngleUnit::Degrees,
+                distance: DistanceUnit::Parsecs,
+                position: DistanceUnit::Parsecs,
+            },
+            photometry: Photometry::Johnson,
+        }
+    }
+
+    /// CSV extracts of the `gaia_source` table of Gaia DR3, as downloaded from the Gaia archive,
+    /// with at least the `ra`, `dec`, `parallax`, `phot_g_mean_mag` and `bp_rp` columns.
+    pub fn gaia_dr3() -> Self {
+        Self {
+            delimiter: b',',
+            selection: StarSelection::default(),
+            columns: CatalogColumns {
+                proper_name: None,
+                designation: Some("designation".into()),
+                right_ascension: "ra".into(),
+                declination: "dec".into(),
+                distance: "parallax".into(),
+                magnitude: "phot_g_mean_mag".into(),
+                absolute_magnitude: None,
+                color_index: Some("bp_rp".into()),
+                position: None,
+            },
+            units: CatalogUnits {
+                right_ascension: AngleUnit::Degrees,
+                declination: AngleUnit::Degrees,
+                distance: DistanceUnit::ParallaxMilliarcseconds,
+                position: DistanceUnit::Parsecs,
+            },
+            photometry: Photometry::Gaia,
+        }
+    }
+}
+
+/// Photometric system of a catalog, converted to the Johnson V magnitude and B-V color index used
+/// by [`Star`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Photometry {
+    /// V magnitude and B-V color index.
+    #[default]
+    Johnson,
+    /// Gaia G magnitude and BP-RP color index.
+    Gaia,
+}
+
+impl Photometry {
+    /// V magnitude and B-V color index from the magnitude and color index in this system.
+    pub fn to_johnson(self, magnitude: f64, color_index: Option<f64>) -> (f64, f64) {
+        match (self, color_index) {
+            (Self::Johnson, color_index) => (magnitude, color_index.unwrap_or(0.0)),
+            (Self::Gaia, Some(bp_rp)) => (magnitude - gaia_g_minus_v(bp_rp), gaia_b_minus_v(bp_rp)),
+            (Self::Gaia, None) => (magnitude, 0.0),
+        }
+    }
+}
+
+/// G-V from BP-RP, from the Gaia DR3 documentation (Riello et al. 2021, table C.2), which holds
+/// for -0.5 < BP-RP < 2.75.
+fn gaia_g_minus_v(bp_rp: f64) -> f64 {
+    let x = bp_rp.clamp(-0.5, 2.75);
+    -0.02704 + 0.01424 * x - 0.2156 * x.powi(2) + 0.01426 * x.powi(3)
+}
+
+/// B-V from BP-RP, interpolated along the main sequence colors of Pecaut & Mamajek (2013). Giants
+/// are somewhat off, which is fine for tinting stars.
+fn gaia_b_minus_v(bp_rp: f64) -> f64 {
+    const SEQUENCE: [(f64, f64); 11] = [
+        (-0.32, -0.30),
+        (-0.14, -0.16),
+        (0.0, 0.0),
+        (0.43, 0.30),
+        (0.77, 0.59),
+        (0.82, 0.65),
+        (0.98, 0.82),
+        (1.45, 1.15),
+        (1.84, 1.43),
+        (2.40, 1.50),
+        (3.30, 1.80),
+    ];
+    interpolate(&SEQUENCE, bp_rp)
+}
+
+/// Piecewise linear interpolation through sorted points, clamped at both ends.
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    let index = points.partition_point(|(px, _)| *px < x);
+    match index {
+        0 => points[0].1,
+        i if i == points.len() => points[i - 1].1,
+        i => {
+            let (x0, y0) = points[i - 1];
+            let (x1, y1) = points[i];
+            y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+        }
+    }
+}
+
+/// Absolute magnitude of a star with the given apparent magnitude, at a distance in parsecs.
+pub(crate) fn absolute_from_apparent(magnitude: f64, distance_parsecs: f64) -> f64 {
+    magnitude - 5.0 * (distance_parsecs.max(f64::MIN_POSITIVE) / 10.0).log10()
+}
+
+/// Names of the columns of the catalog, as given in its header. Optional columns are derived from
+/// the others when missing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogColumns {
+    /// Common name, such as "Sirius", preferred over the designation.
+    pub proper_name: Option<String>,
+    /// Catalog designation, such as "9Alp CMa".
+    pub designation: Option<String>,
+    pub right_ascension: String,
+    pub declination: String,
+    /// Distance from the Sun, or parallax, depending on [`CatalogUnits::distance`].
+    pub distance: String,
+    /// Apparent magnitude, seen from the Sun.
+    pub magnitude: String,
+    /// Computed from the apparent magnitude and the distance when missing.
+    pub absolute_magnitude: Option<String>,
+    /// B-V color index, 0 when missing.
+    pub color_index: Option<String>,
+    /// Equatorial cartesian coordinates, computed from the direction and the distance when
+    /// missing.
+    pub position: Option<[String; 3]>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CatalogUnits {
+    pub right_ascension: AngleUnit,
+    pub declination: AngleUnit,
+    pub distance: DistanceUnit,
+    /// Unit of the cartesian coordinates.
+    pub position: DistanceUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AngleUnit {
+    Degrees,
+    Hours,
+    Radians,
+}
+
+impl AngleUnit {
+    pub fn to_radians(self, value: f64) -> f64 {
+        match self {
+            Self::Degrees => value.to_radians(),
+            Self::Hours => value * PI / 12.0,
+            Self::Radians => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistanceUnit {
+    Parsecs,
+    LightYears,
+    /// Parallax, in milliarcseconds, where 0 or less means the distance is unknown.
+    ParallaxMilliarcseconds,
+}
+
+impl DistanceUnit {
+    pub fn to_parsecs(self, value: f64) -> f64 {
+        const LIGHT_YEARS_PER_PARSEC: f64 = 3.261_563_777;
+        match self {
+            Self::Parsecs => value,
+            Self::LightYears => value / LIGHT_YEARS_PER_PARSEC,
+            Self::ParallaxMilliarcseconds if value > 0.0 => 1000.0 / value,
+            Self::ParallaxMilliarcseconds => UNKNOWN_DISTANCE_PARSECS,
+        }
+    }
+}
+
+/// Indices of the columns of [`CatalogColumns`] within the records of a catalog.
+#[derive(Debug, Clone)]
+pub(crate) struct CatalogLayout {
+    proper_name: Option<usize>,
+    designation: Option<usize>,
+    right_ascension: usize,
+    declination: usize,
+    distance: usize,
+    magnitude: usize,
+    absolute_magnitude: Option<usize>,
+    color_index: Option<usize>,
+    position: Option<[usize; 3]>,
+    units: CatalogUnits,
+    photometry: Photometry,
+    /// Number of fields a record needs to have.
+    len: usize,
+}
+
+impl CatalogLayout {
+    /// Finds the columns in the header of the catalog. Only required columns have to be present.
+    pub(crate) fn from_headers(
+        headers: &StringRecord,
+        settings: &StarsLoaderSettings,
+    ) -> Result<Self, InvalidCatalogFormat> {
+        let find = |name: &str| headers.iter().position(|header| header.trim() == name);
+        let require = |name: &String| {
+            find(name).ok_or_else(|| InvalidCatalogFormat::MissingColumn { name: name.clone() })
+        };
+        let columns = &settings.columns;
+        let position = match &columns.position {
+            Some([x, y, z]) => match (find(x), find(y), find(z)) {
+                (Some(x), Some(y), Some(z)) => Some([x, y, z]),
+                _ => None,
+            },
+            None => None,
+        };
+        let mut layout = Self {
+            proper_name: columns.proper_name.as_deref().and_then(find),
+            designation: columns.designation.as_deref().and_then(find),
+            right_ascension: require(&columns.right_ascension)?,
+            declination: require(&columns.declination)?,
+            distance: require(&columns.distance)?,
+            magnitude: require(&columns.magnitude)?,
+            absolute_magnitude: columns.absolute_magnitude.as_deref().and_then(find),
+            color_index: columns.color_index.as_deref().and_then(find),
+            position,
+            units: settings.units,
+            photometry: settings.photometry,
+            len: 0,
+        };
+        layout.len = [
+            layout.proper_name,
+            layout.designation,
+            Some(layout.right_ascension),
+            Some(layout.declination),
+            Some(layout.distance),
+            Some(layout.magnitude),
+            layout.absolute_magnitude,
+            layout.color_index,
+        ]
+        .into_iter()
+        .chain(layout.position.into_iter().flatten().map(Some))
+        .flatten()
+        .max()
+        .map_or(0, |max| max + 1);
+        Ok(layout)
+    }
+
+    /// Reads a star from a record, converting it to the units of [`Star`].
+    pub(crate) fn parse(&self, record: &StringRecord) -> Result<Star, InvalidCatalogFormat> {
+        if record.len() < self.len {
+            return Err(InvalidCatalogFormat::MissingColumns {
+                expected: self.len,
+                num_columns: record.len(),
+            });
+        }
+        let text = |column: Option<usize>| {
+            column
+                .map(|column| record[column].trim())
+                .filter(|text| !text.is_empty())
+        };
+        let optional = |column: Option<usize>| {
+            text(column)
+                .map(|text| parse_field(text, column.unwrap()))
+                .transpose()
+        };
+        let name = text(self.proper_name)
+            .or_else(|| text(self.designation))
+            .map(str::to_string);
+        let right_ascension = self.units.right_ascension.to_radians(parse_field(
+            &record[self.right_ascension],
+            self.right_ascension,
+        )?);
+        let declination = self
+            .units
+            .declination
+            .to_radians(parse_field(&record[self.declination], self.declination)?);
+        let distance_parsecs = match text(Some(self.distance)) {
+            Some(text) => self
+                .units
+                .distance
+                .to_parsecs(parse_field(text, self.distance)?),
+            None => UNKNOWN_DISTANCE_PARSECS,
+        };
+        // Catalogs leave the color index empty for some stars
+        let (relative_magnitude, color_index) = self.photometry.to_johnson(
+            parse_field(&record[self.magnitude], self.magnitude)?,
+            optional(self.color_index)?,
+        );
+        let absolute_magnitude = optional(self.absolute_magnitude)?
+            .unwrap_or_else(|| absolute_from_apparent(relative_magnitude, distance_parsecs));
+        let position = match self.position {
+            Some([x, y, z]) => {
+                let unit = self.units.position;
+                dvec3(
+                    unit.to_parsecs(parse_field(&record[x], x)?),
+                    unit.to_parsecs(parse_field(&record[y], y)?),
+                    unit.to_parsecs(parse_field(&record[z], z)?),
+                )
+            }
+            None => equatorial_position(right_ascension, declination, distance_parsecs),
+        };
+        Ok(Star {
+            name,
+            right_ascension: right_ascension * 12.0 / PI,
+            declination: declination.to_degrees(),
+            relative_magnitude,
+            absolute_magnitude,
+            distance_parsecs,
+            position,
+            color_index: color_index as f32,
+            color: Color::WHITE,
+        })
+    }
+}
+
+/// Equatorial cartesian coordinates, with X towards the vernal equinox and Z towards the north
+/// celestial pole, as in the HYG database.
+pub(crate) fn equatorial_position(right_ascension: f64, declination: f64, distance: f64) -> DVec3 {
+    let (sin_ra, cos_ra) = right_ascension.sin_cos();
+    let (sin_dec, cos_dec) = declination.sin_cos();
+    distance * dvec3(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec)
+}
+
+pub(crate) fn parse_field(text: &str, column: usize) -> Result<f64, InvalidCatalogFormat> {
+    text.trim()
+        .parse()
+        .map_err(|source| InvalidCatalogFormat::FieldParseError { source, column })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYG_HEADER: &str =
+        "id,hip,hd,hr,gl,bf,proper,ra,dec,dist,pmra,pmdec,rv,mag,absmag,spect,ci,x,y,z";
+
+    fn record(line: &str) -> StringRecord {
+        StringRecord::from(line.split(',').collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn hyg_columns_are_found_by_name() {
+        let layout =
+            CatalogLayout::from_headers(&record(HYG_HEADER), &StarsLoaderSettings::hyg()).unwrap();
+        let sirius = layout
+            .parse(&record(
+                "32263,32349,48915,2491,Gl 244A,9Alp CMa,Sirius,6.752481,-16.716116,2.6371,\
+                 -546.01,-1223.08,-5.5,-1.440,1.454,A0m...,0.009,-0.494323,2.476731,-0.758485",
+            ))
+            .unwrap();
+        assert_eq!(sirius.name.as_deref(), Some("Sirius"));
+        assert_eq!(sirius.distance_parsecs, 2.6371);
+        assert_eq!(sirius.absolute_magnitude, 1.454);
+        assert!((sirius.right_ascension - 6.752481).abs() < 1e-9);
+        assert_eq!(sirius.position, dvec3(-0.494323, 2.476731, -0.758485));
+    }
+
+    #[test]
+    fn missing_required_column_is_an_error() {
+        let headers = record("id,proper,ra,dist,mag");
+        let error = CatalogLayout::from_headers(&headers, &StarsLoaderSettings::hyg()).unwrap_err();
+        assert!(
+            matches!(&error, InvalidCatalogFormat::MissingColumn { name } if name == "dec"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn missing_optional_columns_are_derived() {
+        let mut settings = StarsLoaderSettings::hyg();
+        settings.units.right_ascension = AngleUnit::Degrees;
+        settings.units.distance = DistanceUnit::ParallaxMilliarcseconds;
+        let layout = CatalogLayout::from_headers(&record("ra,dec,dist,mag"), &settings).unwrap();
+        let star = layout.parse(&record("90,0,100,5")).unwrap();
+        assert!((star.distance_parsecs - 10.0).abs() < 1e-12);
+        assert!((star.absolute_magnitude - 5.0).abs() < 1e-12);
+        assert!((star.right_ascension - 6.0).abs() < 1e-12);
+        assert!(star.position.abs_diff_eq(dvec3(0.0, 10.0, 0.0), 1e-9));
+        assert_eq!(star.color_index, 0.0);
+    }
+
+    #[test]
+    fn only_empty_color_indices_default_to_zero() {
+        let layout =
+            CatalogLayout::from_headers(&record("ra,dec,dist,mag,ci"), &StarsLoaderSettings::hyg())
+                .unwrap();
+        assert_eq!(layout.parse(&record("6,0,10,5,")).unwrap().color_index, 0.0);
+        assert!(matches!(
+            layout.parse(&record("6,0,10,5,blue")),
+            Err(InvalidCatalogFormat::FieldParseError { column: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn gaia_photometry_is_converted_to_johnson() {
+        let settings = StarsLoaderSettings::gaia_dr3();
+        let layout = CatalogLayout::from_headers(
+            &record("source_id,designation,ra,dec,parallax,phot_g_mean_mag,bp_rp"),
+            &settings,
+        )
+        .unwrap();
+        let star = layout
+            .parse(&record("1,Gaia DR3 1,0,90,200,4.5,0.82"))
+            .unwrap();
+        assert_eq!(star.name.as_deref(), Some("Gaia DR3 1"));
+        assert!((star.relative_magnitude - 4.652).abs() < 1e-3);
+        assert!((star.color_index - 0.65).abs() < 1e-6);
+        assert!((star.distance_parsecs - 5.0).abs() < 1e-12);
+        assert!(star.position.abs_diff_eq(dvec3(0.0, 0.0, 5.0), 1e-9));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Star;
+use crate::{Star, StarAppearance};
 use bevy::asset::embedded_asset;
 use bevy::core_pipeline::core_3d::{Opaque3d, Opaque3dBinKey};
 use bevy::ecs::query::QueryItem;
@@ -65,14 +65,14 @@ struct Instance {
 }
 
 impl ExtractInstance for Instance {
-    type QueryData = (Read<Star>, Read<GlobalTransform>);
+    type QueryData = (Read<Star>, Read<StarAppearance>, Read<GlobalTransform>);
     type QueryFilter = ();
 
-    fn extract((star, transform): QueryItem<'_, Self::QueryData>) -> Option<Self> {
+    fn extract((star, appearance, transform): QueryItem<'_, Self::QueryData>) -> Option<Self> {
         Some(Self {
             position: transform.translation(),
-            scale: star.mesh_scale(),
-            color: star.material_emissive_color().into(),
+            scale: star.mesh_scale_at(appearance.magnitude, appearance.distance),
+            color: star.material_emissive_color_at(appearance.magnitude).into(),
         })
     }
 }
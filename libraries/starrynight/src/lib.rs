@@ -1,3 +1,4 @@
+pub mod catalog;
 mod instancing;
 
 use bevy::asset::io::Reader;
@@ -7,15 +8,18 @@ use bevy::prelude::*;
 use bevy::tasks::futures_lite::StreamExt;
 use bevy::utils::ConditionalSendFuture;
 use big_space::precision::GridPrecision;
-use big_space::{BigSpace, GridCell, ReferenceFrame};
-use csv_async::StringRecord;
+use big_space::{FloatingOrigin, GridCell, ReferenceFrame};
+use catalog::bsc5::Bsc5AssetLoader;
+use catalog::hipparcos::HipparcosAssetLoader;
+use catalog::{CatalogLayout, StarSelection};
 use std::marker::PhantomData;
 use std::num::ParseFloatError;
-use std::str::FromStr;
 use bevy::ecs::query::QueryItem;
 use bevy::render::extract_component::ExtractComponent;
 use thiserror::Error;
 
+pub use catalog::StarsLoaderSettings;
+
 pub struct StarryNightPlugin<Prec: GridPrecision>(PhantomData<Prec>);
 
 impl<Prec: GridPrecision> Default for StarryNightPlugin<Prec> {
@@ -28,19 +32,34 @@ impl<Prec: GridPrecision> Plugin for StarryNightPlugin<Prec> {
     fn build(&self, app: &mut App) {
         app.add_plugins(instancing::InstanceMaterialPlugin)
             .init_asset_loader::<StarsAssetLoader>()
+            .init_asset_loader::<Bsc5AssetLoader>()
+            .init_asset_loader::<HipparcosAssetLoader>()
             .init_asset::<Stars>()
             .register_type::<Stars>()
             .register_type::<Star>()
-            .add_systems(Update, spawn_stars::<Prec>);
+            .register_type::<StarDistances>()
+            .add_systems(Update, spawn_stars::<Prec>)
+            .add_systems(
+                PostUpdate,
+                update_star_appearances::<Prec>.after(TransformSystem::TransformPropagate),
+            );
     }
 }
 
 #[derive(Debug, Error)]
 pub enum InvalidCatalogFormat {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
     #[error("Invalid catalog file: {0}")]
     ParseError(#[from] csv_async::Error),
-    #[error("Invalid catalog format: expected at least 19 columns, found {num_columns}")]
-    MissingColumns { num_columns: usize },
+    #[error("Invalid catalog header: {0}")]
+    InvalidHeader(&'static str),
+    #[error("Invalid catalog format: entries are {found} bytes long, expected {expected}")]
+    InvalidEntrySize { expected: usize, found: usize },
+    #[error("Invalid catalog format: missing column {name:?}")]
+    MissingColumn { name: String },
+    #[error("Invalid catalog format: expected at least {expected} columns, found {num_columns}")]
+    MissingColumns { expected: usize, num_columns: usize },
     #[error("Invalid catalog format column {column}: {source}")]
     FieldParseError {
         source: ParseFloatError,
@@ -53,75 +72,28 @@ pub struct StarsAssetLoader;
 
 impl AssetLoader for StarsAssetLoader {
     type Asset = Stars;
-    type Settings = ();
+    type Settings = StarsLoaderSettings;
     type Error = InvalidCatalogFormat;
 
     fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
         load_context: &mut LoadContext,
     ) -> impl ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
         async move {
-            let reader = csv_async::AsyncReader::from_reader(reader);
-            let mut stream = reader.into_records().skip(2).map(|record| {
-                const COL_NAME_BF: usize = 5;
-                const COL_NAME_PROPER: usize = 6;
-                const COL_RIGHT_ASCENSION: usize = 7;
-                const COL_DECLINATION: usize = 8;
-                const COL_DISTANCE: usize = 9;
-                const COL_MAGNITUDE: usize = 13;
-                const COL_ABSOLUTE_MAGNITUDE: usize = 14;
-                const COL_COLOR_INDEX: usize = 16;
-                const COL_X: usize = 17;
-                const COL_Y: usize = 18;
-                const COL_Z: usize = 19;
-                record
-                    .map_err(InvalidCatalogFormat::ParseError)
-                    .and_then(|rec| {
-                        let len = rec.len();
-                        (len > COL_Z)
-                            .then_some(rec)
-                            .ok_or(InvalidCatalogFormat::MissingColumns { num_columns: len })
-                    })
-                    .and_then(|record| {
-                        let name = if !record[COL_NAME_PROPER].is_empty() {
-                            Some(record[COL_NAME_PROPER].to_string())
-                        } else if !record[COL_NAME_BF].is_empty() {
-                            Some(record[COL_NAME_BF].to_string())
-                        } else {
-                            None
-                        };
-                        Ok(Star {
-                            name,
-                            right_ascension: parse_record(&record, COL_RIGHT_ASCENSION)?,
-                            declination: parse_record(&record, COL_DECLINATION)?,
-                            relative_magnitude: parse_record(&record, COL_MAGNITUDE)?,
-                            absolute_magnitude: parse_record(&record, COL_ABSOLUTE_MAGNITUDE)?,
-                            color_index: record[COL_COLOR_INDEX].parse().unwrap_or(0.0),
-                            position: dvec3(
-                                parse_record(&record, COL_X)?,
-                                parse_record(&record, COL_Y)?,
-                                parse_record(&record, COL_Z)?,
-                            ),
-                            distance_parsecs: parse_record(&record, COL_DISTANCE)?,
-                            color: Color::WHITE,
-                        })
-                    })
-            });
-            let mut world = World::new();
+            let mut reader = csv_async::AsyncReaderBuilder::new()
+                .delimiter(settings.delimiter)
+                .create_reader(reader);
+            let layout = CatalogLayout::from_headers(reader.headers().await?, settings)?;
+            let mut stream = reader
+                .into_records()
+                .map(|record| layout.parse(&record?));
             let mut stars = Vec::new();
             while let Some(star) = stream.next().await {
-                let star = star?;
-                stars.push(star.clone());
-                if star.relative_magnitude >= 6.5 {
-                    continue;
-                }
-                world.spawn(star);
+                stars.push(star?);
             }
-
-            load_context.add_labeled_asset("Scene".to_string(), Scene::new(world));
-            Ok(Stars(stars))
+            Ok(Stars::new(stars, &settings.selection, load_context))
         }
     }
 
@@ -130,18 +102,34 @@ impl AssetLoader for StarsAssetLoader {
     }
 }
 
-fn parse_record<T: FromStr<Err = ParseFloatError>>(
-    record: &StringRecord,
-    column: usize,
-) -> Result<T, InvalidCatalogFormat> {
-    record[column]
-        .parse()
-        .map_err(|source| InvalidCatalogFormat::FieldParseError { source, column })
-}
-
 #[derive(Debug, Clone, Asset, Reflect, Deref)]
 pub struct Stars(Vec<Star>);
 
+impl Stars {
+    /// Creates the asset from the stars of a catalog, along with its `Scene` sub-asset containing
+    /// the selected stars.
+    pub(crate) fn new(
+        stars: impl IntoIterator<Item = Star>,
+        selection: &StarSelection,
+        load_context: &mut LoadContext,
+    ) -> Self {
+        let mut world = World::new();
+        let mut kept = Vec::new();
+        for star in stars {
+            // The Sun is part of the solar system scene
+            if star.distance_parsecs <= 0.0 {
+                continue;
+            }
+            if selection.contains(&star) {
+                world.spawn(star.clone());
+            }
+            kept.push(star);
+        }
+        load_context.add_labeled_asset("Scene".to_string(), Scene::new(world));
+        Self(kept)
+    }
+}
+
 #[derive(Debug, Clone, Component, Reflect)]
 #[reflect(Component)]
 pub struct Star {
@@ -156,19 +144,34 @@ pub struct Star {
     pub color: Color,
 }
 
+/// Parsec, in meters.
+pub const PARSEC: f64 = 3.085_677_581_491_367e16;
+/// Radius of the sphere on which stars are put with [`StarDistances::Sky`], in meters.
+pub const SKY_DISTANCE: f64 = 5e14;
+/// Angle covered by the mesh of a star of magnitude 0, in radians.
+const BASE_ANGULAR_SCALE: f64 = 6e11 / SKY_DISTANCE;
+/// Largest angle covered by the mesh of a star, in radians, so that a star seen from up close does
+/// not swallow the camera.
+const MAX_ANGULAR_SCALE: f64 = 0.02;
+
 impl Star {
+    /// Position relative to the Sun, from the catalog.
     pub fn position_meters(&self) -> DVec3 {
-        // let lon = (self.right_ascension * 360.0 / 24.0 - 180.0).to_radians();
-        // let lat = self.declination.to_radians();
-        // debug!("{:?}: position: {lat}:{lon}", self.name);
-        // let ret = latlon_to_cartesian(lon, lat) * self.distance_meters();
-        // debug!("{:?}: position: {ret}", self.name);
-        // ret
-        self.position.xzy().normalize() * self.distance_meters()
+        self.position.xzy() * PARSEC
+    }
+
+    /// Position on the sky sphere, in the direction of the star as seen from the Sun.
+    pub fn sky_position_meters(&self) -> DVec3 {
+        self.position.xzy().normalize() * SKY_DISTANCE
     }
 
     pub fn distance_meters(&self) -> f64 {
-        5e14
+        self.distance_parsecs * PARSEC
+    }
+
+    /// Apparent magnitude of the star seen from the given distance, in meters.
+    pub fn apparent_magnitude_at(&self, distance: f64) -> f64 {
+        self.absolute_magnitude + 5.0 * (distance / (10.0 * PARSEC)).log10()
     }
 
     pub fn luminosity(&self) -> f64 {
@@ -183,7 +186,7 @@ impl Star {
     }
 
     pub fn magnitude_scaling_biased(&self, base_value: f64, base_magnitude: f64, bias: f64) -> f64 {
-        base_value * 10f64.powf(bias * (base_magnitude - self.relative_magnitude))
+        magnitude_scaling(base_value, base_magnitude, self.relative_magnitude, bias)
     }
 
     pub fn temperature(&self) -> f32 {
@@ -201,17 +204,20 @@ impl Star {
     }
 
     pub fn emissive_power(&self) -> f32 {
-        const BASE_VALUE: f64 = 120_000.0; // Arbitrary base value
-        const SOL_MAG: f64 = -26.7; // Absolute magnitude for the Sun
-
-        self.magnitude_scaling(BASE_VALUE, SOL_MAG) as _
+        emissive_power(self.relative_magnitude)
     }
 
     pub fn mesh_scale(&self) -> f32 {
-        const BASE_SCALE: f64 = 6e11;
+        self.mesh_scale_at(self.relative_magnitude, SKY_DISTANCE)
+    }
+
+    /// Scale of the mesh of the star when it has the given magnitude, seen from the given
+    /// distance, in meters.
+    pub fn mesh_scale_at(&self, magnitude: f64, distance: f64) -> f32 {
         const BASE_MAG: f64 = 0.0;
 
-        self.magnitude_scaling_biased(BASE_SCALE, BASE_MAG, 0.1) as _
+        let angle = magnitude_scaling(BASE_ANGULAR_SCALE, BASE_MAG, magnitude, 0.1);
+        (distance * angle.min(MAX_ANGULAR_SCALE)) as _
     }
 
     pub fn blackbody_color(&self) -> Srgba {
@@ -219,10 +225,35 @@ impl Star {
     }
 
     pub fn material_emissive_color(&self) -> Srgba {
-        self.blackbody_color() * self.emissive_power()
+        self.material_emissive_color_at(self.relative_magnitude)
+    }
+
+    /// Emissive color of the star when it has the given magnitude.
+    pub fn material_emissive_color_at(&self, magnitude: f64) -> Srgba {
+        self.blackbody_color() * emissive_power(magnitude)
     }
 }
 
+fn magnitude_scaling(base_value: f64, base_magnitude: f64, magnitude: f64, bias: f64) -> f64 {
+    base_value * 10f64.powf(bias * (base_magnitude - magnitude))
+}
+
+fn emissive_power(magnitude: f64) -> f32 {
+    const BASE_VALUE: f64 = 120_000.0; // Arbitrary base value
+    const SOL_MAG: f64 = -26.7; // Absolute magnitude for the Sun
+
+    magnitude_scaling(BASE_VALUE, SOL_MAG, magnitude, 0.4) as _
+}
+
+/// How a star looks from the floating origin, updated every frame.
+#[derive(Debug, Copy, Clone, Component)]
+pub struct StarAppearance {
+    /// Apparent magnitude.
+    pub magnitude: f64,
+    /// Distance to the star, in meters.
+    pub distance: f64,
+}
+
 fn latlon_to_cartesian(lon: f64, lat: f64) -> DVec3 {
     let (y, r) = lat.sin_cos();
     let (x, z) = lon.sin_cos();
@@ -234,7 +265,7 @@ fn latlon_to_cartesian(lon: f64, lat: f64) -> DVec3 {
 /// Not accurate, but acceptable to distant stars.
 ///
 /// Taken from https://tannerhelland.com/2012/09/18/convert-temperature-rgb-algorithm-code.html
-fn blackbody_color(k: f32) -> Srgba {
+pub fn blackbody_color(k: f32) -> Srgba {
     let (r, g, b) = if k <= 6600.0 {
         // Calculate red
         let r = 255.0;
@@ -278,14 +309,40 @@ fn blackbody_color(k: f32) -> Srgba {
     Srgba::new(r, g, b, 1.0)
 }
 
+/// Where stars are put.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Reflect)]
+pub enum StarDistances {
+    /// On a sphere around the origin, as seen from the Sun, which is enough for a backdrop.
+    #[default]
+    Sky,
+    /// At their positions in the catalog, relative to the Sun at the origin, so that the sky
+    /// changes when travelling between stars. Named stars are reference frames which the camera
+    /// can be put in.
+    Real,
+}
+
+/// Star catalog scene root. It is a reference frame, either in its own big space, or within the
+/// big space of the camera for stars to be at their real positions.
 #[derive(Debug, Copy, Clone, Component, Reflect)]
 #[reflect(Component, opaque)]
-#[require(Transform, BigSpace, GridCell<Prec>)]
-pub struct StarryNight<Prec: GridPrecision>(PhantomData<Prec>);
+#[require(Transform, GridCell<Prec>, ReferenceFrame<Prec>)]
+pub struct StarryNight<Prec: GridPrecision> {
+    pub distances: StarDistances,
+    __prec: PhantomData<Prec>,
+}
 
 impl<Prec: GridPrecision> Default for StarryNight<Prec> {
     fn default() -> Self {
-        Self(PhantomData)
+        Self::new(StarDistances::default())
+    }
+}
+
+impl<Prec: GridPrecision> StarryNight<Prec> {
+    pub fn new(distances: StarDistances) -> Self {
+        Self {
+            distances,
+            __prec: PhantomData,
+        }
     }
 }
 
@@ -295,24 +352,58 @@ struct InsertedStar;
 fn spawn_stars<Prec: GridPrecision>(
     mut commands: Commands,
     q: Query<(Entity, &Parent, &Star), Without<InsertedStar>>,
-    q_parent: Query<&ReferenceFrame<Prec>>,
+    q_parent: Query<(&ReferenceFrame<Prec>, &StarryNight<Prec>)>,
 ) {
     for (entity, parent, star) in &q {
-        let Ok(frame) = q_parent.get(**parent) else {
+        let Ok((frame, starry_night)) = q_parent.get(**parent) else {
             continue;
         };
         trace!("Add star {star:?}");
 
-        let (cell, pos) = frame.translation_to_grid(star.position_meters());
+        let (position, distance) = match starry_night.distances {
+            StarDistances::Sky => (star.sky_position_meters(), SKY_DISTANCE),
+            StarDistances::Real => (star.position_meters(), star.distance_meters()),
+        };
+        let (cell, pos) = frame.translation_to_grid(position);
 
         let mut entity_commands = commands.entity(entity);
         entity_commands.insert((
-            Transform::from_translation(pos).with_scale(Vec3::splat(star.mesh_scale())),
+            Transform::from_translation(pos),
             cell,
+            StarAppearance {
+                magnitude: star.relative_magnitude,
+                distance,
+            },
             InsertedStar,
         ));
         if let Some(name) = star.name.clone() {
             entity_commands.insert(Name::new(name));
+            if starry_night.distances == StarDistances::Real {
+                entity_commands.insert(ReferenceFrame::<Prec>::default());
+            }
         }
     }
 }
+
+/// Updates the magnitude and distance of stars as seen from the floating origin. Stars on the sky
+/// sphere keep the magnitude they have from the Sun.
+fn update_star_appearances<Prec: GridPrecision>(
+    q_origin: Query<&GlobalTransform, With<FloatingOrigin>>,
+    q_roots: Query<&StarryNight<Prec>>,
+    mut q_stars: Query<(&Parent, &Star, &GlobalTransform, &mut StarAppearance)>,
+) {
+    let origin = q_origin
+        .get_single()
+        .map_or(Vec3::ZERO, GlobalTransform::translation);
+    for (parent, star, transform, mut appearance) in &mut q_stars {
+        let Ok(starry_night) = q_roots.get(parent.get()) else {
+            continue;
+        };
+        let distance = transform.translation().distance(origin) as f64;
+        appearance.distance = distance;
+        appearance.magnitude = match starry_night.distances {
+            StarDistances::Sky => star.relative_magnitude,
+            StarDistances::Real => star.apparent_magnitude_at(distance),
+        };
+    }
+}
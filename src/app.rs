@@ -11,7 +11,7 @@ use bevy::hierarchy::BuildChildren;
 use bevy::log::debug;
 use bevy::prelude::{
     default, Camera, Camera3d, ClearColor, Commands, Entity, EntityCommand, Msaa, OnAdd, Query,
-    Res, SceneRoot, TransformPlugin, Trigger, Window, WindowPlugin, World,
+    Res, Resource, SceneRoot, TransformPlugin, Trigger, Window, WindowPlugin, World,
 };
 use bevy::render::camera::Exposure;
 use bevy::window::WindowResolution;
@@ -22,18 +22,21 @@ use big_space::{FloatingOrigin, ReferenceFrame};
 use pan_orbit::components::{PanOrbitCamera, PanOrbitState};
 use pan_orbit::PanOrbitCameraPlugin;
 use postprocessing::lens_flares::{LensFlare, LensFlareTarget};
+use solar_system::depth::AdaptiveNearPlane;
 use solar_system::scene::components::{BigSpaceScene, SceneCamera};
 use solar_system::scene::{CameraConfig, SolarSystemSettings};
 use solar_system::sun::Sun;
-use starrynight::StarryNight;
+use starrynight::{StarDistances, StarryNight};
 
-type SolarSystemPrec = i32;
+/// Grid precision of the big space, wide enough for the distances between stars.
+pub(crate) type SolarSystemPrec = i64;
 type StarsPrec = SolarSystemPrec;
 
 #[derive(Default)]
 pub(crate) struct AppSettings {
     pub(crate) resolution: WindowResolution,
     pub(crate) with_inspector: bool,
+    pub(crate) scene: String,
 }
 
 pub fn get_app(settings: AppSettings) -> App {
@@ -63,6 +66,7 @@ pub fn get_app(settings: AppSettings) -> App {
         },
     ))
     .insert_resource(ClearColor(Color::BLACK))
+    .insert_resource(ScenePath(settings.scene))
     .add_systems(Startup, setup)
     .add_observer(on_add_scene_camera)
     .add_observer(on_add_sun)
@@ -75,24 +79,39 @@ fn debug_show_named_entities(trigger: Trigger<OnAdd, Name>, q: Query<&Name>) {
     debug!("{entity}: {name}", entity = trigger.entity());
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((
-        SceneRoot(asset_server.load("scenes/solar.system.yaml#Scene")),
-        SolarSystemSettings {
-            cell_length: 10_000.0,
-            switching_threshold: 100.0,
-        },
-        BigSpaceScene::<SolarSystemPrec>::default(),
-    ));
-    commands.spawn((
-        SceneRoot(asset_server.load("hyg_v38.csv#Scene")),
-        ReferenceFrame::<StarsPrec>::new(1e9, 100.0),
-        StarryNight::<StarsPrec>::default(),
-    ));
+/// Path of the solar system scene to load, relative to the assets folder.
+#[derive(Resource)]
+struct ScenePath(String);
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, scene: Res<ScenePath>) {
+    let solar_system = commands
+        .spawn((
+            SceneRoot(asset_server.load(format!("{}#Scene", scene.0))),
+            SolarSystemSettings {
+                cell_length: 10_000.0,
+                switching_threshold: 100.0,
+            },
+            BigSpaceScene::<SolarSystemPrec>::default(),
+        ))
+        .id();
+    // In the same big space as the camera, centered on the Sun, so that the camera can travel to
+    // other stars
+    commands
+        .spawn((
+            SceneRoot(asset_server.load("hyg_v38.csv#Scene")),
+            ReferenceFrame::<StarsPrec>::new(1e9, 100.0),
+            StarryNight::<StarsPrec>::new(StarDistances::Real),
+        ))
+        .set_parent(solar_system);
 }
 
-fn on_add_sun(trigger: Trigger<OnAdd, Sun>, mut commands: Commands) {
-    commands.entity(trigger.entity()).insert(LensFlareTarget);
+fn on_add_sun(trigger: Trigger<OnAdd, Sun>, q_suns: Query<&Sun>, mut commands: Commands) {
+    let Ok(sun) = q_suns.get(trigger.entity()) else {
+        return;
+    };
+    commands.entity(trigger.entity()).insert(LensFlareTarget {
+        luminosity: sun.luminosity,
+    });
 }
 
 fn on_add_scene_camera(trigger: Trigger<OnAdd, SceneCamera>, mut commands: Commands) {
@@ -117,6 +136,7 @@ fn on_add_scene_camera(trigger: Trigger<OnAdd, SceneCamera>, mut commands: Comma
                     ..default()
                 },
                 Exposure::SUNLIGHT,
+                AdaptiveNearPlane::default(),
                 Msaa::Sample4,
                 Bloom {
                     intensity: 0.05,
@@ -23,6 +23,9 @@ enum CliCommand {
         resolution: Option<Resolution>,
         #[clap(long, action = ArgAction::SetTrue)]
         inspector: bool,
+        /// Scene to load, relative to the assets folder.
+        #[clap(long, default_value = "scenes/solar.system.yaml")]
+        scene: String,
     },
 }
 
@@ -57,12 +60,14 @@ impl CliCommand {
             Self::Run {
                 resolution,
                 inspector,
+                scene,
             } => {
                 app::get_app(app::AppSettings {
                     resolution: resolution
                         .map(|r| WindowResolution::new(r.width, r.height))
                         .unwrap_or_default(),
                     with_inspector: inspector,
+                    scene,
                 })
                 .run();
             }
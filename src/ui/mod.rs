@@ -1,4 +1,4 @@
-use crate::app::Reparent;
+use crate::app::{Reparent, SolarSystemPrec};
 use crate::ui::planets::Planets;
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::ecs::system::SystemParam;
@@ -8,16 +8,19 @@ use bevy::render::diagnostic::RenderDiagnosticsPlugin;
 use bevy::window::PrimaryWindow;
 use bevy_blur_regions::{BlurRegionsCamera, BlurRegionsPlugin, EguiWindowBlurExt};
 use bevy_egui::{EguiContext, EguiPlugin};
+use big_space::ReferenceFrame;
 use chrono::{DateTime, Datelike, Days, Months, TimeDelta, Timelike, Utc};
 use egui::panel::TopBottomSide;
 use egui::{containers, emath, widgets, Align, FontId, Ui};
 use egui_plot::{PlotPoint, PlotPoints};
-use solar_system::body::PlanetaryBody;
+use pan_orbit::components::PanOrbitState;
+use solar_system::body::{BodyShape, PlanetaryBody};
+use solar_system::clock::{ClockPreset, SimulationClock};
 use solar_system::mjd::Mjd;
 use solar_system::orbit::DrawOrbits;
 use solar_system::scene::components::SceneCamera;
-use solar_system::scene::distance::{Distance, DistanceUnit};
-use starrynight::Star;
+use solar_system::scene::distance::Distance;
+use starrynight::{Star, StarAppearance};
 use std::ops;
 
 mod inspector;
@@ -56,6 +59,10 @@ struct UiState {
     topbar_expanded: bool,
     date_window_opened: bool,
     show_labels: bool,
+    step_preset: usize,
+    /// Text of the camera distance field, refreshed from the camera unless it is being edited.
+    camera_distance: String,
+    camera_distance_error: Option<String>,
 }
 
 impl Default for UiState {
@@ -64,6 +71,9 @@ impl Default for UiState {
             topbar_expanded: false,
             date_window_opened: false,
             show_labels: true,
+            step_preset: 3,
+            camera_distance: String::new(),
+            camera_distance_error: None,
         }
     }
 }
@@ -75,15 +85,48 @@ struct UiSystems<'w, 's> {
     diagnostics: Res<'w, DiagnosticsStore>,
     draw_orbits: ResMut<'w, DrawOrbits>,
     mjd: ResMut<'w, Time<Mjd>>,
-    time: ResMut<'w, Time<Virtual>>,
+    clock: ResMut<'w, SimulationClock>,
     q_camera_blur: Query<'w, 's, &'static mut BlurRegionsCamera<20>>,
     q_camera_entity: Query<'w, 's, Entity, With<SceneCamera>>,
     q_camera_parent: Query<'w, 's, &'static Parent, With<SceneCamera>>,
+    q_camera_state: Query<'w, 's, &'static mut PanOrbitState, With<SceneCamera>>,
     q_camera_transform:
         Query<'w, 's, (&'static GlobalTransform, &'static Camera), With<SceneCamera>>,
-    q_planetary_bodies:
-        Query<'w, 's, (&'static GlobalTransform, Option<&'static Name>), With<PlanetaryBody>>,
-    q_stars: Query<'w, 's, (&'static GlobalTransform, &'static Star, &'static Name)>,
+    q_planetary_bodies: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            Option<&'static Name>,
+            Option<&'static Children>,
+        ),
+        With<PlanetaryBody>,
+    >,
+    q_body_shapes: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            &'static BodyShape,
+            &'static Parent,
+        ),
+    >,
+    q_stars: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            &'static Star,
+            &'static StarAppearance,
+            &'static Name,
+        ),
+    >,
+    q_star_frames: Query<
+        'w,
+        's,
+        (Entity, &'static Star, &'static Name),
+        With<ReferenceFrame<SolarSystemPrec>>,
+    >,
     commands: Commands<'w, 's>,
 }
 
@@ -122,6 +165,7 @@ impl UiSystems<'_, '_> {
     fn topbar_ui(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             self.parent_selector(ui);
+            self.camera_distance(ui);
             ui.separator();
             self.current_date(ui);
             ui.with_layout(egui::Layout::right_to_left(Align::Max), |ui| {
@@ -147,7 +191,8 @@ impl UiSystems<'_, '_> {
                     .get_planets()
                     .map(|(e, s)| (e, s.to_string()))
                     .collect::<Vec<_>>();
-                for (e, s) in planets {
+                let stars = self.get_nearby_stars();
+                for (e, s) in planets.into_iter().chain(stars) {
                     if ui.button(s).clicked() {
                         if let Ok(entity) = self.q_camera_entity.get_single() {
                             self.commands.entity(entity).queue(Reparent(e));
@@ -157,39 +202,129 @@ impl UiSystems<'_, '_> {
             });
     }
 
+    /// Distance of the camera from the center of the current planet, which can be typed with units,
+    /// such as `2000 km` or `0.1 AU`.
+    fn camera_distance(&mut self, ui: &mut Ui) {
+        let Ok(mut state) = self.q_camera_state.get_single_mut() else {
+            return;
+        };
+        let id = ui.label("Distance").id;
+        let response = ui
+            .add(widgets::TextEdit::singleline(&mut self.state.camera_distance).desired_width(80.0))
+            .labelled_by(id);
+        if response.lost_focus() {
+            match self.state.camera_distance.parse::<Distance>() {
+                Ok(distance) if distance.to_base_value() > 0.0 => {
+                    state.radius = distance.to_base_value() as f32;
+                    self.state.camera_distance_error = None;
+                }
+                Ok(_) => {
+                    self.state.camera_distance_error = Some("Distance must be positive".into());
+                }
+                Err(err) => self.state.camera_distance_error = Some(err.to_string()),
+            }
+        }
+        if !response.has_focus() {
+            self.state.camera_distance = format!("{:.1}", Distance::from(state.radius as f64));
+        }
+        if let Some(error) = &self.state.camera_distance_error {
+            ui.colored_label(egui::Color32::LIGHT_RED, "⚠")
+                .on_hover_text(error);
+        }
+    }
+
     fn current_date(&mut self, ui: &mut Ui) {
-        let r1 = ui.label(
-            self.mjd
-                .context()
-                .format("%Y-%m-%d %H:%M:%S UTC")
-                .to_string(),
-        );
+        let r1 = ui.label(format_utc(self.mjd.context()));
         let r2 = ui.label(self.mjd.context().to_string());
 
         if r1.double_clicked() || r2.double_clicked() {
             self.state.date_window_opened = true;
         }
+        self.live_indicator(ui);
+        if ui
+            .button("Now")
+            .on_hover_text("Follow the system clock")
+            .clicked()
+        {
+            self.clock.go_live(0.0);
+        }
+    }
+
+    fn live_indicator(&mut self, ui: &mut Ui) {
+        if self.clock.is_live() {
+            ui.colored_label(egui::Color32::LIGHT_GREEN, "● LIVE")
+                .on_hover_text("Locked to the system clock");
+        } else {
+            ui.colored_label(egui::Color32::GRAY, "○ Live off")
+                .on_hover_text("Simulated time, press \"Now\" to follow the system clock");
+        }
     }
 
     fn speed_controls(&mut self, ui: &mut Ui) {
-        let mut cur_speed = self.time.relative_speed();
         ui.with_layout(egui::Layout::left_to_right(Align::Max), |ui| {
+            let step = ClockPreset::ALL[self.state.step_preset];
+            if ui
+                .button("⏮")
+                .on_hover_text(format!("Step back {}", step.label))
+                .clicked()
+            {
+                self.clock.step(-step.seconds);
+            }
+            let pause_label = if self.clock.is_paused() { "▶" } else { "⏸" };
+            if ui.button(pause_label).clicked() {
+                self.clock.toggle_pause();
+            }
+            if ui
+                .button("⏭")
+                .on_hover_text(format!("Step forward {}", step.label))
+                .clicked()
+            {
+                self.clock.step(step.seconds);
+            }
+            containers::ComboBox::from_id_salt("clock_step")
+                .selected_text(step.label)
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for (i, preset) in ClockPreset::ALL.iter().enumerate() {
+                        ui.selectable_value(&mut self.state.step_preset, i, preset.label);
+                    }
+                });
+            ui.separator();
+
+            if ui
+                .selectable_label(self.clock.is_reversed(), "Reverse")
+                .clicked()
+            {
+                self.clock.reverse();
+            }
+            let mut cur_speed = self.clock.rate();
             if ui.button("-").clicked() {
-                self.time.set_relative_speed(cur_speed / 10.);
+                self.clock.set_rate(cur_speed / 10.);
             }
             if ui
                 .add(
                     widgets::DragValue::new(&mut cur_speed)
                         .suffix("x")
-                        .range(0.1..=1000.0),
+                        .speed(cur_speed.abs().max(1.0) * 1e-2)
+                        .range(-SimulationClock::MAX_RATE..=SimulationClock::MAX_RATE),
                 )
                 .changed()
             {
-                self.time.set_relative_speed(cur_speed);
+                self.clock.set_rate(cur_speed);
             };
             if ui.button("+").clicked() {
-                self.time.set_relative_speed(cur_speed * 10.0);
+                self.clock.set_rate(cur_speed * 10.0);
             }
+            containers::ComboBox::from_id_salt("clock_rate")
+                .selected_text("Presets")
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for preset in ClockPreset::ALL {
+                        if ui.button(format!("{}/s", preset.label)).clicked() {
+                            self.clock.set_preset(preset);
+                        }
+                    }
+                });
         });
     }
 
@@ -260,11 +395,46 @@ impl UiSystems<'_, '_> {
             .show_with_blur(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Current date: ");
-                    ui.label(self.mjd.context().format("%Y-%m-%d %H:%M:%S").to_string());
+                    ui.label(format_utc(self.mjd.context()));
+                    self.live_indicator(ui);
                 });
-                if let Some(new_datetime) = datetime_edit_widget(ui, **self.mjd.context()) {
+                if let Some(offset) = self.clock.live_offset() {
+                    ui.horizontal(|ui| {
+                        let mut hours = offset / 3600.0;
+                        ui.label("Offset from system time: ");
+                        if ui
+                            .add(widgets::DragValue::new(&mut hours).suffix(" h").speed(0.1))
+                            .changed()
+                        {
+                            self.clock.go_live(hours * 3600.0);
+                        }
+                    });
+                }
+                if let Some(new_datetime) = self
+                    .mjd
+                    .context()
+                    .to_utc()
+                    .and_then(|datetime| datetime_edit_widget(ui, datetime))
+                {
+                    self.clock.leave_live();
                     self.mjd.context_mut().set_from_datetime(new_datetime);
                 }
+                ui.horizontal(|ui| {
+                    let mut mjd = self.mjd.context().mjd();
+                    ui.label("MJD (TT): ");
+                    if ui
+                        .add(
+                            widgets::DragValue::new(&mut mjd)
+                                .speed(1.0)
+                                .max_decimals(6)
+                                .update_while_editing(false),
+                        )
+                        .changed()
+                    {
+                        self.clock.leave_live();
+                        self.mjd.context_mut().set_from_mjd(mjd);
+                    }
+                });
 
                 ui.with_layout(egui::Layout::left_to_right(Align::Max), |ui| {
                     if ui.button("Close").clicked() {
@@ -283,10 +453,17 @@ impl UiSystems<'_, '_> {
                     return;
                 };
 
-                if ui
-                    .allocate_rect(ui.clip_rect(), egui::Sense::click())
-                    .double_clicked()
-                {
+                // Double-clicking a body moves the camera to it, anywhere else recenters it
+                let response = ui.allocate_rect(ui.clip_rect(), egui::Sense::click());
+                if response.double_clicked() {
+                    let zoom_factor = ui.ctx().zoom_factor();
+                    let picked = response.interact_pointer_pos().and_then(|position| {
+                        let position = vec2(position.x, position.y) * zoom_factor;
+                        self.pick_body(cam_transform, camera, position)
+                    });
+                    if let (Some(body), Ok(entity)) = (picked, self.q_camera_entity.get_single()) {
+                        self.commands.entity(entity).queue(Reparent(body));
+                    }
                     self.commands.trigger(pan_orbit::events::RecenterCamera);
                 }
 
@@ -295,13 +472,22 @@ impl UiSystems<'_, '_> {
                 }
 
                 let painter = ui.painter();
-                for (planet_transform, name) in &self.q_planetary_bodies {
+                for (planet_transform, name, children) in &self.q_planetary_bodies {
                     const CIRCLE_SIZE: f32 = 5.0;
                     const TEXT_POS: f32 = CIRCLE_SIZE + 3.0;
 
-                    let distance = planet_transform
-                        .translation()
-                        .distance(cam_transform.translation());
+                    // Distance to the surface, when the shape of the body is known
+                    let camera_position = cam_transform.translation();
+                    let distance = children
+                        .iter()
+                        .flat_map(|children| children.iter())
+                        .find_map(|child| self.q_body_shapes.get(*child).ok())
+                        .map(|(transform, shape, _)| {
+                            shape.distance_to_surface(transform, camera_position)
+                        })
+                        .unwrap_or_else(|| {
+                            planet_transform.translation().distance(camera_position)
+                        });
                     let distance = Distance::from(distance as f64);
                     let name = name
                         .map(|name| name.to_string())
@@ -323,7 +509,7 @@ impl UiSystems<'_, '_> {
                     );
                 }
 
-                for (transform, star, name) in &self.q_stars {
+                for (transform, star, appearance, name) in &self.q_stars {
                     const CIRCLE_SIZE: f32 = 3.0;
                     const TEXT_POS: f32 = CIRCLE_SIZE + 3.0;
 
@@ -332,14 +518,10 @@ impl UiSystems<'_, '_> {
                     else {
                         continue;
                     };
-                    if star.relative_magnitude > 3.0 {
+                    if appearance.magnitude > 3.0 {
                         continue;
                     }
-                    let distance = Distance {
-                        value: star.distance_parsecs,
-                        unit: DistanceUnit::Parsecs,
-                    }
-                    .renormalize();
+                    let distance = Distance::from(appearance.distance);
                     let text = format!("{name}\n{distance:.1}");
                     let center = egui::pos2(viewport.x, viewport.y);
                     let color = {
@@ -358,20 +540,62 @@ impl UiSystems<'_, '_> {
             });
     }
 
+    /// Planet whose body is under the given viewport position, hit along its actual shape.
+    fn pick_body(
+        &self,
+        cam_transform: &GlobalTransform,
+        camera: &Camera,
+        position: Vec2,
+    ) -> Option<Entity> {
+        let ray = camera.viewport_to_world(cam_transform, position).ok()?;
+        self.q_body_shapes
+            .iter()
+            .filter_map(|(transform, shape, parent)| {
+                Some((shape.ray_intersection(transform, ray)?, parent.get()))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, planet)| planet)
+    }
+
     fn get_planets(&self) -> impl Iterator<Item = (Entity, &str)> {
         self.planets.iter()
     }
 
+    /// Named stars which the camera can travel to, closest to the Sun first.
+    fn get_nearby_stars(&self) -> Vec<(Entity, String)> {
+        const MAX_DISTANCE_PARSECS: f64 = 5.0;
+        let mut stars = self
+            .q_star_frames
+            .iter()
+            .filter(|(_, star, _)| star.distance_parsecs <= MAX_DISTANCE_PARSECS)
+            .map(|(entity, star, name)| (star.distance_parsecs, entity, name.to_string()))
+            .collect::<Vec<_>>();
+        stars.sort_by(|a, b| a.0.total_cmp(&b.0));
+        stars
+            .into_iter()
+            .map(|(_, entity, name)| (entity, name))
+            .collect()
+    }
+
     fn get_planet(&self) -> Option<String> {
         for parent in &self.q_camera_parent {
             if let Some(name) = self.planets.get_name(**parent) {
                 return Some(name.to_string());
             }
+            if let Ok((_, _, name)) = self.q_star_frames.get(**parent) {
+                return Some(name.to_string());
+            }
         }
         None
     }
 }
 
+fn format_utc(mjd: &Mjd) -> String {
+    mjd.to_utc()
+        .map(|datetime| datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| String::from("Out of calendar range"))
+}
+
 fn datetime_edit_widget(ui: &mut Ui, input: DateTime<Utc>) -> Option<DateTime<Utc>> {
     let mut ret = None;
     ui.columns_const(|[left, right]| {